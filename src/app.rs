@@ -1,24 +1,36 @@
 //! Core application state and log processing logic.
 //!
 //! This module contains the main `AppState` struct that manages:
-//! - Log line storage in a ring buffer
+//! - Log line storage in a ring buffer (or a whole file in browse mode)
 //! - Filtering and search functionality
 //! - Bookmarks and navigation state
 //! - UI mode and panel focus
 
-use crate::config::{Config, FILTER_DEBOUNCE_MS};
+use crate::browse::{FileBrowser, INDEX_BYTES_PER_STEP, IndexUpdate};
+use crate::config::{
+    BROWSE_SCAN_LINES_PER_TICK, COLUMN_SCAN_LINES, Config, EVENT_IDLE_MS, FILTER_DEBOUNCE_MS,
+    FilterConfig, HighlightConfig,
+};
 use crate::discovery::DiscoveredSource;
-use crate::filter::{ActiveFilter, MatchRange, SavedFilter};
+use crate::filter::{self, ActiveFilter, FilterChip, MatchRange, SavedFilter};
+use crate::health::{SourceHealth, SourceStatus};
+use crate::highlight::{self, HIGHLIGHT_COLORS, HighlightRule};
+use crate::merge::ReorderBuffer;
+use crate::multiline::EventGrouper;
+use crate::parse::{self, Fields, ParserSet};
+use crate::session::Session;
 use crate::sources::LogSourceType;
+use crate::sources::k8s::Scope;
 use crate::theme::Theme;
 use arboard::{Clipboard, Error as ClipboardError};
 use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Style};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fs::File;
 use std::io::Write;
-use std::time::Instant;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tui_textarea::TextArea;
 
 /// Common timestamp formats to try parsing
@@ -35,7 +47,7 @@ const TIMESTAMP_FORMATS: &[&str] = &[
 ];
 
 /// Try to parse a timestamp from the beginning of a line
-fn parse_timestamp(line: &str) -> Option<DateTime<Local>> {
+pub fn parse_timestamp(line: &str) -> Option<DateTime<Local>> {
     // Extract the first ~35 characters which should contain any timestamp
     let prefix: String = line.chars().take(35).collect();
 
@@ -121,6 +133,43 @@ impl LogLevel {
             LogLevel::None
         }
     }
+
+    /// Parse a level name as used in structured logs ("warn", "ERROR", "fatal")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "error" | "err" | "fatal" | "critical" | "crit" | "panic" | "alert" | "emerg" | "e"
+            | "f" => Some(LogLevel::Error),
+            "warn" | "warning" | "w" => Some(LogLevel::Warn),
+            "info" | "information" | "notice" | "i" => Some(LogLevel::Info),
+            "debug" | "dbg" | "d" => Some(LogLevel::Debug),
+            "trace" | "verbose" | "t" | "v" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name ("warn"); empty for `None`
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+            LogLevel::None => "",
+        }
+    }
+
+    /// Rank for `level>=warn` style comparisons (higher is more severe)
+    pub fn severity(self) -> u8 {
+        match self {
+            LogLevel::None => 0,
+            LogLevel::Trace => 1,
+            LogLevel::Debug => 2,
+            LogLevel::Info => 3,
+            LogLevel::Warn => 4,
+            LogLevel::Error => 5,
+        }
+    }
 }
 
 /// A single log line with optional cached rendering
@@ -135,23 +184,51 @@ pub struct LogLine {
     pub timestamp: Option<DateTime<Local>>,
     /// Whether this line is valid JSON
     pub is_json: bool,
+    /// Whether this line is logfmt (`key=value key="quoted value"`)
+    pub is_logfmt: bool,
     /// Index of the source this line came from
     pub source_id: usize,
+    /// Structured fields (JSON object or logfmt lines; empty otherwise)
+    pub fields: Fields,
 }
 
 impl LogLine {
     pub fn new(raw: String) -> Self {
-        let level = LogLevel::detect(&raw);
         let has_ansi = raw.contains('\x1b');
-        let timestamp = parse_timestamp(&raw);
         let is_json = Self::detect_json(&raw);
+        let logfmt = if is_json || has_ansi {
+            None
+        } else {
+            parse::parse_logfmt_fields(&raw)
+        };
+        let is_logfmt = logfmt.is_some();
+        let fields = if is_json {
+            parse::parse_json_fields(&raw).unwrap_or_default()
+        } else {
+            logfmt.unwrap_or_default()
+        };
+
+        // Structured lines say what they are; don't guess from key names
+        let (level, timestamp) = if fields.is_empty() {
+            (LogLevel::detect(&raw), parse_timestamp(&raw))
+        } else {
+            let level = parse::level_from_fields(&fields).unwrap_or_else(|| {
+                parse::message_from_fields(&fields)
+                    .map(LogLevel::detect)
+                    .unwrap_or(LogLevel::None)
+            });
+            (level, parse::timestamp_from_fields(&fields))
+        };
+
         Self {
             raw,
             level,
             has_ansi,
             timestamp,
             is_json,
+            is_logfmt,
             source_id: 0,
+            fields,
         }
     }
 
@@ -161,6 +238,18 @@ impl LogLine {
         self
     }
 
+    /// Override the detected level (for sources that report it explicitly)
+    pub fn with_level(mut self, level: LogLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Override the parsed timestamp (for sources that report it explicitly)
+    pub fn with_timestamp(mut self, timestamp: DateTime<Local>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
     /// Detect if a line is JSON
     fn detect_json(line: &str) -> bool {
         let trimmed = line.trim();
@@ -206,6 +295,7 @@ pub enum FocusedPanel {
     LogView,
     Sources,
     Filters,
+    Highlights,
 }
 
 /// View mode for multi-source display
@@ -225,6 +315,29 @@ pub enum PickerMode {
     K8s,
 }
 
+/// What the K8s picker's scope chooser lists
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScopeKind {
+    Context,
+    Namespace,
+}
+
+/// List of kubeconfig contexts or namespaces shown over the pod list
+#[derive(Debug)]
+pub struct ScopeChooser {
+    pub kind: ScopeKind,
+    /// Choices; `None` stands for all namespaces
+    pub choices: Vec<Option<String>>,
+    /// The choice in effect, marked in the list
+    pub current: Option<usize>,
+    /// Currently selected index
+    pub selected: usize,
+    /// Waiting for kubectl
+    pub loading: bool,
+    /// Error message if listing failed
+    pub error: Option<String>,
+}
+
 /// State for the container/pod picker overlay
 #[derive(Debug)]
 pub struct PickerState {
@@ -244,6 +357,13 @@ pub struct PickerState {
     pub loading: bool,
     /// Error message if discovery failed
     pub error: Option<String>,
+    /// kubeconfig context pods are listed from (None = the current one);
+    /// kept between openings
+    pub k8s_context: Option<String>,
+    /// Namespace pods are listed from (None = all namespaces)
+    pub k8s_namespace: Option<String>,
+    /// Context or namespace chooser, while open
+    pub scope_chooser: Option<ScopeChooser>,
 }
 
 impl Default for PickerState {
@@ -257,6 +377,9 @@ impl Default for PickerState {
             initial_checked: Vec::new(),
             loading: false,
             error: None,
+            k8s_context: None,
+            k8s_namespace: None,
+            scope_chooser: None,
         }
     }
 }
@@ -272,12 +395,88 @@ impl PickerState {
         self.initial_checked.clear();
         self.loading = true;
         self.error = None;
+        self.scope_chooser = None;
     }
 
     /// Close the picker
     pub fn close(&mut self) {
         self.visible = false;
         self.loading = false;
+        self.scope_chooser = None;
+    }
+
+    /// Where the K8s picker lists pods
+    pub fn k8s_scope(&self) -> Scope {
+        Scope {
+            context: self.k8s_context.clone(),
+            namespace: self.k8s_namespace.clone(),
+        }
+    }
+
+    /// Open the context or namespace chooser (once the pod list has loaded)
+    pub fn open_scope_chooser(&mut self, kind: ScopeKind) {
+        if self.mode != PickerMode::K8s || self.loading {
+            return;
+        }
+        self.scope_chooser = Some(ScopeChooser {
+            kind,
+            choices: Vec::new(),
+            current: None,
+            selected: 0,
+            loading: true,
+            error: None,
+        });
+    }
+
+    /// Fill the open chooser with listed names. `active` is the kubeconfig's
+    /// current context, which applies while none has been chosen.
+    pub fn set_scope_choices(&mut self, result: Result<(Vec<String>, Option<String>), String>) {
+        let (chosen_context, chosen_namespace) = (&self.k8s_context, &self.k8s_namespace);
+        let Some(chooser) = &mut self.scope_chooser else {
+            return;
+        };
+        chooser.loading = false;
+        let (names, active) = match result {
+            Ok(listing) => listing,
+            Err(e) => {
+                chooser.error = Some(e);
+                return;
+            }
+        };
+        let (choices, current): (Vec<Option<String>>, Option<&String>) = match chooser.kind {
+            ScopeKind::Context => (
+                names.into_iter().map(Some).collect(),
+                chosen_context.as_ref().or(active.as_ref()),
+            ),
+            ScopeKind::Namespace => (
+                std::iter::once(None)
+                    .chain(names.into_iter().map(Some))
+                    .collect(),
+                chosen_namespace.as_ref(),
+            ),
+        };
+        chooser.current = choices.iter().position(|c| c.as_ref() == current);
+        chooser.selected = chooser.current.unwrap_or(0);
+        chooser.choices = choices;
+    }
+
+    /// Apply the selected context or namespace and list pods again.
+    /// Choosing a context starts over with all namespaces.
+    pub fn choose_scope(&mut self) {
+        let Some(chooser) = self.scope_chooser.take() else {
+            return;
+        };
+        let Some(choice) = chooser.choices.get(chooser.selected).cloned() else {
+            return;
+        };
+        match chooser.kind {
+            ScopeKind::Context => {
+                self.k8s_context = choice;
+                self.k8s_namespace = None;
+            }
+            ScopeKind::Namespace => self.k8s_namespace = choice,
+        }
+        self.open(PickerMode::K8s);
     }
 
     /// Set discovered sources, marking any that match existing sources as already checked
@@ -296,8 +495,18 @@ impl PickerState {
                         (LogSourceType::Docker { container }, PickerMode::Docker) => {
                             container == &discovered.name
                         }
-                        (LogSourceType::K8s { pod, namespace, .. }, PickerMode::K8s) => {
-                            pod == &discovered.name && *namespace == discovered.namespace
+                        (
+                            LogSourceType::K8s {
+                                pod,
+                                context,
+                                namespace,
+                                ..
+                            },
+                            PickerMode::K8s,
+                        ) => {
+                            pod == &discovered.name
+                                && *namespace == discovered.namespace
+                                && *context == discovered.context
                         }
                         _ => false,
                     })
@@ -332,14 +541,20 @@ impl PickerState {
 
     /// Navigate up
     pub fn up(&mut self) {
-        if !self.sources.is_empty() && self.selected > 0 {
+        if let Some(chooser) = &mut self.scope_chooser {
+            chooser.selected = chooser.selected.saturating_sub(1);
+        } else if !self.sources.is_empty() && self.selected > 0 {
             self.selected -= 1;
         }
     }
 
     /// Navigate down
     pub fn down(&mut self) {
-        if !self.sources.is_empty() && self.selected < self.sources.len() - 1 {
+        if let Some(chooser) = &mut self.scope_chooser {
+            if chooser.selected + 1 < chooser.choices.len() {
+                chooser.selected += 1;
+            }
+        } else if !self.sources.is_empty() && self.selected < self.sources.len() - 1 {
             self.selected += 1;
         }
     }
@@ -391,6 +606,70 @@ impl SettingsState {
     }
 }
 
+/// State for the table column picker overlay
+#[derive(Debug, Default)]
+pub struct ColumnPickerState {
+    /// Whether the column picker is visible
+    pub visible: bool,
+    /// Field names seen in recent lines, in first-seen order
+    pub keys: Vec<String>,
+    /// Currently highlighted key index
+    pub selected: usize,
+    /// Chosen columns, in display order
+    pub chosen: Vec<String>,
+}
+
+impl ColumnPickerState {
+    /// Open the picker with the available keys and the current columns
+    pub fn open(&mut self, keys: Vec<String>, chosen: Vec<String>) {
+        self.visible = true;
+        self.keys = keys;
+        self.selected = 0;
+        self.chosen = chosen;
+    }
+
+    /// Close the picker
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    /// Navigate up
+    pub fn up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    /// Navigate down
+    pub fn down(&mut self) {
+        if !self.keys.is_empty() && self.selected < self.keys.len() - 1 {
+            self.selected += 1;
+        }
+    }
+
+    /// Add the highlighted key as the last column, or remove it
+    pub fn toggle_selected(&mut self) {
+        let Some(key) = self.keys.get(self.selected) else {
+            return;
+        };
+        match self.chosen.iter().position(|c| c == key) {
+            Some(pos) => {
+                self.chosen.remove(pos);
+            }
+            None => self.chosen.push(key.clone()),
+        }
+    }
+}
+
+/// How a line came to be listed in a pane's `filtered_indices`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RowInfo {
+    /// Shown as context around a match rather than matching itself
+    pub context: bool,
+    /// Lines were left out between this row and the one above it
+    pub after_gap: bool,
+}
+
 /// State for a single log view pane
 pub struct PaneState<'a> {
     // Scrolling
@@ -404,14 +683,26 @@ pub struct PaneState<'a> {
     pub viewport_height: usize,
 
     // Filtering
-    /// Indices into lines that match the current filter
+    /// Indices into lines that match the current filter (plus context lines)
     pub filtered_indices: Vec<usize>,
+    /// Parallel to `filtered_indices`: context and group boundaries
+    pub row_info: Vec<RowInfo>,
     /// Currently active filter
     pub active_filter: Option<ActiveFilter>,
+    /// Pinned include/exclude filters, applied on top of the active one
+    pub filter_stack: Vec<FilterChip>,
     /// Filter text input widget
     pub filter_textarea: TextArea<'a>,
     /// Whether filter is regex mode
     pub filter_is_regex: bool,
+    /// Hide lines below this level (None = show every line)
+    pub min_level: Option<LogLevel>,
+    /// Lines shown before each match
+    pub context_before: usize,
+    /// Lines shown after each match
+    pub context_after: usize,
+    /// After-context lines still owed to the last match
+    context_pending: usize,
     /// Last time filter input changed (for debounce)
     pub filter_last_change: Option<Instant>,
     /// Whether we need to recompute filter (after debounce)
@@ -429,9 +720,21 @@ pub struct PaneState<'a> {
     /// Bookmarked line indices (into the lines buffer)
     pub bookmarks: Vec<usize>,
 
+    // Folding of multi-line events (per-pane)
+    /// Whether events show all their lines unless folded one by one
+    pub unfold_all: bool,
+    /// Events (line indices) folded or unfolded against `unfold_all`
+    pub fold_toggled: Vec<usize>,
+
     // Line selection (for yank/click)
     /// Currently selected line index (into filtered_indices), if any
     pub selected_line: Option<usize>,
+    /// Whether the selected line's remaining fields are shown (table mode)
+    pub detail_expanded: bool,
+
+    // Browse mode
+    /// Next file line to test against the filter (browse mode only)
+    pub scan_pos: usize,
 }
 
 impl<'a> PaneState<'a> {
@@ -448,16 +751,26 @@ impl<'a> PaneState<'a> {
             horizontal_scroll: 0,
             viewport_height: 20,
             filtered_indices: Vec::new(),
+            row_info: Vec::new(),
             active_filter: None,
+            filter_stack: Vec::new(),
             filter_textarea: textarea,
             filter_is_regex: false,
+            min_level: None,
+            context_before: 0,
+            context_after: 0,
+            context_pending: 0,
             filter_last_change: None,
             filter_needs_recompute: false,
             filter_history_idx: None,
             visible_sources: vec![true; num_sources],
             view_mode: SourceViewMode::default(),
             bookmarks: Vec::new(),
+            unfold_all: false,
+            fold_toggled: Vec::new(),
             selected_line: None,
+            detail_expanded: false,
+            scan_pos: 0,
         }
     }
 
@@ -479,17 +792,223 @@ impl<'a> PaneState<'a> {
             horizontal_scroll: self.horizontal_scroll,
             viewport_height: self.viewport_height,
             filtered_indices: self.filtered_indices.clone(),
+            row_info: self.row_info.clone(),
             active_filter: self.active_filter.clone(),
+            filter_stack: self.filter_stack.clone(),
             filter_textarea: textarea,
             filter_is_regex: self.filter_is_regex,
+            min_level: self.min_level,
+            context_before: self.context_before,
+            context_after: self.context_after,
+            context_pending: self.context_pending,
             filter_last_change: None,
             filter_needs_recompute: false,
             filter_history_idx: None,
             visible_sources: self.visible_sources.clone(),
             view_mode: self.view_mode,
             bookmarks: self.bookmarks.clone(),
+            unfold_all: self.unfold_all,
+            fold_toggled: self.fold_toggled.clone(),
             selected_line: None, // Don't copy selection to new pane
+            detail_expanded: false,
+            scan_pos: self.scan_pos,
+        }
+    }
+
+    /// Check if a line passes this pane's source visibility and filter
+    pub fn matches_line(&self, line: &LogLine, sources: &[LogSourceType]) -> bool {
+        self.shows_source(line) && self.matches_content(line, sources)
+    }
+
+    /// Check source visibility and view mode
+    fn shows_source(&self, line: &LogLine) -> bool {
+        if !self
+            .visible_sources
+            .get(line.source_id)
+            .copied()
+            .unwrap_or(true)
+        {
+            return false;
+        }
+        match self.view_mode {
+            SourceViewMode::AllMerged => true,
+            SourceViewMode::SingleSource(id) => id == line.source_id,
+        }
+    }
+
+    /// Check the level threshold and filters
+    fn matches_content(&self, line: &LogLine, sources: &[LogSourceType]) -> bool {
+        // Check level threshold (lines without a level are hidden too)
+        if let Some(min) = self.min_level {
+            if line.level.severity() < min.severity() {
+                return false;
+            }
+        }
+
+        // Check text filter, then the pinned filters
+        if let Some(filter) = &self.active_filter {
+            if !filter.matches_line(line, sources) {
+                return false;
+            }
+        }
+        self.filter_stack
+            .iter()
+            .all(|chip| chip.admits(line, sources))
+    }
+
+    /// Whether matching depends on line content (and not only on its source)
+    fn filters_line_content(&self) -> bool {
+        self.active_filter.is_some()
+            || self.min_level.is_some()
+            || self.filter_stack.iter().any(|chip| chip.enabled)
+    }
+
+    /// Whether matches are shown with surrounding lines
+    pub fn has_context(&self) -> bool {
+        self.context_before > 0 || self.context_after > 0
+    }
+
+    /// Whether the event at line `index` shows all its lines
+    pub fn is_unfolded(&self, index: usize) -> bool {
+        self.unfold_all != self.fold_toggled.contains(&index)
+    }
+
+    /// Screen rows taken by the row at `pos`: its line, or every line of an
+    /// unfolded event (`event_lines` gives the line count of a line index)
+    fn row_height(&self, pos: usize, event_lines: &impl Fn(usize) -> usize) -> usize {
+        match self.filtered_indices.get(pos) {
+            Some(&index) if self.is_unfolded(index) => event_lines(index).max(1),
+            _ => 1,
+        }
+    }
+
+    /// Row details for a position in `filtered_indices`
+    pub fn row(&self, pos: usize) -> RowInfo {
+        self.row_info.get(pos).copied().unwrap_or_default()
+    }
+
+    fn clear_rows(&mut self) {
+        self.filtered_indices.clear();
+        self.row_info.clear();
+        self.context_pending = 0;
+    }
+
+    /// Lines that could be shown before a match at `index`: lines passing
+    /// `visible` after the last listed row, nearest first. One more than
+    /// `context_before` is returned so a gap can be told apart.
+    fn context_candidates(&self, index: usize, visible: impl Fn(usize) -> bool) -> Vec<usize> {
+        if !self.has_context() {
+            return Vec::new();
+        }
+        let floor = self.filtered_indices.last().map_or(0, |&last| last + 1);
+        (floor..index)
+            .rev()
+            .filter(|&i| visible(i))
+            .take(self.context_before + 1)
+            .collect()
+    }
+
+    /// List a line that passed source visibility. A match brings in the
+    /// `earlier` lines (see `context_candidates`) as context, and the
+    /// `context_after` lines following it are listed as context too.
+    fn push_row(&mut self, index: usize, matched: bool, earlier: &[usize]) {
+        if matched {
+            let shown = earlier.len().min(self.context_before);
+            let after_gap = earlier.len() > shown && !self.filtered_indices.is_empty();
+            for (n, &i) in earlier[..shown].iter().rev().enumerate() {
+                self.filtered_indices.push(i);
+                self.row_info.push(RowInfo {
+                    context: true,
+                    after_gap: after_gap && n == 0,
+                });
+            }
+            self.filtered_indices.push(index);
+            self.row_info.push(RowInfo {
+                context: false,
+                after_gap: after_gap && shown == 0,
+            });
+            self.context_pending = self.context_after;
+        } else if self.context_pending > 0 {
+            self.filtered_indices.push(index);
+            self.row_info.push(RowInfo {
+                context: true,
+                after_gap: false,
+            });
+            self.context_pending -= 1;
+        }
+    }
+
+    /// Drop the row for the oldest buffered line, which was evicted
+    fn shift_rows(&mut self) {
+        if self.filtered_indices.first() == Some(&0) {
+            self.filtered_indices.remove(0);
+            self.row_info.remove(0);
+            if let Some(first) = self.row_info.first_mut() {
+                first.after_gap = false;
+            }
+        }
+        for idx in &mut self.filtered_indices {
+            *idx -= 1;
+        }
+    }
+
+    /// Scroll position that puts the last row at the bottom of the view,
+    /// counting unfolded events and the separators between context groups
+    fn bottom_scroll(&self, event_lines: impl Fn(usize) -> usize) -> usize {
+        let len = self.filtered_indices.len();
+        let mut pos = len;
+        let mut used = 0;
+        while pos > 0 {
+            let separator = pos < len && self.row(pos).after_gap;
+            let needed = used + self.row_height(pos - 1, &event_lines) + usize::from(separator);
+            if needed > self.viewport_height {
+                break;
+            }
+            used = needed;
+            pos -= 1;
+        }
+        pos
+    }
+
+    /// Number of matching rows (context lines aside)
+    fn match_count(&self) -> usize {
+        self.row_info.iter().filter(|row| !row.context).count()
+    }
+
+    /// Context rows directly above the match at `pos`, within its group
+    fn leading_context(&self, pos: usize) -> usize {
+        let mut n = 0;
+        while n < self.context_before
+            && n < pos
+            && self.row(pos - n - 1).context
+            && !self.row(pos - n).after_gap
+        {
+            n += 1;
         }
+        n
+    }
+}
+
+/// Number of lines in the buffered line (or multi-line event) at `index`
+fn event_lines(lines: &VecDeque<LogLine>, index: usize) -> usize {
+    lines
+        .get(index)
+        .map_or(1, |line| line.raw.split('\n').count())
+}
+
+/// A `[[filters]]` entry as a saved filter; `exclude` patterns become
+/// exclude filters in its stack
+fn predefined_filter(config: &FilterConfig) -> SavedFilter {
+    SavedFilter {
+        name: config.name.clone(),
+        pattern: config.pattern.clone(),
+        is_regex: config.regex,
+        stack: config
+            .exclude
+            .iter()
+            .map(|pattern| FilterChip::new(ActiveFilter::new(pattern.clone(), false), true))
+            .collect(),
+        predefined: true,
     }
 }
 
@@ -502,6 +1021,14 @@ pub struct AppState<'a> {
     pub max_lines: usize,
     /// Available log sources
     pub sources: Vec<LogSourceType>,
+    /// Health of each source (parallel to `sources`)
+    pub source_health: Vec<SourceHealth>,
+    /// Sources a watcher ended, which it may start again in the same slot
+    watch_removed: HashSet<usize>,
+    /// Whole-file browser; when set, lines are read from disk instead of `lines`
+    pub browse: Option<FileBrowser>,
+    /// Lines read from disk for the frame being drawn (browse mode)
+    browse_page: Vec<LogLine>,
 
     // === Pane management ===
     /// Panes (1 or 2)
@@ -522,6 +1049,16 @@ pub struct AppState<'a> {
     pub saved_filters: Vec<SavedFilter>,
     /// Selected saved filter index (for navigation)
     pub selected_filter_idx: usize,
+    /// `[[filters]]` from the config file, with the sources they apply to
+    predefined_filters: Vec<(glob::Pattern, SavedFilter)>,
+    /// State file that saved filters and history are written to
+    session_path: Option<PathBuf>,
+    /// Highlight rules, drawn on every line
+    pub highlights: Vec<HighlightRule>,
+    /// Selected highlight rule index (for navigation)
+    pub selected_highlight_idx: usize,
+    /// Config file that highlights and settings are saved to
+    pub config_path: Option<PathBuf>,
     /// Whether side panel is visible
     pub show_side_panel: bool,
     /// Whether the app should quit
@@ -530,10 +1067,14 @@ pub struct AppState<'a> {
     pub status_message: Option<String>,
     /// Whether to show help overlay
     pub show_help: bool,
+    /// Source shown in the detail overlay
+    pub source_detail: Option<usize>,
     /// Picker state for adding sources at runtime
     pub picker: PickerState,
     /// Settings overlay state
     pub settings: SettingsState,
+    /// Table column picker state
+    pub column_picker: ColumnPickerState,
     /// Shared clipboard handle (kept alive to avoid X11 drops)
     pub clipboard: Option<Clipboard>,
 
@@ -548,10 +1089,22 @@ pub struct AppState<'a> {
     pub json_pretty: bool,
     /// Whether to show line numbers in log view
     pub show_line_numbers: bool,
+    /// Whether structured lines are shown as a table of fields
+    pub table_mode: bool,
+    /// Fields shown as table columns (empty picks them automatically)
+    pub table_columns: Vec<String>,
     /// Active color theme
     pub theme: Theme,
     /// Export directory for logs
     pub export_dir: String,
+    /// Whether lines from multiple sources are ordered by timestamp
+    pub merge_by_timestamp: bool,
+    /// Lines held back for timestamp ordering
+    reorder: ReorderBuffer,
+    /// User-defined line parsers from the config file
+    parsers: ParserSet,
+    /// Joins continuation lines into multi-line events
+    events: EventGrouper,
 
     // === Throughput tracking ===
     /// Lines received in the last second (for throughput display)
@@ -574,14 +1127,41 @@ impl<'a> AppState<'a> {
     pub fn new(config: &Config, sources: Vec<LogSourceType>) -> Self {
         let num_sources = sources.len();
 
+        let (mut parsers, mut config_errors) = ParserSet::new(&config.parsers);
+        for source in &sources {
+            parsers.add_source(source);
+        }
+        let (mut events, event_errors) =
+            EventGrouper::new(&config.multiline, Duration::from_millis(EVENT_IDLE_MS));
+        for source in &sources {
+            events.add_source(source);
+        }
+        config_errors.extend(event_errors);
+        let (highlights, highlight_errors) = highlight::compile(&config.highlights);
+        config_errors.extend(highlight_errors);
+        let mut predefined_filters = Vec::new();
+        for filter in &config.filters {
+            match glob::Pattern::new(&filter.sources) {
+                Ok(pattern) => predefined_filters.push((pattern, predefined_filter(filter))),
+                Err(e) => config_errors.push(format!(
+                    "Filter '{}': invalid sources glob: {}",
+                    filter.name, e
+                )),
+            }
+        }
+
         // Create initial pane
         let initial_pane = PaneState::new(num_sources);
 
-        Self {
+        let mut state = Self {
             // Shared log data
             lines: VecDeque::with_capacity(config.max_lines),
             max_lines: config.max_lines,
             sources,
+            source_health: vec![SourceHealth::new(Instant::now()); num_sources],
+            watch_removed: HashSet::new(),
+            browse: None,
+            browse_page: Vec::new(),
 
             // Pane management - start with single pane
             panes: vec![initial_pane],
@@ -594,12 +1174,19 @@ impl<'a> AppState<'a> {
             current_source_idx: 0,
             saved_filters: Vec::new(),
             selected_filter_idx: 0,
+            predefined_filters,
+            session_path: None,
+            highlights,
+            selected_highlight_idx: 0,
+            config_path: Config::config_path(),
             show_side_panel: config.show_side_panel,
             should_quit: false,
-            status_message: None,
+            status_message: (!config_errors.is_empty()).then(|| config_errors.join("; ")),
             show_help: false,
+            source_detail: None,
             picker: PickerState::default(),
             settings: SettingsState::default(),
+            column_picker: ColumnPickerState::default(),
             clipboard: None,
 
             // Display preferences
@@ -608,8 +1195,14 @@ impl<'a> AppState<'a> {
             show_relative_time: false,
             json_pretty: false,
             show_line_numbers: false,
+            table_mode: false,
+            table_columns: config.table_columns.clone(),
             theme: config.get_theme(),
             export_dir: config.export_dir.clone(),
+            merge_by_timestamp: config.merge_by_timestamp,
+            reorder: ReorderBuffer::new(Duration::from_millis(config.merge_window_ms)),
+            parsers,
+            events,
 
             // Throughput tracking
             lines_per_second: 0,
@@ -621,7 +1214,9 @@ impl<'a> AppState<'a> {
 
             // Layout tracking
             log_view_areas: vec![Rect::default()],
-        }
+        };
+        state.refresh_predefined_filters();
+        state
     }
 
     /// Check if we're in split mode (have 2 panes)
@@ -944,6 +1539,7 @@ impl<'a> AppState<'a> {
         }
         // Reset history browsing position
         self.panes[self.active_pane].filter_history_idx = None;
+        self.save_session();
     }
 
     /// Navigate up in filter history (older filters)
@@ -1010,21 +1606,133 @@ impl<'a> AppState<'a> {
             .selected_line
             .unwrap_or(self.panes[self.active_pane].scroll);
         let line_idx = self.panes[self.active_pane].filtered_indices.get(idx)?;
+        if let Some(browser) = &self.browse {
+            return browser.read_line(*line_idx);
+        }
         self.lines.get(*line_idx).map(|l| l.raw.clone())
     }
 
     /// Select a line at the given viewport-relative row (0 = top of log view)
     pub fn select_line_at_row(&mut self, row: usize) {
+        let lines = &self.lines;
         let pane = &mut self.panes[self.active_pane];
-        let target_idx = pane.scroll + row;
-        if target_idx < pane.filtered_indices.len() {
-            pane.selected_line = Some(target_idx);
+        // Separators between context groups take a row of their own, and
+        // unfolded events one per line
+        let mut target_idx = pane.scroll;
+        let mut screen_row = 0;
+        while target_idx < pane.filtered_indices.len() {
+            if target_idx > pane.scroll && pane.row(target_idx).after_gap {
+                if screen_row == row {
+                    return;
+                }
+                screen_row += 1;
+            }
+            screen_row += pane.row_height(target_idx, &|i| event_lines(lines, i));
+            if row < screen_row {
+                pane.selected_line = Some(target_idx);
+                return;
+            }
+            target_idx += 1;
         }
     }
 
     /// Clear line selection
     pub fn clear_selection(&mut self) {
         self.panes[self.active_pane].selected_line = None;
+        self.panes[self.active_pane].detail_expanded = false;
+    }
+
+    /// Toggle table mode
+    pub fn toggle_table_mode(&mut self) {
+        self.table_mode = !self.table_mode;
+        if !self.table_mode {
+            for pane in &mut self.panes {
+                pane.detail_expanded = false;
+            }
+        }
+        self.status_message = Some(format!(
+            "Table view: {}",
+            if self.table_mode { "on" } else { "off" }
+        ));
+    }
+
+    /// Field sets of the most recent lines, newest first
+    fn recent_fields(&self) -> impl Iterator<Item = &Fields> {
+        let lines: Box<dyn Iterator<Item = &LogLine>> = if self.browse.is_some() {
+            Box::new(self.browse_page.iter().rev())
+        } else {
+            Box::new(self.lines.iter().rev())
+        };
+        lines.take(COLUMN_SCAN_LINES).map(|line| &line.fields)
+    }
+
+    /// Columns shown in table mode: the chosen ones, or the timestamp, level
+    /// and message fields of recent lines
+    pub fn table_columns(&self) -> Vec<String> {
+        if self.table_columns.is_empty() {
+            parse::default_columns(self.recent_fields())
+        } else {
+            self.table_columns.clone()
+        }
+    }
+
+    /// Open the column picker with the field names of recent lines
+    pub fn open_column_picker(&mut self) {
+        let mut keys: Vec<String> = Vec::new();
+        let recent: Vec<&Fields> = self.recent_fields().collect();
+        // Oldest first so keys appear in the order lines introduced them
+        for fields in recent.into_iter().rev() {
+            for (key, _) in fields {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        if keys.is_empty() {
+            self.status_message = Some("No structured fields in recent lines".to_string());
+            return;
+        }
+        let chosen = self.table_columns();
+        self.column_picker.open(keys, chosen);
+    }
+
+    /// Use the picked columns and switch to table mode
+    pub fn apply_column_picker(&mut self) {
+        self.table_columns = std::mem::take(&mut self.column_picker.chosen);
+        self.column_picker.close();
+        self.table_mode = true;
+        self.status_message = Some(if self.table_columns.is_empty() {
+            "Columns: auto".to_string()
+        } else {
+            format!("Columns: {}", self.table_columns.join(" | "))
+        });
+    }
+
+    /// Show or hide the fields of the selected line (table mode).
+    /// Selects the top line first if nothing is selected.
+    pub fn toggle_field_detail(&mut self) {
+        let pane = &mut self.panes[self.active_pane];
+        if pane.filtered_indices.is_empty() {
+            return;
+        }
+        if pane.selected_line.is_none() {
+            pane.selected_line = Some(pane.scroll);
+            pane.detail_expanded = true;
+        } else {
+            pane.detail_expanded = !pane.detail_expanded;
+        }
+    }
+
+    /// Fields of a pane's selected line
+    pub fn selected_fields(&self, pane_idx: usize) -> Option<Fields> {
+        let pane = self.panes.get(pane_idx)?;
+        let line_idx = *pane.filtered_indices.get(pane.selected_line?)?;
+        if let Some(browser) = &self.browse {
+            let mut line = LogLine::new(browser.read_line(line_idx)?);
+            self.parsers.apply(&mut line);
+            return Some(line.fields);
+        }
+        self.lines.get(line_idx).map(|line| line.fields.clone())
     }
 
     /// Available theme names in cycle order
@@ -1053,23 +1761,132 @@ impl<'a> AppState<'a> {
 
     /// Save current display settings to config file
     pub fn save_settings(&mut self) {
-        let config = Config {
-            theme: self.theme.name().to_string(),
-            level_colors: self.level_colors_enabled,
-            line_wrap: self.line_wrap,
-            show_side_panel: self.show_side_panel,
-            ..Config::default()
+        let theme = self.theme.name().to_string();
+        let (level_colors, line_wrap, show_side_panel) = (
+            self.level_colors_enabled,
+            self.line_wrap,
+            self.show_side_panel,
+        );
+        let result = self.update_config_file(|config| {
+            config.theme = theme;
+            config.level_colors = level_colors;
+            config.line_wrap = line_wrap;
+            config.show_side_panel = show_side_panel;
+        });
+        self.status_message = Some(match result {
+            Ok(()) => "Settings saved".to_string(),
+            Err(e) => format!("Failed to save: {}", e),
+        });
+    }
+
+    /// Change the config file on disk, keeping everything the UI doesn't
+    /// manage (and ignoring environment overrides)
+    fn update_config_file(&self, update: impl FnOnce(&mut Config)) -> Result<(), String> {
+        let path = self
+            .config_path
+            .as_ref()
+            .ok_or_else(|| "Could not determine config directory".to_string())?;
+        let mut config = Config::load_file(path).map_err(|e| format!("invalid config: {}", e))?;
+        update(&mut config);
+        config.save_to(path)
+    }
+
+    /// Per-rule style and ranges of `line` covered by enabled highlight rules
+    pub fn get_highlight_ranges(&self, line: &str) -> Vec<(MatchRange, Style)> {
+        self.highlights
+            .iter()
+            .filter(|rule| rule.config.enabled)
+            .flat_map(|rule| {
+                let style = rule.style(&self.theme);
+                rule.find_matches(line).into_iter().map(move |m| (m, style))
+            })
+            .collect()
+    }
+
+    /// Turn the active filter into a highlight rule: everything stays
+    /// visible and the pattern is colored instead. The text is taken
+    /// literally (or as a regex in regex mode), even if it parsed as a query.
+    pub fn highlight_active_filter(&mut self) {
+        let Some(filter) = self.panes[self.active_pane].active_filter.as_ref() else {
+            self.status_message = Some("No active filter to highlight (use / first)".to_string());
+            return;
         };
-        match config.save() {
-            Ok(()) => {
-                self.status_message = Some("Settings saved".to_string());
+        let config = HighlightConfig {
+            pattern: filter.pattern.clone(),
+            regex: filter.is_regex,
+            color: HIGHLIGHT_COLORS[self.highlights.len() % HIGHLIGHT_COLORS.len()].to_string(),
+            style: String::new(),
+            enabled: true,
+        };
+        match HighlightRule::new(config) {
+            Ok(rule) => {
+                let pattern = rule.config.pattern.clone();
+                self.highlights.push(rule);
+                self.selected_highlight_idx = self.highlights.len() - 1;
+                let pane = &mut self.panes[self.active_pane];
+                pane.active_filter = None;
+                pane.filter_textarea.select_all();
+                pane.filter_textarea.cut();
+                self.recompute_filter();
+                self.save_highlights(format!("Highlighting: {}", pattern));
             }
-            Err(e) => {
-                self.status_message = Some(format!("Failed to save: {}", e));
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    /// Enable or disable the selected highlight rule
+    pub fn toggle_selected_highlight(&mut self) {
+        if let Some(rule) = self.highlights.get_mut(self.selected_highlight_idx) {
+            rule.config.enabled = !rule.config.enabled;
+            let message = format!(
+                "Highlight {}: {}",
+                if rule.config.enabled { "on" } else { "off" },
+                rule.config.pattern
+            );
+            self.save_highlights(message);
+        }
+    }
+
+    /// Give the selected highlight rule the next color
+    pub fn cycle_selected_highlight_color(&mut self) {
+        if let Some(rule) = self.highlights.get_mut(self.selected_highlight_idx) {
+            rule.cycle_color();
+            let message = format!("Highlight color: {}", rule.config.color);
+            self.save_highlights(message);
+        }
+    }
+
+    /// Delete the selected highlight rule
+    pub fn remove_selected_highlight(&mut self) {
+        if self.selected_highlight_idx < self.highlights.len() {
+            let rule = self.highlights.remove(self.selected_highlight_idx);
+            if self.selected_highlight_idx >= self.highlights.len()
+                && self.selected_highlight_idx > 0
+            {
+                self.selected_highlight_idx -= 1;
             }
+            self.save_highlights(format!("Highlight deleted: {}", rule.config.pattern));
         }
     }
 
+    /// Write the highlight rules to the config file, touching nothing else
+    /// in it, and report `message` (or the failure) in the status bar
+    fn save_highlights(&mut self, message: String) {
+        let highlights: Vec<HighlightConfig> = self
+            .highlights
+            .iter()
+            .map(|rule| rule.config.clone())
+            .collect();
+        let result = match &self.config_path {
+            Some(path) => Config::save_highlights_to(path, &highlights),
+            None => Err("Could not determine config directory".to_string()),
+        };
+        self.status_message = Some(match result {
+            Ok(()) => message,
+            Err(e) => format!("{} (not saved: {})", message, e),
+        });
+    }
+
     /// Toggle a setting by index (used by settings overlay)
     pub fn toggle_setting(&mut self, index: usize) {
         match index {
@@ -1152,6 +1969,15 @@ impl<'a> AppState<'a> {
         let mut file = File::create(path).map_err(|e| e.to_string())?;
 
         let mut count = 0;
+        if let Some(browser) = &self.browse {
+            for &idx in &self.panes[self.active_pane].filtered_indices {
+                if let Some(raw) = browser.read_line(idx) {
+                    writeln!(file, "{}", raw).map_err(|e| e.to_string())?;
+                    count += 1;
+                }
+            }
+            return Ok(count);
+        }
         for &idx in &self.panes[self.active_pane].filtered_indices {
             if let Some(line) = self.lines.get(idx) {
                 writeln!(file, "{}", line.raw).map_err(|e| e.to_string())?;
@@ -1173,38 +1999,192 @@ impl<'a> AppState<'a> {
         self.sources.get(self.current_source_idx)
     }
 
-    /// Add a new source at runtime
-    #[allow(dead_code)]
-    pub fn add_source(&mut self, source: LogSourceType) {
+    /// Add a new source at runtime, visible in every pane. Returns its source id.
+    pub fn add_source(&mut self, source: LogSourceType) -> usize {
+        self.parsers.add_source(&source);
+        self.events.add_source(&source);
         self.sources.push(source);
-        self.panes[self.active_pane].visible_sources.push(true);
+        self.source_health.push(SourceHealth::new(Instant::now()));
+        for pane in &mut self.panes {
+            pane.visible_sources.push(true);
+        }
+        self.refresh_predefined_filters();
+        self.sources.len() - 1
     }
 
-    /// Save the current filter with a name
-    pub fn save_current_filter(&mut self, name: String) {
-        if let Some(ref filter) = self.panes[self.active_pane].active_filter {
-            self.saved_filters.push(SavedFilter {
-                name,
-                pattern: filter.pattern.clone(),
-                is_regex: filter.is_regex,
-            });
-            self.status_message = Some("Filter saved".to_string());
+    /// Update the status of a source
+    pub fn set_source_status(&mut self, source_id: usize, status: SourceStatus) {
+        if let Some(health) = self.source_health.get_mut(source_id) {
+            health.set_status(status, Instant::now());
         }
     }
 
-    /// Apply a saved filter by index
-    pub fn apply_saved_filter(&mut self, idx: usize) {
-        if let Some(saved) = self.saved_filters.get(idx) {
-            let pattern = saved.pattern.clone();
-            let is_regex = saved.is_regex;
-            let name = saved.name.clone();
+    /// Count lines delivered by a source
+    pub fn record_source_lines(&mut self, source_id: usize, count: u64) {
+        if let Some(health) = self.source_health.get_mut(source_id) {
+            health.record_lines(count, Instant::now());
+        }
+    }
+
+    /// Remember when a source's newest line was written
+    pub fn record_source_timestamp(&mut self, source_id: usize, timestamp: DateTime<Local>) {
+        if let Some(health) = self.source_health.get_mut(source_id) {
+            health.record_timestamp(timestamp);
+        }
+    }
+
+    /// Timestamp of the newest line a source delivered
+    pub fn source_last_timestamp(&self, source_id: usize) -> Option<DateTime<Local>> {
+        self.source_health
+            .get(source_id)
+            .and_then(|health| health.last_timestamp)
+    }
+
+    /// Remember an error reported by a source
+    pub fn record_source_error(&mut self, source_id: usize, message: String) {
+        if let Some(health) = self.source_health.get_mut(source_id) {
+            health.record_error(message, Instant::now());
+        }
+    }
+
+    /// A watcher reported a source: the slot to start it in (a new one, or
+    /// the one the watcher ended earlier), or None when it is already there.
+    /// A source that ended on its own, like a compressed file read once, is
+    /// left alone.
+    pub fn watched_source_added(&mut self, source: &LogSourceType) -> Option<usize> {
+        match self.sources.iter().position(|s| s == source) {
+            Some(idx) if self.watch_removed.remove(&idx) => {
+                self.set_source_status(idx, SourceStatus::Connecting);
+                Some(idx)
+            }
+            Some(_) => None,
+            None => Some(self.add_source(source.clone())),
+        }
+    }
+
+    /// A watcher lost a source: mark it ended and return its slot
+    pub fn watched_source_removed(&mut self, source: &LogSourceType) -> Option<usize> {
+        let idx = self.sources.iter().position(|s| s == source)?;
+        self.set_source_status(idx, SourceStatus::Ended);
+        self.watch_removed.insert(idx);
+        Some(idx)
+    }
+
+    /// Open the detail overlay for the selected source
+    pub fn show_source_detail(&mut self) {
+        if self.current_source_idx < self.sources.len() {
+            self.source_detail = Some(self.current_source_idx);
+        }
+    }
+
+    /// Save the current filter and the pinned filter stack with a name
+    pub fn save_current_filter(&mut self, name: String) {
+        let pane = &self.panes[self.active_pane];
+        if pane.active_filter.is_none() && pane.filter_stack.is_empty() {
+            return;
+        }
+        let (pattern, is_regex) = pane
+            .active_filter
+            .as_ref()
+            .map(|f| (f.pattern.clone(), f.is_regex))
+            .unwrap_or_default();
+        self.saved_filters.push(SavedFilter {
+            name,
+            pattern,
+            is_regex,
+            stack: pane.filter_stack.clone(),
+            predefined: false,
+        });
+        self.status_message = Some("Filter saved".to_string());
+        self.save_session();
+    }
+
+    /// Delete the selected saved filter (those from the config file stay)
+    pub fn delete_saved_filter(&mut self) {
+        let Some(filter) = self.saved_filters.get(self.selected_filter_idx) else {
+            return;
+        };
+        if filter.predefined {
+            self.status_message = Some(format!("'{}' is defined in config.toml", filter.name));
+            return;
+        }
+        self.saved_filters.remove(self.selected_filter_idx);
+        if self.selected_filter_idx >= self.saved_filters.len() && self.selected_filter_idx > 0 {
+            self.selected_filter_idx -= 1;
+        }
+        self.status_message = Some("Filter deleted".to_string());
+        self.save_session();
+    }
+
+    /// List the config file's filters whose sources are open, ahead of the
+    /// user's own saved filters
+    fn refresh_predefined_filters(&mut self) {
+        let mut filters: Vec<SavedFilter> = self
+            .predefined_filters
+            .iter()
+            .filter(|(pattern, _)| {
+                pattern.as_str() == "*" || self.sources.iter().any(|s| s.matches_glob(pattern))
+            })
+            .map(|(_, filter)| filter.clone())
+            .collect();
+        filters.extend(self.saved_filters.drain(..).filter(|f| !f.predefined));
+        self.saved_filters = filters;
+        self.selected_filter_idx = self
+            .selected_filter_idx
+            .min(self.saved_filters.len().saturating_sub(1));
+    }
+
+    /// Load saved filters and filter history from the state file, and keep
+    /// writing changes to it. An unreadable file is left alone.
+    pub fn restore_session(&mut self, path: PathBuf) {
+        match Session::load(&path) {
+            Ok(session) => {
+                self.saved_filters.extend(session.saved_filters);
+                self.filter_history = session.filter_history;
+                self.filter_history.truncate(Self::MAX_FILTER_HISTORY);
+                self.session_path = Some(path);
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Couldn't read {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Write saved filters and filter history to the state file
+    fn save_session(&mut self) {
+        let Some(path) = &self.session_path else {
+            return;
+        };
+        let session = Session {
+            saved_filters: self
+                .saved_filters
+                .iter()
+                .filter(|f| !f.predefined)
+                .cloned()
+                .collect(),
+            filter_history: self.filter_history.clone(),
+        };
+        if let Err(e) = session.save(path) {
+            self.status_message = Some(format!("Couldn't save {}: {}", path.display(), e));
+        }
+    }
+
+    /// Apply a saved filter by index
+    pub fn apply_saved_filter(&mut self, idx: usize) {
+        if let Some(saved) = self.saved_filters.get(idx) {
+            let pattern = saved.pattern.clone();
+            let is_regex = saved.is_regex;
+            let name = saved.name.clone();
+            let stack = saved.stack.clone();
 
             self.panes[self.active_pane].filter_textarea = TextArea::new(vec![pattern.clone()]);
             self.panes[self.active_pane]
                 .filter_textarea
                 .set_cursor_line_style(Style::default());
             self.panes[self.active_pane].filter_is_regex = is_regex;
-            self.panes[self.active_pane].active_filter = Some(ActiveFilter::new(pattern, is_regex));
+            self.panes[self.active_pane].active_filter =
+                (!pattern.is_empty()).then(|| ActiveFilter::new(pattern, is_regex));
+            self.panes[self.active_pane].filter_stack = stack;
             self.recompute_filter();
             self.status_message = Some(format!("Applied filter: {}", name));
         }
@@ -1249,7 +2229,8 @@ impl<'a> AppState<'a> {
         self.focused_panel = match self.focused_panel {
             FocusedPanel::LogView => FocusedPanel::Sources,
             FocusedPanel::Sources => FocusedPanel::Filters,
-            FocusedPanel::Filters => FocusedPanel::LogView,
+            FocusedPanel::Filters => FocusedPanel::Highlights,
+            FocusedPanel::Highlights => FocusedPanel::LogView,
         };
     }
 
@@ -1271,21 +2252,17 @@ impl<'a> AppState<'a> {
 
                 // Adjust bookmark and filtered indices for ALL panes
                 for pane in &mut self.panes {
-                    pane.bookmarks.retain_mut(|idx| {
-                        if *idx == 0 {
-                            return false;
-                        }
-                        *idx -= 1;
-                        true
-                    });
+                    for indices in [&mut pane.bookmarks, &mut pane.fold_toggled] {
+                        indices.retain_mut(|idx| {
+                            if *idx == 0 {
+                                return false;
+                            }
+                            *idx -= 1;
+                            true
+                        });
+                    }
 
-                    pane.filtered_indices.retain_mut(|idx| {
-                        if *idx == 0 {
-                            return false;
-                        }
-                        *idx -= 1;
-                        true
-                    });
+                    pane.shift_rows();
                 }
             }
 
@@ -1294,19 +2271,15 @@ impl<'a> AppState<'a> {
 
             // Check if the new line matches the filter for EACH pane
             for pane_idx in 0..num_panes {
-                if self.matches_filter_for_pane(pane_idx, line_index) {
-                    self.panes[pane_idx].filtered_indices.push(line_index);
-                }
+                self.admit_line(pane_idx, line_index);
             }
         }
 
         // Update scroll for ALL panes
+        let lines = &self.lines;
         for pane in &mut self.panes {
             if pane.stick_to_bottom && !pane.filtered_indices.is_empty() {
-                pane.scroll = pane
-                    .filtered_indices
-                    .len()
-                    .saturating_sub(pane.viewport_height);
+                pane.scroll = pane.bottom_scroll(|i| event_lines(lines, i));
             }
 
             // Adjust scroll if it's now out of bounds
@@ -1334,54 +2307,37 @@ impl<'a> AppState<'a> {
         }
     }
 
-    /// Check if a line at the given index matches the current filter (active pane)
-    fn matches_filter(&self, index: usize) -> bool {
-        self.matches_filter_for_pane(self.active_pane, index)
-    }
-
-    /// Check if a line at the given index matches the filter for a specific pane
-    fn matches_filter_for_pane(&self, pane_idx: usize, index: usize) -> bool {
-        let line = match self.lines.get(index) {
-            Some(l) => l,
-            None => return false,
-        };
-
-        let pane = match self.panes.get(pane_idx) {
-            Some(p) => p,
-            None => return false,
+    /// List the buffered line at `index` in a pane if it matches the
+    /// pane's filter or falls within the context of a match
+    fn admit_line(&mut self, pane_idx: usize, index: usize) {
+        let (Some(pane), Some(line)) = (self.panes.get(pane_idx), self.lines.get(index)) else {
+            return;
         };
-
-        // Check source visibility
-        if !pane
-            .visible_sources
-            .get(line.source_id)
-            .copied()
-            .unwrap_or(true)
-        {
-            return false;
-        }
-
-        // Check view mode
-        match pane.view_mode {
-            SourceViewMode::AllMerged => {}
-            SourceViewMode::SingleSource(id) if id != line.source_id => return false,
-            _ => {}
-        }
-
-        // Check text filter
-        match &pane.active_filter {
-            None => true,
-            Some(filter) => filter.matches(&line.raw),
+        if !pane.shows_source(line) {
+            return;
         }
+        let matched = pane.matches_content(line, &self.sources);
+        let earlier = if matched {
+            pane.context_candidates(index, |i| pane.shows_source(&self.lines[i]))
+        } else {
+            Vec::new()
+        };
+        self.panes[pane_idx].push_row(index, matched, &earlier);
     }
 
     /// Recompute filtered_indices based on current filter
     pub fn recompute_filter(&mut self) {
-        self.panes[self.active_pane].filtered_indices.clear();
+        self.panes[self.active_pane].clear_rows();
+        if self.browse.is_some() {
+            // The file is rescanned incrementally by `tick_browse`
+            let pane = &mut self.panes[self.active_pane];
+            pane.scan_pos = 0;
+            pane.scroll = 0;
+            pane.selected_line = None;
+            return;
+        }
         for i in 0..self.lines.len() {
-            if self.matches_filter(i) {
-                self.panes[self.active_pane].filtered_indices.push(i);
-            }
+            self.admit_line(self.active_pane, i);
         }
 
         // Adjust scroll if it's now out of bounds
@@ -1443,66 +2399,93 @@ impl<'a> AppState<'a> {
         self.panes[self.active_pane].stick_to_bottom = true;
     }
 
-    /// Go to next matching line (when filter is active)
+    /// Go to next matching line (when filter is active), skipping context lines
     pub fn next_match(&mut self) {
-        if self.panes[self.active_pane].filtered_indices.is_empty() {
+        let pane = &self.panes[self.active_pane];
+        let len = pane.filtered_indices.len();
+        if pane.match_count() == 0 {
             return;
         }
-        if self.panes[self.active_pane].scroll
-            < self.panes[self.active_pane].filtered_indices.len() - 1
-        {
-            self.panes[self.active_pane].scroll += 1;
-            self.panes[self.active_pane].stick_to_bottom = false;
-            self.status_message = Some(format!(
-                "Match {}/{}",
-                self.panes[self.active_pane].scroll + 1,
-                self.panes[self.active_pane].filtered_indices.len()
-            ));
-        } else {
-            // Wrap to beginning
-            self.panes[self.active_pane].scroll = 0;
-            self.panes[self.active_pane].stick_to_bottom = false;
-            self.status_message = Some(format!(
-                "Match {}/{} (wrapped)",
-                self.panes[self.active_pane].scroll + 1,
-                self.panes[self.active_pane].filtered_indices.len()
-            ));
+        let current = self.current_match_pos();
+        let pane = &self.panes[self.active_pane];
+        match (current + 1..len).find(|&pos| !pane.row(pos).context) {
+            Some(pos) => self.show_match(pos, false),
+            None => {
+                // Wrap to beginning
+                let first = (0..len).find(|&pos| !pane.row(pos).context).unwrap_or(0);
+                self.show_match(first, true);
+            }
         }
     }
 
-    /// Go to previous matching line (when filter is active)
+    /// Go to previous matching line (when filter is active), skipping context lines
     pub fn prev_match(&mut self) {
-        if self.panes[self.active_pane].filtered_indices.is_empty() {
+        let pane = &self.panes[self.active_pane];
+        let len = pane.filtered_indices.len();
+        if pane.match_count() == 0 {
             return;
         }
-        if self.panes[self.active_pane].scroll > 0 {
-            self.panes[self.active_pane].scroll -= 1;
-            self.panes[self.active_pane].stick_to_bottom = false;
-            self.status_message = Some(format!(
-                "Match {}/{}",
-                self.panes[self.active_pane].scroll + 1,
-                self.panes[self.active_pane].filtered_indices.len()
-            ));
-        } else {
-            // Wrap to end
-            self.panes[self.active_pane].scroll =
-                self.panes[self.active_pane].filtered_indices.len() - 1;
-            self.panes[self.active_pane].stick_to_bottom = false;
-            self.status_message = Some(format!(
-                "Match {}/{} (wrapped)",
-                self.panes[self.active_pane].scroll + 1,
-                self.panes[self.active_pane].filtered_indices.len()
-            ));
+        let current = self.current_match_pos();
+        let pane = &self.panes[self.active_pane];
+        match (0..current).rev().find(|&pos| !pane.row(pos).context) {
+            Some(pos) => self.show_match(pos, false),
+            None => {
+                // Wrap to end
+                let last = (0..len)
+                    .rev()
+                    .find(|&pos| !pane.row(pos).context)
+                    .unwrap_or(0);
+                self.show_match(last, true);
+            }
         }
     }
 
-    /// Get match ranges for a line (for highlighting)
-    pub fn get_match_ranges(&self, line: &str) -> Vec<MatchRange> {
-        if let Some(ref filter) = self.panes[self.active_pane].active_filter {
-            filter.find_matches(line)
-        } else {
-            Vec::new()
+    /// Position of the match at the top of the view, below the context
+    /// lines shown before it
+    fn current_match_pos(&self) -> usize {
+        let pane = &self.panes[self.active_pane];
+        let mut pos = pane.scroll;
+        while pos < pane.scroll + pane.context_before
+            && pos + 1 < pane.filtered_indices.len()
+            && pane.row(pos).context
+            && !pane.row(pos + 1).after_gap
+        {
+            pos += 1;
         }
+        pos
+    }
+
+    /// Scroll so the match at `pos` is at the top, with its leading context
+    fn show_match(&mut self, pos: usize, wrapped: bool) {
+        let pane = &mut self.panes[self.active_pane];
+        pane.scroll = pos - pane.leading_context(pos);
+        pane.stick_to_bottom = false;
+        let number = (0..=pos).filter(|&p| !pane.row(p).context).count();
+        self.status_message = Some(format!(
+            "Match {}/{}{}",
+            number,
+            pane.match_count(),
+            if wrapped { " (wrapped)" } else { "" }
+        ));
+    }
+
+    /// Get match ranges for a line (for highlighting): the active filter
+    /// and enabled include filters from the stack
+    pub fn get_match_ranges(&self, line: &str) -> Vec<MatchRange> {
+        let pane = &self.panes[self.active_pane];
+        let mut matches: Vec<MatchRange> = pane
+            .active_filter
+            .iter()
+            .chain(
+                pane.filter_stack
+                    .iter()
+                    .filter(|chip| chip.enabled && !chip.exclude)
+                    .map(|chip| &chip.filter),
+            )
+            .flat_map(|f| f.find_matches(line))
+            .collect();
+        filter::merge_overlapping(&mut matches);
+        matches
     }
 
     /// Get the current filter input text
@@ -1521,10 +2504,11 @@ impl<'a> AppState<'a> {
         } else {
             // Add to history before applying
             self.add_to_filter_history(input.clone());
-            self.panes[self.active_pane].active_filter = Some(ActiveFilter::new(
-                input,
-                self.panes[self.active_pane].filter_is_regex,
-            ));
+            let filter = ActiveFilter::new(input, self.panes[self.active_pane].filter_is_regex);
+            if let Some(ref error) = filter.error {
+                self.status_message = Some(format!("Query error: {} (matching as text)", error));
+            }
+            self.panes[self.active_pane].active_filter = Some(filter);
         }
         self.recompute_filter();
         self.mode = InputMode::Normal;
@@ -1579,6 +2563,185 @@ impl<'a> AppState<'a> {
         }
     }
 
+    /// Pin the active filter to the pane's filter stack as an include
+    /// (`exclude` false) or exclude filter, leaving the filter input free
+    pub fn push_filter(&mut self, exclude: bool) {
+        let pane = &mut self.panes[self.active_pane];
+        let Some(filter) = pane.active_filter.take() else {
+            self.status_message = Some("No active filter to pin (use / to filter)".to_string());
+            return;
+        };
+        let chip = FilterChip::new(filter, exclude);
+        let label = chip.label();
+        pane.filter_stack.push(chip);
+        pane.filter_textarea.select_all();
+        pane.filter_textarea.cut();
+        self.recompute_filter();
+        self.status_message = Some(format!(
+            "Pinned filter {}: {}",
+            self.panes[self.active_pane].filter_stack.len(),
+            label
+        ));
+    }
+
+    /// Enable or disable the filter at `idx` in the active pane's stack
+    pub fn toggle_filter_chip(&mut self, idx: usize) {
+        let Some(chip) = self.panes[self.active_pane].filter_stack.get_mut(idx) else {
+            return;
+        };
+        chip.enabled = !chip.enabled;
+        let message = format!(
+            "Filter {} {}: {}",
+            idx + 1,
+            if chip.enabled { "on" } else { "off" },
+            chip.label()
+        );
+        self.recompute_filter();
+        self.status_message = Some(message);
+    }
+
+    /// Remove the most recently pinned filter
+    pub fn pop_filter_chip(&mut self) {
+        if let Some(chip) = self.panes[self.active_pane].filter_stack.pop() {
+            self.recompute_filter();
+            self.status_message = Some(format!("Removed filter: {}", chip.label()));
+        }
+    }
+
+    /// Remove every pinned filter from the active pane
+    pub fn clear_filter_stack(&mut self) {
+        if !self.panes[self.active_pane].filter_stack.is_empty() {
+            self.panes[self.active_pane].filter_stack.clear();
+            self.recompute_filter();
+            self.status_message = Some("Filter stack cleared".to_string());
+        }
+    }
+
+    /// Raise the active pane's level threshold (off → TRACE → ... → ERROR)
+    pub fn raise_min_level(&mut self) {
+        let next = match self.panes[self.active_pane].min_level {
+            None => LogLevel::Trace,
+            Some(LogLevel::Trace) => LogLevel::Debug,
+            Some(LogLevel::Debug) => LogLevel::Info,
+            Some(LogLevel::Info) => LogLevel::Warn,
+            Some(_) => LogLevel::Error,
+        };
+        self.set_min_level(Some(next));
+    }
+
+    /// Lower the active pane's level threshold, down to showing every line
+    pub fn lower_min_level(&mut self) {
+        let next = match self.panes[self.active_pane].min_level {
+            Some(LogLevel::Error) => Some(LogLevel::Warn),
+            Some(LogLevel::Warn) => Some(LogLevel::Info),
+            Some(LogLevel::Info) => Some(LogLevel::Debug),
+            Some(LogLevel::Debug) => Some(LogLevel::Trace),
+            _ => None,
+        };
+        self.set_min_level(next);
+    }
+
+    fn set_min_level(&mut self, level: Option<LogLevel>) {
+        if self.panes[self.active_pane].min_level == level {
+            return;
+        }
+        self.panes[self.active_pane].min_level = level;
+        self.recompute_filter();
+        self.status_message = Some(match level {
+            Some(level) => format!("Level: {} and above", level.name().to_uppercase()),
+            None => "Level: all lines".to_string(),
+        });
+    }
+
+    /// Show one more line before and after each match in the active pane
+    pub fn grow_context(&mut self) {
+        let pane = &self.panes[self.active_pane];
+        self.set_context(pane.context_before + 1, pane.context_after + 1);
+    }
+
+    /// Show one line less before and after each match in the active pane
+    pub fn shrink_context(&mut self) {
+        let pane = &self.panes[self.active_pane];
+        self.set_context(
+            pane.context_before.saturating_sub(1),
+            pane.context_after.saturating_sub(1),
+        );
+    }
+
+    /// Set how many lines are shown before and after each match in the
+    /// active pane (like `grep -B`/`-A`)
+    fn set_context(&mut self, before: usize, after: usize) {
+        let pane = &mut self.panes[self.active_pane];
+        if (pane.context_before, pane.context_after) == (before, after) {
+            return;
+        }
+        pane.context_before = before;
+        pane.context_after = after;
+        self.recompute_filter();
+        self.status_message = Some(if before == 0 && after == 0 {
+            "Context: off".to_string()
+        } else {
+            format!("Context: {} before, {} after each match", before, after)
+        });
+    }
+
+    /// Fold or unfold the multi-line event at the selected line (or the top
+    /// of the view)
+    pub fn toggle_fold(&mut self) {
+        let pane = &self.panes[self.active_pane];
+        let Some(&line_idx) = pane
+            .filtered_indices
+            .get(pane.selected_line.unwrap_or(pane.scroll))
+        else {
+            return;
+        };
+        if event_lines(&self.lines, line_idx) < 2 {
+            self.status_message = Some("Not a multi-line event".to_string());
+            return;
+        }
+        let pane = &mut self.panes[self.active_pane];
+        match pane.fold_toggled.iter().position(|&i| i == line_idx) {
+            Some(pos) => {
+                pane.fold_toggled.remove(pos);
+            }
+            None => pane.fold_toggled.push(line_idx),
+        }
+        let unfolded = pane.is_unfolded(line_idx);
+        self.keep_following();
+        self.status_message = Some(
+            if unfolded {
+                "Event unfolded"
+            } else {
+                "Event folded"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Fold or unfold every multi-line event in the active pane
+    pub fn toggle_fold_all(&mut self) {
+        let pane = &mut self.panes[self.active_pane];
+        pane.unfold_all = !pane.unfold_all;
+        pane.fold_toggled.clear();
+        self.status_message = Some(
+            if pane.unfold_all {
+                "All events unfolded"
+            } else {
+                "All events folded"
+            }
+            .to_string(),
+        );
+        self.keep_following();
+    }
+
+    /// Keep the last row in view after event heights changed
+    fn keep_following(&mut self) {
+        let pane = &mut self.panes[self.active_pane];
+        if pane.stick_to_bottom && !pane.filtered_indices.is_empty() {
+            pane.scroll = pane.bottom_scroll(|i| event_lines(&self.lines, i));
+        }
+    }
+
     /// Toggle regex mode for filtering
     pub fn toggle_regex_mode(&mut self) {
         self.panes[self.active_pane].filter_is_regex =
@@ -1602,15 +2765,182 @@ impl<'a> AppState<'a> {
         ));
     }
 
+    /// Start browsing a whole file instead of streaming into the ring buffer
+    pub fn start_browse(&mut self, browser: FileBrowser) {
+        self.browse = Some(browser);
+        for pane in &mut self.panes {
+            pane.stick_to_bottom = false;
+            pane.scroll = 0;
+            pane.scan_pos = 0;
+            pane.clear_rows();
+        }
+    }
+
+    /// Whether the file being browsed is still being indexed or filtered
+    pub fn browse_in_progress(&self) -> bool {
+        match &self.browse {
+            Some(browser) => {
+                !browser.is_fully_indexed()
+                    || self.panes.iter().any(|p| p.scan_pos < browser.line_count())
+            }
+            None => false,
+        }
+    }
+
+    /// Whether incoming lines currently go through the reorder buffer
+    pub fn merging_by_timestamp(&self) -> bool {
+        self.merge_by_timestamp && self.sources.len() > 1
+    }
+
+    /// Accept lines from sources: joined into multi-line events where a rule
+    /// says so, then appended directly, or held back briefly and merged by
+    /// timestamp when several sources are streaming
+    pub fn receive_lines(&mut self, mut lines: Vec<LogLine>) {
+        // Configured parsers run first: their timestamps decide the merge order
+        for line in &mut lines {
+            self.parsers.apply(line);
+        }
+        let events = self.events.push(lines, Instant::now());
+        self.deliver_lines(events);
+    }
+
+    fn deliver_lines(&mut self, lines: Vec<LogLine>) {
+        if self.merging_by_timestamp() {
+            self.reorder.push(lines, Instant::now());
+        } else {
+            self.push_lines(lines);
+        }
+    }
+
+    /// Append held-back lines: events whose source went quiet, and lines
+    /// whose reorder window has passed
+    pub fn tick_merge(&mut self) {
+        if !self.events.is_empty() {
+            let ready = self.events.drain_ready(Instant::now());
+            self.deliver_lines(ready);
+        }
+        if !self.reorder.is_empty() {
+            let ready = self.reorder.drain_ready(Instant::now());
+            self.push_lines(ready);
+        }
+    }
+
+    /// Switch between arrival order and timestamp order
+    pub fn toggle_merge_order(&mut self) {
+        self.merge_by_timestamp = !self.merge_by_timestamp;
+        if !self.merge_by_timestamp {
+            let held = self.reorder.drain_all();
+            self.push_lines(held);
+        }
+        self.status_message = Some(format!(
+            "Line order: {}",
+            if self.merge_by_timestamp {
+                "by timestamp"
+            } else {
+                "by arrival"
+            }
+        ));
+    }
+
+    /// Advance browse mode by one step: index more of the file, pick up
+    /// growth and truncation, and extend each pane's filter results.
+    /// Work per call is bounded so the UI stays responsive on huge files.
+    pub fn tick_browse(&mut self) {
+        let Some(browser) = self.browse.as_mut() else {
+            return;
+        };
+
+        match browser.index_step(INDEX_BYTES_PER_STEP) {
+            Ok(IndexUpdate::Reset) => {
+                for pane in &mut self.panes {
+                    pane.clear_rows();
+                    pane.bookmarks.clear();
+                    pane.scan_pos = 0;
+                    pane.scroll = 0;
+                    pane.selected_line = None;
+                }
+                self.status_message = Some("File truncated, reading from start".to_string());
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.status_message = Some(format!("Error reading file: {}", e));
+                return;
+            }
+        }
+
+        let count = browser.line_count();
+        let mut budget = BROWSE_SCAN_LINES_PER_TICK;
+        for pane in &mut self.panes {
+            if pane.scan_pos < count && !pane.filters_line_content() {
+                // Every line comes from the same source, so they all share
+                // the verdict of an empty line
+                let from = pane.scan_pos;
+                if pane.matches_line(&LogLine::new(String::new()), &self.sources) {
+                    pane.filtered_indices.extend(from..count);
+                    pane.row_info
+                        .resize(pane.filtered_indices.len(), RowInfo::default());
+                }
+                pane.scan_pos = count;
+            }
+
+            while pane.scan_pos < count && budget > 0 {
+                let from = pane.scan_pos;
+                let to = browser.range_end_within(from, budget, INDEX_BYTES_PER_STEP);
+                let raws = match browser.read_range(from, to) {
+                    Ok(raws) => raws,
+                    Err(e) => {
+                        self.status_message = Some(format!("Error reading file: {}", e));
+                        return;
+                    }
+                };
+                for (i, raw) in raws.into_iter().enumerate() {
+                    let mut line = LogLine::new(raw);
+                    self.parsers.apply(&mut line);
+                    if !pane.shows_source(&line) {
+                        continue;
+                    }
+                    let index = from + i;
+                    let matched = pane.matches_content(&line, &self.sources);
+                    let earlier = if matched {
+                        pane.context_candidates(index, |_| true)
+                    } else {
+                        Vec::new()
+                    };
+                    pane.push_row(index, matched, &earlier);
+                }
+                pane.scan_pos = to;
+                budget -= to - from;
+            }
+
+            if pane.stick_to_bottom && !pane.filtered_indices.is_empty() {
+                // Browsed lines are never grouped into events
+                pane.scroll = pane.bottom_scroll(|_| 1);
+            }
+            if !pane.filtered_indices.is_empty() {
+                pane.scroll = pane.scroll.min(pane.filtered_indices.len() - 1);
+            } else {
+                pane.scroll = 0;
+            }
+        }
+    }
+
+    /// Total number of lines (file lines in browse mode, buffered lines otherwise)
+    pub fn total_lines(&self) -> usize {
+        match &self.browse {
+            Some(browser) => browser.line_count(),
+            None => self.lines.len(),
+        }
+    }
+
     /// Get total and visible line counts
     pub fn line_counts(&self) -> (usize, usize) {
         (
-            self.lines.len(),
+            self.total_lines(),
             self.panes[self.active_pane].filtered_indices.len(),
         )
     }
 
-    /// Get visible lines for a specific pane
+    /// Get visible lines for a specific pane, paired with their line index
     pub fn visible_lines_for_pane(
         &mut self,
         pane_idx: usize,
@@ -1628,29 +2958,43 @@ impl<'a> AppState<'a> {
         }
 
         if height_changed && self.panes[pane_idx].stick_to_bottom {
-            self.panes[pane_idx].scroll = self.panes[pane_idx]
-                .filtered_indices
-                .len()
-                .saturating_sub(self.panes[pane_idx].viewport_height);
+            self.panes[pane_idx].scroll =
+                self.panes[pane_idx].bottom_scroll(|i| event_lines(&self.lines, i));
         }
 
         let start = self.panes[pane_idx].scroll;
         let end = (start + height).min(self.panes[pane_idx].filtered_indices.len());
+        let window = &self.panes[pane_idx].filtered_indices[start..end];
+
+        if let Some(browser) = &self.browse {
+            self.browse_page = window
+                .iter()
+                .map(|&line_idx| {
+                    let mut line = LogLine::new(browser.read_line(line_idx).unwrap_or_default());
+                    self.parsers.apply(&mut line);
+                    line
+                })
+                .collect();
+            return window
+                .iter()
+                .copied()
+                .zip(self.browse_page.iter())
+                .collect();
+        }
 
-        self.panes[pane_idx].filtered_indices[start..end]
+        window
             .iter()
-            .enumerate()
-            .filter_map(|(i, &line_idx)| self.lines.get(line_idx).map(|line| (start + i, line)))
+            .filter_map(|&line_idx| self.lines.get(line_idx).map(|line| (line_idx, line)))
             .collect()
     }
 
     /// Get line counts for a specific pane
     pub fn line_counts_for_pane(&self, pane_idx: usize) -> (usize, usize) {
         if pane_idx >= self.panes.len() {
-            return (self.lines.len(), 0);
+            return (self.total_lines(), 0);
         }
         (
-            self.lines.len(),
+            self.total_lines(),
             self.panes[pane_idx].filtered_indices.len(),
         )
     }
@@ -1659,6 +3003,11 @@ impl<'a> AppState<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::MultilineConfig;
+
+    fn ended(state: &AppState, source_id: usize) -> bool {
+        state.source_health[source_id].status(Instant::now()) == SourceStatus::Ended
+    }
 
     // LogLevel::detect() tests
 
@@ -1836,4 +3185,530 @@ mod tests {
         let line_not_json = LogLine::new("plain text".to_string());
         assert!(!line_not_json.is_json);
     }
+
+    #[test]
+    fn test_logline_uses_json_fields() {
+        // A key named "error_count" must not make this an error line
+        let line = LogLine::new(
+            r#"{"ts":"2024-03-01T12:00:00Z","level":"info","msg":"done","error_count":0}"#
+                .to_string(),
+        );
+        assert_eq!(line.level, LogLevel::Info);
+        assert_eq!(line.timestamp.unwrap().timestamp(), 1_709_294_400);
+        assert_eq!(parse::field(&line.fields, "error_count"), Some("0"));
+
+        // Without a level field, only the message is inspected
+        let line = LogLine::new(r#"{"msg":"upstream WARN: slow","error":null}"#.to_string());
+        assert_eq!(line.level, LogLevel::Warn);
+        let line = LogLine::new(r#"{"error_rate":0.1}"#.to_string());
+        assert_eq!(line.level, LogLevel::None);
+
+        assert!(LogLine::new("ERROR plain".to_string()).fields.is_empty());
+    }
+
+    #[test]
+    fn test_logline_parses_logfmt() {
+        let line = LogLine::new(
+            r#"ts=2024-03-01T12:00:00Z level=debug msg="cache miss for error page" dur=3ms"#
+                .to_string(),
+        );
+        assert!(line.is_logfmt);
+        assert!(!line.is_json);
+        assert_eq!(line.level, LogLevel::Debug);
+        assert_eq!(line.timestamp.unwrap().timestamp(), 1_709_294_400);
+        assert_eq!(parse::field(&line.fields, "dur"), Some("3ms"));
+
+        let prose = LogLine::new("ERROR retry failed attempt=3 max=3".to_string());
+        assert!(!prose.is_logfmt);
+        assert_eq!(prose.level, LogLevel::Error);
+    }
+
+    #[test]
+    fn test_picker_scope_chooser_switches_context_and_namespace() {
+        let mut picker = PickerState::default();
+        picker.open(PickerMode::K8s);
+        // Not while pods are still loading
+        picker.open_scope_chooser(ScopeKind::Context);
+        assert!(picker.scope_chooser.is_none());
+
+        picker.set_sources(Vec::new(), &[]);
+        picker.open_scope_chooser(ScopeKind::Context);
+        picker.set_scope_choices(Ok((
+            vec!["staging".to_string(), "prod".to_string()],
+            Some("staging".to_string()),
+        )));
+        let chooser = picker.scope_chooser.as_ref().unwrap();
+        assert_eq!(chooser.current, Some(0));
+        assert_eq!(chooser.selected, 0);
+
+        picker.down();
+        picker.choose_scope();
+        assert_eq!(picker.k8s_context.as_deref(), Some("prod"));
+        assert!(picker.loading && picker.scope_chooser.is_none());
+
+        // Namespaces start with "all namespaces"
+        picker.set_sources(Vec::new(), &[]);
+        picker.open_scope_chooser(ScopeKind::Namespace);
+        picker.set_scope_choices(Ok((vec!["payments".to_string()], None)));
+        assert_eq!(picker.scope_chooser.as_ref().unwrap().current, Some(0));
+        picker.down();
+        picker.choose_scope();
+        assert_eq!(
+            picker.k8s_scope(),
+            Scope {
+                context: Some("prod".to_string()),
+                namespace: Some("payments".to_string()),
+            }
+        );
+
+        // The same pod in another cluster is a different source
+        let pod = |context: Option<&str>| DiscoveredSource {
+            name: "api-1".to_string(),
+            source_type: crate::discovery::SourceType::K8s,
+            status: "Running".to_string(),
+            extra: None,
+            namespace: Some("payments".to_string()),
+            context: context.map(str::to_string),
+        };
+        let existing = pod(Some("staging")).k8s_sources();
+        picker.set_sources(vec![pod(Some("staging")), pod(Some("prod"))], &existing);
+        assert_eq!(picker.checked, vec![true, false]);
+    }
+
+    #[test]
+    fn test_column_picker_chooses_table_columns() {
+        let mut state = AppState::new(&Config::default(), Vec::new());
+        state.open_column_picker();
+        assert!(!state.column_picker.visible);
+
+        state.push_lines(vec![
+            LogLine::new("plain".to_string()),
+            LogLine::new(r#"{"ts":"x","level":"warn","svc":"api","msg":"m"}"#.to_string()),
+        ]);
+        assert_eq!(state.table_columns(), vec!["ts", "level", "msg"]);
+
+        state.open_column_picker();
+        assert_eq!(state.column_picker.keys, vec!["ts", "level", "svc", "msg"]);
+        // Add "svc" at the end, drop "level"
+        state.column_picker.selected = 2;
+        state.column_picker.toggle_selected();
+        state.column_picker.selected = 1;
+        state.column_picker.toggle_selected();
+        state.apply_column_picker();
+
+        assert!(state.table_mode);
+        assert_eq!(state.table_columns(), vec!["ts", "msg", "svc"]);
+    }
+
+    #[test]
+    fn test_add_source_is_visible_in_all_panes() {
+        let mut state = AppState::new(&Config::default(), Vec::new());
+        state.split_vertical();
+        let id = state.add_source(LogSourceType::Docker {
+            container: "web".to_string(),
+        });
+
+        assert_eq!(id, 0);
+        assert_eq!(state.source_health.len(), 1);
+        assert!(!ended(&state, id));
+        for pane in &state.panes {
+            assert_eq!(pane.visible_sources, vec![true]);
+        }
+
+        state.set_source_status(id, SourceStatus::Ended);
+        assert!(ended(&state, id));
+    }
+
+    #[test]
+    fn test_watch_restarts_only_sources_it_removed() {
+        let archive = LogSourceType::File {
+            path: PathBuf::from("/var/log/app/old.log.gz"),
+            rotated: false,
+        };
+        let live = LogSourceType::File {
+            path: PathBuf::from("/var/log/app/app.log"),
+            rotated: false,
+        };
+        let mut state = AppState::new(&Config::default(), vec![archive.clone(), live.clone()]);
+
+        // A compressed file ends after its one read; seeing it again is no
+        // reason to read it twice
+        state.set_source_status(0, SourceStatus::Ended);
+        assert_eq!(state.watched_source_added(&archive), None);
+        assert_eq!(state.watched_source_added(&live), None);
+
+        // Deleted and created again: restarted in the same slot, once
+        assert_eq!(state.watched_source_removed(&live), Some(1));
+        assert!(ended(&state, 1));
+        assert_eq!(state.watched_source_added(&live), Some(1));
+        assert!(!ended(&state, 1));
+        assert_eq!(state.watched_source_added(&live), None);
+
+        let new = LogSourceType::File {
+            path: PathBuf::from("/var/log/app/new.log"),
+            rotated: false,
+        };
+        assert_eq!(state.watched_source_added(&new), Some(2));
+    }
+
+    #[test]
+    fn test_context_lines_around_matches() {
+        let lines = |raws: &[&str]| -> Vec<LogLine> {
+            raws.iter()
+                .map(|raw| LogLine::new(raw.to_string()))
+                .collect()
+        };
+        let mut state = AppState::new(&Config::default(), Vec::new());
+        state.push_lines(lines(&[
+            "a", "b", "panic 1", "c", "d", "e", "f", "panic 2", "g",
+        ]));
+        state.panes[0].active_filter = Some(ActiveFilter::new("panic".to_string(), false));
+        state.set_context(1, 1);
+
+        let pane = &state.panes[0];
+        assert_eq!(pane.filtered_indices, vec![1, 2, 3, 6, 7, 8]);
+        let context: Vec<bool> = pane.row_info.iter().map(|row| row.context).collect();
+        assert_eq!(context, vec![true, false, true, true, false, true]);
+        let gaps: Vec<usize> = (0..6).filter(|&pos| pane.row(pos).after_gap).collect();
+        assert_eq!(gaps, vec![3]);
+
+        // n/N land on matches, with the leading context line above them
+        state.panes[0].scroll = 0;
+        state.next_match();
+        assert_eq!(state.panes[0].scroll, 3);
+        assert_eq!(state.status_message.as_deref(), Some("Match 2/2"));
+        state.next_match();
+        assert_eq!(state.panes[0].scroll, 0);
+        state.prev_match();
+        assert_eq!(state.panes[0].scroll, 3);
+
+        // Streaming lines pick up context without a gap when adjacent
+        state.push_lines(lines(&["h", "panic 3", "i", "j"]));
+        let pane = &mut state.panes[0];
+        assert_eq!(pane.filtered_indices, vec![1, 2, 3, 6, 7, 8, 9, 10, 11]);
+        assert!(!pane.row(6).after_gap);
+
+        // The separator takes a row when following the bottom
+        pane.viewport_height = 7;
+        assert_eq!(pane.bottom_scroll(|_| 1), 3);
+
+        state.shrink_context();
+        assert_eq!(state.panes[0].filtered_indices, vec![2, 7, 10]);
+        assert!(
+            state.panes[0]
+                .row_info
+                .iter()
+                .all(|row| !row.context && !row.after_gap)
+        );
+    }
+
+    #[test]
+    fn test_multiline_events_filter_and_fold_as_a_unit() {
+        let config = Config {
+            multiline: vec![MultilineConfig {
+                continuation: Some(r"^(\s|Caused by)".to_string()),
+                start: None,
+                max_lines: 500,
+                sources: "*".to_string(),
+            }],
+            ..Config::default()
+        };
+        let mut state = AppState::new(&config, vec![LogSourceType::Stdin]);
+        state.receive_lines(
+            [
+                "ERROR request failed: java.lang.IllegalStateException: boom",
+                "\tat com.example.Api.handle(Api.java:42)",
+                "Caused by: java.io.IOException: connection reset",
+                "INFO next request",
+            ]
+            .into_iter()
+            .map(|raw| LogLine::new(raw.to_string()))
+            .collect(),
+        );
+        assert_eq!(state.lines.len(), 1);
+        assert_eq!(state.lines[0].level, LogLevel::Error);
+        assert_eq!(event_lines(&state.lines, 0), 3);
+
+        // The last event is released once its source goes quiet
+        let ready = state
+            .events
+            .drain_ready(Instant::now() + Duration::from_secs(1));
+        state.deliver_lines(ready);
+        assert_eq!(state.lines.len(), 2);
+
+        state.panes[0].active_filter = Some(ActiveFilter::new("IOException".to_string(), false));
+        state.recompute_filter();
+        assert_eq!(state.panes[0].filtered_indices, vec![0]);
+        assert!(
+            state
+                .get_current_line_text()
+                .unwrap()
+                .ends_with("connection reset")
+        );
+
+        // Unfolded, the event takes one screen row per line
+        state.panes[0].scroll = 0;
+        state.toggle_fold();
+        assert!(state.panes[0].is_unfolded(0));
+        state.select_line_at_row(2);
+        assert_eq!(state.panes[0].selected_line, Some(0));
+        state.toggle_fold_all();
+        assert!(state.panes[0].is_unfolded(0));
+        state.toggle_fold();
+        assert!(!state.panes[0].is_unfolded(0));
+        state.toggle_fold_all();
+        assert!(!state.panes[0].is_unfolded(0));
+    }
+
+    #[test]
+    fn test_min_level_hides_lines_below_threshold() {
+        let mut state = AppState::new(&Config::default(), Vec::new());
+        state.push_lines(
+            [
+                "INFO started",
+                "WARN disk 90% full",
+                "DEBUG cache hit",
+                "ERROR failed",
+                "plain",
+            ]
+            .into_iter()
+            .map(|raw| LogLine::new(raw.to_string()))
+            .collect(),
+        );
+
+        for _ in 0..4 {
+            state.raise_min_level();
+        }
+        assert_eq!(state.panes[0].min_level, Some(LogLevel::Warn));
+        assert_eq!(state.panes[0].filtered_indices, vec![1, 3]);
+
+        state.raise_min_level();
+        state.raise_min_level();
+        assert_eq!(state.panes[0].min_level, Some(LogLevel::Error));
+        assert_eq!(state.panes[0].filtered_indices, vec![3]);
+
+        for _ in 0..5 {
+            state.lower_min_level();
+        }
+        assert_eq!(state.panes[0].min_level, None);
+        assert_eq!(state.panes[0].filtered_indices.len(), 5);
+    }
+
+    #[test]
+    fn test_filter_stack_includes_excludes_and_saves() {
+        let mut state = AppState::new(&Config::default(), Vec::new());
+        state.push_lines(
+            [
+                "GET /order/1",
+                "GET /healthz order",
+                "GET /metrics",
+                "GET /order/2 metrics",
+            ]
+            .into_iter()
+            .map(|raw| LogLine::new(raw.to_string()))
+            .collect(),
+        );
+
+        for (pattern, exclude) in [("order", false), ("healthz", true), ("metrics", true)] {
+            state.panes[0].active_filter = Some(ActiveFilter::new(pattern.to_string(), false));
+            state.push_filter(exclude);
+        }
+        assert!(state.panes[0].active_filter.is_none());
+        assert_eq!(state.panes[0].filter_stack.len(), 3);
+        assert_eq!(state.panes[0].filtered_indices, vec![0]);
+
+        state.toggle_filter_chip(2);
+        assert_eq!(state.panes[0].filtered_indices, vec![0, 3]);
+
+        state.save_current_filter("orders".to_string());
+        state.clear_filter_stack();
+        assert_eq!(state.panes[0].filtered_indices.len(), 4);
+
+        state.apply_saved_filter(0);
+        assert!(state.panes[0].active_filter.is_none());
+        assert_eq!(state.panes[0].filtered_indices, vec![0, 3]);
+
+        state.pop_filter_chip();
+        assert_eq!(state.panes[0].filtered_indices, vec![0, 3]);
+        state.pop_filter_chip();
+        assert_eq!(state.panes[0].filtered_indices, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_highlight_rules_are_saved_without_losing_config() {
+        let path = std::env::temp_dir().join(format!("bark-hl-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "max_lines = 123\ntable_columns = [\"ts\", \"msg\"]\n",
+        )
+        .unwrap();
+        let mut state = AppState::new(&Config::default(), Vec::new());
+        state.config_path = Some(path.clone());
+        state.push_lines(vec![LogLine::new("login user_id=42".to_string())]);
+
+        state.panes[0].active_filter = Some(ActiveFilter::new("user_id=42".to_string(), false));
+        state.highlight_active_filter();
+        assert!(state.panes[0].active_filter.is_none());
+        assert_eq!(state.highlights.len(), 1);
+        let ranges = state.get_highlight_ranges("login user_id=42");
+        assert_eq!((ranges[0].0.start, ranges[0].0.end), (6, 16));
+
+        state.toggle_selected_highlight();
+        assert!(state.get_highlight_ranges("login user_id=42").is_empty());
+        state.save_settings();
+
+        let saved = Config::load_file(&path).unwrap();
+        assert_eq!(saved.max_lines, 123);
+        assert_eq!(saved.table_columns, vec!["ts", "msg"]);
+        assert_eq!(saved.highlights.len(), 1);
+        assert_eq!(saved.highlights[0].pattern, "user_id=42");
+        assert!(!saved.highlights[0].enabled);
+
+        state.remove_selected_highlight();
+        assert!(Config::load_file(&path).unwrap().highlights.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_saved_filters_persist_and_predefined_filters_follow_sources() {
+        let filter = |name: &str, sources: &str| FilterConfig {
+            name: name.to_string(),
+            pattern: "level>=warn".to_string(),
+            regex: false,
+            exclude: vec!["healthz".to_string()],
+            sources: sources.to_string(),
+        };
+        let config = Config {
+            filters: vec![filter("problems", "*"), filter("api", "docker:api-*")],
+            ..Config::default()
+        };
+        let mut state = AppState::new(&config, vec![LogSourceType::Stdin]);
+        let names = |state: &AppState| -> Vec<String> {
+            state.saved_filters.iter().map(|f| f.name.clone()).collect()
+        };
+        assert_eq!(names(&state), vec!["problems"]);
+        assert!(state.saved_filters[0].stack[0].exclude);
+
+        let path = std::env::temp_dir().join(format!("bark-state-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        state.restore_session(path.clone());
+        state.panes[0].active_filter = Some(ActiveFilter::new("timeout".to_string(), false));
+        state.add_to_filter_history("timeout".to_string());
+        state.save_current_filter("timeouts".to_string());
+
+        state.add_source(LogSourceType::Docker {
+            container: "api-1".to_string(),
+        });
+        assert_eq!(names(&state), vec!["problems", "api", "timeouts"]);
+
+        state.selected_filter_idx = 0;
+        state.delete_saved_filter();
+        assert_eq!(state.saved_filters.len(), 3);
+
+        let mut restored = AppState::new(&Config::default(), Vec::new());
+        restored.restore_session(path.clone());
+        assert_eq!(names(&restored), vec!["timeouts"]);
+        assert_eq!(restored.filter_history, vec!["timeout"]);
+
+        restored.delete_saved_filter();
+        let mut emptied = AppState::new(&Config::default(), Vec::new());
+        emptied.restore_session(path.clone());
+        assert!(emptied.saved_filters.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_receive_lines_merges_sources_by_timestamp() {
+        let config = Config {
+            merge_window_ms: 0,
+            ..Config::default()
+        };
+        let sources = vec![
+            LogSourceType::Docker {
+                container: "web".to_string(),
+            },
+            LogSourceType::Stdin,
+        ];
+        let mut state = AppState::new(&config, sources);
+        let at = |s: i64| Local.timestamp_opt(1_700_000_000 + s, 0).unwrap();
+        let line = |id, text: &str, s| {
+            LogLine::new(text.to_string())
+                .with_source_id(id)
+                .with_timestamp(at(s))
+        };
+
+        state.receive_lines(vec![line(0, "second", 2), line(1, "first", 1)]);
+        assert!(state.lines.is_empty());
+        state.tick_merge();
+        let raw: Vec<&str> = state.lines.iter().map(|l| l.raw.as_str()).collect();
+        assert_eq!(raw, vec!["first", "second"]);
+
+        // Arrival order appends immediately
+        state.toggle_merge_order();
+        state.receive_lines(vec![line(0, "late", 0)]);
+        assert_eq!(state.lines.back().unwrap().raw, "late");
+    }
+
+    // Browse mode tests
+
+    fn browse_state(name: &str, content: &str) -> (AppState<'static>, std::path::PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("bark_app_browse_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        let mut state = AppState::new(
+            &Config::default(),
+            vec![LogSourceType::File {
+                path: path.clone(),
+                rotated: false,
+            }],
+        );
+        state.start_browse(FileBrowser::open(&path).unwrap());
+        while state.browse_in_progress() {
+            state.tick_browse();
+        }
+        (state, path)
+    }
+
+    #[test]
+    fn test_browse_reads_whole_file_beyond_max_lines() {
+        let content: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        let (mut state, path) = browse_state("whole", &content);
+        state.max_lines = 10;
+
+        assert_eq!(state.line_counts(), (50, 50));
+        assert!(state.lines.is_empty());
+        assert_eq!(state.get_current_line_text().as_deref(), Some("line 0"));
+
+        state.go_to_bottom();
+        assert_eq!(state.get_current_line_text().as_deref(), Some("line 49"));
+
+        let visible = state.visible_lines_for_pane(0, 5);
+        assert_eq!(visible.len(), 5);
+        assert_eq!(visible[4].0, 49);
+        assert_eq!(visible[4].1.raw, "line 49");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_browse_filter_and_matches() {
+        let (mut state, path) = browse_state(
+            "filter",
+            "INFO start\nERROR one\nINFO mid\nERROR two\nINFO end\n",
+        );
+
+        state.panes[0].active_filter = Some(ActiveFilter::new("ERROR".to_string(), false));
+        state.recompute_filter();
+        while state.browse_in_progress() {
+            state.tick_browse();
+        }
+        assert_eq!(state.panes[0].filtered_indices, vec![1, 3]);
+
+        state.next_match();
+        assert_eq!(state.get_current_line_text().as_deref(), Some("ERROR two"));
+        state.next_match();
+        assert_eq!(state.get_current_line_text().as_deref(), Some("ERROR one"));
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -0,0 +1,285 @@
+//! Whole-file browsing backed by a line offset index.
+//!
+//! `FileBrowser` indexes the byte offset of every line in a file so any line
+//! can be read on demand. Nothing is loaded into the ring buffer: the index is
+//! built incrementally (a bounded number of bytes per frame) and lines are
+//! read from disk only when they are displayed, filtered or exported.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::sources::tail::decode_line;
+
+/// Maximum bytes indexed per call to `index_step`
+pub const INDEX_BYTES_PER_STEP: u64 = 8 * 1024 * 1024;
+
+/// Maximum bytes read for a single line (longer lines are cut off)
+const MAX_LINE_BYTES: u64 = 1024 * 1024;
+
+/// Result of advancing the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexUpdate {
+    /// No new complete lines
+    Unchanged,
+    /// This many new lines were indexed
+    Grew(usize),
+    /// The file shrank (truncated); the index was rebuilt from scratch
+    Reset,
+}
+
+/// Random access to the lines of a (possibly huge) file
+pub struct FileBrowser {
+    path: PathBuf,
+    file: File,
+    /// Start offset of each indexed line, plus the end offset of the last one
+    starts: Vec<u64>,
+    /// Size of the file when last checked
+    file_len: u64,
+    /// Bytes examined so far, including an unterminated trailing line
+    scanned_len: u64,
+}
+
+impl FileBrowser {
+    /// Open a file for browsing. The index starts empty; call `index_step`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            starts: vec![0],
+            file_len,
+            scanned_len: 0,
+        })
+    }
+
+    /// Number of complete lines indexed so far
+    pub fn line_count(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// Bytes covered by the index
+    fn indexed_len(&self) -> u64 {
+        *self.starts.last().unwrap_or(&0)
+    }
+
+    /// Whether the whole file (as of the last check) has been indexed
+    pub fn is_fully_indexed(&self) -> bool {
+        self.scanned_len >= self.file_len
+    }
+
+    /// Indexing progress in percent
+    pub fn index_progress(&self) -> u8 {
+        if self.file_len == 0 {
+            return 100;
+        }
+        ((self.scanned_len as f64 / self.file_len as f64) * 100.0).min(100.0) as u8
+    }
+
+    /// Index up to `budget` more bytes, picking up growth and truncation
+    pub fn index_step(&mut self, budget: u64) -> io::Result<IndexUpdate> {
+        let meta = std::fs::metadata(&self.path)?;
+        let mut reset = false;
+        if meta.len() < self.scanned_len {
+            // Truncated: reopen and index from the beginning
+            self.file = File::open(&self.path)?;
+            self.starts = vec![0];
+            self.scanned_len = 0;
+            reset = true;
+        }
+        self.file_len = meta.len();
+
+        // Bytes between the last newline and `scanned_len` hold no newline,
+        // so scanning resumes where it left off
+        let start = self.scanned_len.max(self.indexed_len());
+        if start >= self.file_len {
+            return Ok(if reset {
+                IndexUpdate::Reset
+            } else {
+                IndexUpdate::Unchanged
+            });
+        }
+
+        let to_read = (self.file_len - start).min(budget);
+        let mut buf = vec![0u8; to_read as usize];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut buf)?;
+
+        let before = self.line_count();
+        for (i, &byte) in buf.iter().enumerate() {
+            if byte == b'\n' {
+                self.starts.push(start + i as u64 + 1);
+            }
+        }
+        let added = self.line_count() - before;
+        self.scanned_len = start + to_read;
+
+        Ok(if reset {
+            IndexUpdate::Reset
+        } else if added > 0 {
+            IndexUpdate::Grew(added)
+        } else {
+            IndexUpdate::Unchanged
+        })
+    }
+
+    /// Read a single line by index
+    pub fn read_line(&self, idx: usize) -> Option<String> {
+        let start = *self.starts.get(idx)?;
+        let end = *self.starts.get(idx + 1)?;
+        let len = (end - start).min(MAX_LINE_BYTES);
+        let mut buf = vec![0u8; len as usize];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(start)).ok()?;
+        file.read_exact(&mut buf).ok()?;
+        Some(decode_line(strip_newline(&buf)))
+    }
+
+    /// Read a contiguous range of lines with a single sequential read
+    pub fn read_range(&self, from: usize, to: usize) -> io::Result<Vec<String>> {
+        let to = to.min(self.line_count());
+        if from >= to {
+            return Ok(Vec::new());
+        }
+        let base = self.starts[from];
+        let end = self.starts[to];
+        let mut buf = vec![0u8; (end - base) as usize];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(base))?;
+        file.read_exact(&mut buf)?;
+
+        Ok((from..to)
+            .map(|i| {
+                let s = (self.starts[i] - base) as usize;
+                let e = (self.starts[i + 1] - base) as usize;
+                let line = strip_newline(&buf[s..e]);
+                let line = &line[..line.len().min(MAX_LINE_BYTES as usize)];
+                decode_line(line)
+            })
+            .collect())
+    }
+
+    /// Pick the end of a range starting at `from` that stays within `max_bytes`
+    /// (always at least one line)
+    pub fn range_end_within(&self, from: usize, max_lines: usize, max_bytes: u64) -> usize {
+        let count = self.line_count();
+        if from >= count {
+            return from;
+        }
+        let limit = self.starts[from] + max_bytes;
+        let mut end = (from + max_lines).min(count);
+        while end > from + 1 && self.starts[end] > limit {
+            end = from + (end - from) / 2;
+        }
+        end
+    }
+}
+
+fn strip_newline(bytes: &[u8]) -> &[u8] {
+    bytes.strip_suffix(b"\n").unwrap_or(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("bark_browse_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn index_all(browser: &mut FileBrowser) {
+        while !browser.is_fully_indexed() {
+            browser.index_step(INDEX_BYTES_PER_STEP).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_index_and_read_lines() {
+        let path = temp_file("read", "alpha\nbeta\r\ngamma\n");
+        let mut browser = FileBrowser::open(&path).unwrap();
+        index_all(&mut browser);
+
+        assert_eq!(browser.line_count(), 3);
+        assert_eq!(browser.read_line(1).as_deref(), Some("beta"));
+        assert_eq!(browser.read_line(3), None);
+        assert_eq!(
+            browser.read_range(0, 10).unwrap(),
+            vec!["alpha", "beta", "gamma"]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_index_in_small_steps() {
+        let path = temp_file("steps", "one\ntwo\nthree\nfour\n");
+        let mut browser = FileBrowser::open(&path).unwrap();
+        let mut steps = 0;
+        while !browser.is_fully_indexed() {
+            browser.index_step(5).unwrap();
+            steps += 1;
+        }
+        assert!(steps > 1);
+        assert_eq!(browser.line_count(), 4);
+        assert_eq!(browser.read_line(3).as_deref(), Some("four"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_unterminated_last_line_waits_for_newline() {
+        let path = temp_file("partial", "done\npart");
+        let mut browser = FileBrowser::open(&path).unwrap();
+        index_all(&mut browser);
+        assert_eq!(browser.line_count(), 1);
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        f.write_all(b"ial\n").unwrap();
+        assert_eq!(
+            browser.index_step(INDEX_BYTES_PER_STEP).unwrap(),
+            IndexUpdate::Grew(1)
+        );
+        assert_eq!(browser.read_line(1).as_deref(), Some("partial"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_truncation_resets_index() {
+        let path = temp_file("reset", "a\nb\nc\n");
+        let mut browser = FileBrowser::open(&path).unwrap();
+        index_all(&mut browser);
+
+        std::fs::write(&path, "x\n").unwrap();
+        assert_eq!(
+            browser.index_step(INDEX_BYTES_PER_STEP).unwrap(),
+            IndexUpdate::Reset
+        );
+        assert_eq!(browser.line_count(), 1);
+        assert_eq!(browser.read_line(0).as_deref(), Some("x"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_range_end_within_byte_limit() {
+        let path = temp_file("range", "aaaa\nbbbb\ncccc\ndddd\n");
+        let mut browser = FileBrowser::open(&path).unwrap();
+        index_all(&mut browser);
+
+        assert_eq!(browser.range_end_within(0, 100, 1024), 4);
+        assert_eq!(browser.range_end_within(0, 2, 1024), 2);
+        // Byte limit smaller than a line still yields one line
+        assert_eq!(browser.range_end_within(1, 100, 1), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -7,7 +7,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::theme::Theme;
 
@@ -17,6 +17,36 @@ pub const DEFAULT_CHANNEL_BUFFER: usize = 1000;
 /// Default number of lines to tail from sources
 pub const DEFAULT_TAIL_LINES: &str = "1000";
 
+/// How often local files are checked for new data, in milliseconds
+pub const FILE_POLL_INTERVAL_MS: u64 = 250;
+
+/// How often glob and directory sources are checked for new files, in milliseconds
+pub const WATCH_POLL_INTERVAL_MS: u64 = 1000;
+
+/// How often Kubernetes label selectors are checked for new pods, in milliseconds
+pub const POD_WATCH_POLL_INTERVAL_MS: u64 = 3000;
+
+/// How long to wait before resubscribing to Docker events, in milliseconds
+pub const DOCKER_EVENTS_RETRY_MS: u64 = 3000;
+
+/// Seconds without data before a source is shown as stalled
+pub const SOURCE_STALL_SECS: u64 = 60;
+
+/// Errors kept per source for the detail view
+pub const SOURCE_ERROR_HISTORY: usize = 50;
+
+/// Lines tested against filters per frame while browsing a whole file
+pub const BROWSE_SCAN_LINES_PER_TICK: usize = 50_000;
+
+/// Widest a table column gets before its values are truncated
+pub const TABLE_COLUMN_MAX_WIDTH: usize = 40;
+
+/// Recent lines scanned for field names (column picker and default columns)
+pub const COLUMN_SCAN_LINES: usize = 500;
+
+/// How long a multi-line event waits for more lines before it's shown, in milliseconds
+pub const EVENT_IDLE_MS: u64 = 300;
+
 /// Filter input debounce delay in milliseconds
 pub const FILTER_DEBOUNCE_MS: u128 = 150;
 
@@ -42,6 +72,107 @@ pub struct Config {
     /// SSH StrictHostKeyChecking mode: "yes" (default, strict), "accept-new", or "no"
     /// WARNING: "accept-new" and "no" are insecure and vulnerable to MITM attacks
     pub ssh_host_key_checking: String,
+    /// Order lines from multiple sources by timestamp instead of arrival
+    pub merge_by_timestamp: bool,
+    /// How long lines are held back for timestamp ordering, in milliseconds
+    pub merge_window_ms: u64,
+    /// Fields shown as table columns, e.g. ["ts", "level", "svc", "msg"]
+    /// (empty picks timestamp, level and message fields automatically)
+    pub table_columns: Vec<String>,
+    /// Regex parsers for formats bark doesn't recognize on its own
+    pub parsers: Vec<ParserConfig>,
+    /// Patterns colored on every line, whatever the filter
+    pub highlights: Vec<HighlightConfig>,
+    /// Filters listed under Saved Filters from the start
+    pub filters: Vec<FilterConfig>,
+    /// Rules joining stack traces and other multi-line output into events
+    pub multiline: Vec<MultilineConfig>,
+}
+
+/// A user-defined line parser (`[[parsers]]` in the config file)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParserConfig {
+    /// Name shown in errors
+    #[serde(default)]
+    pub name: String,
+    /// Regex with named groups; `ts`, `level` and `msg` have special meaning,
+    /// any other group becomes a field
+    pub pattern: String,
+    /// chrono format of the `ts` group, e.g. "%d/%m/%Y %H:%M:%S%.3f"
+    #[serde(default)]
+    pub timestamp_format: Option<String>,
+    /// Glob matched against the source name (or file path) the parser applies to
+    #[serde(default = "default_sources")]
+    pub sources: String,
+}
+
+fn default_sources() -> String {
+    "*".to_string()
+}
+
+/// A predefined saved filter (`[[filters]]` in the config file)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FilterConfig {
+    pub name: String,
+    /// Filter text (substring, query, or regex when `regex` is set)
+    #[serde(default)]
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    /// Substrings whose lines are hidden, pinned as exclude filters
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Glob of source names (or file paths); the filter is listed while a
+    /// matching source is open
+    #[serde(default = "default_sources")]
+    pub sources: String,
+}
+
+/// A rule joining lines into multi-line events (`[[multiline]]` in the
+/// config file). Set either `continuation` or `start`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultilineConfig {
+    /// Regex of lines that belong to the event before them,
+    /// e.g. `^(\s|Caused by)` for Java stack traces
+    #[serde(default)]
+    pub continuation: Option<String>,
+    /// Regex of lines that begin an event; any other line continues the
+    /// previous one, e.g. `^\d{4}-\d\d-\d\d` for timestamped logs
+    #[serde(default)]
+    pub start: Option<String>,
+    /// Longest event in lines; longer output is split into several events
+    #[serde(default = "default_event_max_lines")]
+    pub max_lines: usize,
+    /// Glob of source names (or file paths) the rule applies to
+    #[serde(default = "default_sources")]
+    pub sources: String,
+}
+
+fn default_event_max_lines() -> usize {
+    500
+}
+
+/// A highlight rule (`[[highlights]]` in the config file)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighlightConfig {
+    /// Text to highlight (case-insensitive), or a regex when `regex` is set
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    /// Theme color ("error", "warn", "info", "debug", "trace", "match", "key",
+    /// "value"), color name ("magenta") or "#rrggbb"; empty uses "match"
+    #[serde(default)]
+    pub color: String,
+    /// Modifiers joined with "+": "bold", "italic", "underline", "reverse"
+    #[serde(default)]
+    pub style: String,
+    /// Disabled rules are kept but not drawn
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -54,6 +185,13 @@ impl Default for Config {
             export_dir: "/tmp".to_string(),
             theme: "default".to_string(),
             ssh_host_key_checking: "yes".to_string(),
+            merge_by_timestamp: true,
+            merge_window_ms: 500,
+            table_columns: Vec::new(),
+            parsers: Vec::new(),
+            highlights: Vec::new(),
+            filters: Vec::new(),
+            multiline: Vec::new(),
         }
     }
 }
@@ -70,13 +208,9 @@ impl Config {
 
         // Try to load from config file
         if let Some(path) = Self::config_path() {
-            if path.exists() {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    match toml::from_str::<Config>(&content) {
-                        Ok(file_config) => config = file_config,
-                        Err(e) => eprintln!("Warning: Invalid config at {}: {}", path.display(), e),
-                    }
-                }
+            match Self::load_file(&path) {
+                Ok(file_config) => config = file_config,
+                Err(e) => eprintln!("Warning: Invalid config at {}: {}", path.display(), e),
             }
         }
 
@@ -101,6 +235,21 @@ impl Config {
         if let Ok(val) = std::env::var("BARK_THEME") {
             config.theme = val;
         }
+        if let Ok(val) = std::env::var("BARK_MERGE_BY_TIMESTAMP") {
+            config.merge_by_timestamp = val == "1" || val.to_lowercase() == "true";
+        }
+        if let Ok(val) = std::env::var("BARK_MERGE_WINDOW_MS") {
+            if let Ok(ms) = val.parse() {
+                config.merge_window_ms = ms;
+            }
+        }
+        if let Ok(val) = std::env::var("BARK_TABLE_COLUMNS") {
+            config.table_columns = val
+                .split(',')
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect();
+        }
         if let Ok(val) = std::env::var("BARK_SSH_HOST_KEY_CHECKING") {
             // Validate the value
             let val_lower = val.to_lowercase();
@@ -122,23 +271,75 @@ impl Config {
         Theme::by_name(&self.theme)
     }
 
-    /// Save configuration to file (future feature)
-    #[allow(dead_code)]
-    pub fn save(&self) -> Result<(), String> {
-        let path = Self::config_path()
-            .ok_or_else(|| "Could not determine config directory".to_string())?;
+    /// Where saved filters and filter history are kept between runs
+    pub fn state_path() -> Option<PathBuf> {
+        Self::config_path().map(|p| p.with_file_name("state.json"))
+    }
 
+    /// Read a config file without environment overrides (defaults if it
+    /// doesn't exist)
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        toml::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    /// Save configuration to a file
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
         // Create config directory if it doesn't exist
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
 
         let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(&path, content).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())?;
 
         Ok(())
     }
 
+    /// Replace the `[[highlights]]` rules in a config file, leaving the rest
+    /// of it, comments and formatting included, as written
+    pub fn save_highlights_to(path: &Path, highlights: &[HighlightConfig]) -> Result<(), String> {
+        let content = if path.exists() {
+            fs::read_to_string(path).map_err(|e| e.to_string())?
+        } else {
+            String::new()
+        };
+        let mut doc: toml_edit::DocumentMut = content.parse().map_err(|e| format!("{}", e))?;
+
+        // Only what differs from the defaults, like a rule written by hand
+        let mut rules = toml_edit::ArrayOfTables::new();
+        for rule in highlights {
+            let mut table = toml_edit::Table::new();
+            table["pattern"] = toml_edit::value(rule.pattern.as_str());
+            if rule.regex {
+                table["regex"] = toml_edit::value(true);
+            }
+            if !rule.color.is_empty() {
+                table["color"] = toml_edit::value(rule.color.as_str());
+            }
+            if !rule.style.is_empty() {
+                table["style"] = toml_edit::value(rule.style.as_str());
+            }
+            if !rule.enabled {
+                table["enabled"] = toml_edit::value(false);
+            }
+            rules.push(table);
+        }
+        if rules.is_empty() {
+            doc.remove("highlights");
+        } else {
+            doc["highlights"] = toml_edit::Item::ArrayOfTables(rules);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, doc.to_string()).map_err(|e| e.to_string())
+    }
+
     /// Legacy function for compatibility
     pub fn from_env() -> Self {
         Self::load()
@@ -180,6 +381,84 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_highlights_deserialization_and_round_trip() {
+        let config: Config = toml::from_str(
+            r#"
+            [[highlights]]
+            pattern = "user_id=42"
+            color = "magenta"
+            style = "bold+underline"
+
+            [[highlights]]
+            pattern = 'time(d )?out'
+            regex = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.highlights.len(), 2);
+        assert_eq!(config.highlights[0].color, "magenta");
+        assert!(!config.highlights[0].regex);
+        assert!(config.highlights[1].regex);
+        assert!(config.highlights[1].enabled);
+        assert_eq!(config.highlights[1].color, "");
+
+        let path = std::env::temp_dir().join(format!("bark-config-{}.toml", std::process::id()));
+        config.save_to(&path).unwrap();
+        let loaded = Config::load_file(&path).unwrap();
+        assert_eq!(loaded.highlights, config.highlights);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_filters_deserialization() {
+        let config: Config = toml::from_str(
+            r#"
+            [[filters]]
+            name = "api errors"
+            pattern = "level>=error"
+            exclude = ["healthz"]
+            sources = "docker:api-*"
+
+            [[filters]]
+            name = "slow"
+            pattern = 'took \d{4,}ms'
+            regex = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.filters.len(), 2);
+        assert_eq!(config.filters[0].exclude, vec!["healthz"]);
+        assert_eq!(config.filters[0].sources, "docker:api-*");
+        assert!(config.filters[1].regex);
+        assert_eq!(config.filters[1].sources, "*");
+    }
+
+    #[test]
+    fn test_multiline_deserialization() {
+        let config: Config = toml::from_str(
+            r#"
+            [[multiline]]
+            continuation = '^(\s|Caused by)'
+            sources = "docker:billing-*"
+
+            [[multiline]]
+            start = '^\d{4}-'
+            max_lines = 50
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.multiline.len(), 2);
+        assert_eq!(
+            config.multiline[0].continuation.as_deref(),
+            Some(r"^(\s|Caused by)")
+        );
+        assert_eq!(config.multiline[0].max_lines, 500);
+        assert!(config.multiline[1].continuation.is_none());
+        assert_eq!(config.multiline[1].max_lines, 50);
+        assert_eq!(config.multiline[1].sources, "*");
+    }
+
     #[test]
     fn test_config_path_is_some() {
         // On most systems, config_path should return Some
@@ -231,6 +510,30 @@ mod tests {
         assert_eq!(config.theme, "default");
     }
 
+    #[test]
+    fn test_parsers_deserialization() {
+        let toml_str = r#"
+            [[parsers]]
+            name = "legacy"
+            pattern = '^(?P<ts>\S+ \S+) <(?P<level>\w+)> (?P<msg>.*)$'
+            timestamp_format = "%d.%m.%Y %H:%M:%S"
+            sources = "docker:legacy-*"
+
+            [[parsers]]
+            pattern = '^(?P<msg>.*)$'
+        "#;
+        let config: Config = toml::from_str(toml_str).expect("deserialization should work");
+        assert_eq!(config.parsers.len(), 2);
+        assert_eq!(config.parsers[0].sources, "docker:legacy-*");
+        assert_eq!(
+            config.parsers[0].timestamp_format.as_deref(),
+            Some("%d.%m.%Y %H:%M:%S")
+        );
+        // Unnamed parsers apply to every source
+        assert_eq!(config.parsers[1].sources, "*");
+        assert!(config.parsers[1].timestamp_format.is_none());
+    }
+
     #[test]
     fn test_ssh_host_key_checking_default() {
         let config = Config::default();
@@ -246,4 +549,32 @@ mod tests {
         let config: Config = toml::from_str(toml_str).expect("deserialization should work");
         assert_eq!(config.ssh_host_key_checking, "accept-new");
     }
+
+    #[test]
+    fn test_save_highlights_keeps_the_rest_of_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("bark_highlights_{}.toml", std::process::id()));
+        let written = "# My settings\ntheme = \"nord\"   # easy on the eyes\n\n[[highlights]]\npattern = \"old\"\n";
+        fs::write(&path, written).unwrap();
+
+        let rule = HighlightConfig {
+            pattern: "timeout".to_string(),
+            regex: false,
+            color: "warn".to_string(),
+            style: String::new(),
+            enabled: false,
+        };
+        Config::save_highlights_to(&path, std::slice::from_ref(&rule)).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("# My settings\ntheme = \"nord\"   # easy on the eyes\n"));
+        assert!(!saved.contains("old"));
+        // Defaults aren't spelled out
+        assert!(!saved.contains("max_lines"));
+        assert!(!saved.contains("style"));
+        assert_eq!(Config::load_file(&path).unwrap().highlights, vec![rule]);
+
+        Config::save_highlights_to(&path, &[]).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("highlights"));
+        let _ = fs::remove_file(&path);
+    }
 }
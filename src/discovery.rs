@@ -2,13 +2,16 @@ use std::process::Command;
 
 use anyhow::Result;
 
+use crate::sources::LogSourceType;
+use crate::sources::docker_api::{self, DockerHost};
+use crate::sources::k8s::Scope;
+
 /// Information about a discovered container or pod
 #[derive(Debug, Clone)]
 pub struct DiscoveredSource {
     /// Name of the container/pod
     pub name: String,
     /// Type of source (for display)
-    #[allow(dead_code)]
     pub source_type: SourceType,
     /// Current status (running, stopped, etc.)
     pub status: String,
@@ -16,6 +19,45 @@ pub struct DiscoveredSource {
     pub extra: Option<String>,
     /// Namespace (for K8s pods)
     pub namespace: Option<String>,
+    /// kubeconfig context the pod was found in, if not the current one
+    pub context: Option<String>,
+}
+
+impl DiscoveredSource {
+    /// Where a discovered pod lives
+    pub fn scope(&self) -> Scope {
+        Scope {
+            context: self.context.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+
+    /// Sources for a discovered pod (see `k8s_pod_sources`)
+    pub fn k8s_sources(&self) -> Vec<LogSourceType> {
+        k8s_pod_sources(&self.name, &self.scope(), self.extra.as_deref())
+    }
+}
+
+/// Sources for a pod given its comma-separated container names: one per
+/// container when it has several, so sidecars can be shown and hidden on
+/// their own
+pub fn k8s_pod_sources(pod: &str, scope: &Scope, containers: Option<&str>) -> Vec<LogSourceType> {
+    let containers: Vec<&str> = containers
+        .map(|names| names.split(',').filter(|c| !c.is_empty()).collect())
+        .unwrap_or_default();
+    let source = |container: Option<&str>| LogSourceType::K8s {
+        pod: pod.to_string(),
+        context: scope.context.clone(),
+        namespace: scope.namespace.clone(),
+        container: container.map(str::to_string),
+        init: false,
+        previous: false,
+    };
+    if containers.len() > 1 {
+        containers.into_iter().map(|c| source(Some(c))).collect()
+    } else {
+        vec![source(None)]
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,8 +75,26 @@ impl std::fmt::Display for SourceType {
     }
 }
 
-/// Discover running Docker containers
+/// Discover running Docker containers, asking the daemon directly when its
+/// socket is reachable and `docker ps` otherwise
 pub fn discover_docker_containers() -> Result<Vec<DiscoveredSource>> {
+    if let Some(host) = DockerHost::from_env() {
+        // The CLI may still get through (e.g. a context with its own host)
+        if let Ok(containers) = docker_api::list_containers(&host) {
+            return Ok(containers
+                .into_iter()
+                .map(|container| DiscoveredSource {
+                    name: container.name,
+                    source_type: SourceType::Docker,
+                    status: container.status,
+                    extra: Some(container.image),
+                    namespace: None,
+                    context: None,
+                })
+                .collect());
+        }
+    }
+
     let output = Command::new("docker")
         .args(["ps", "--format", "{{.Names}}\t{{.Status}}\t{{.Image}}"])
         .output()?;
@@ -57,6 +117,7 @@ pub fn discover_docker_containers() -> Result<Vec<DiscoveredSource>> {
                     status: parts[1].to_string(),
                     extra: parts.get(2).map(|s| s.to_string()),
                     namespace: None, // Docker doesn't have namespaces
+                    context: None,
                 })
             } else {
                 None
@@ -67,9 +128,14 @@ pub fn discover_docker_containers() -> Result<Vec<DiscoveredSource>> {
     Ok(sources)
 }
 
-/// Discover Kubernetes pods
-pub fn discover_k8s_pods(namespace: Option<&str>) -> Result<Vec<DiscoveredSource>> {
+/// Discover Kubernetes pods in a namespace, or in all of them if the scope
+/// names none
+pub fn discover_k8s_pods(scope: &Scope) -> Result<Vec<DiscoveredSource>> {
     let mut cmd = Command::new("kubectl");
+    if let Some(context) = &scope.context {
+        cmd.arg("--context").arg(context);
+    }
+    let namespace = scope.namespace.as_deref();
 
     // Include NAMESPACE in output when querying all namespaces
     let all_namespaces = namespace.is_none();
@@ -116,6 +182,7 @@ pub fn discover_k8s_pods(namespace: Option<&str>) -> Result<Vec<DiscoveredSource
                         source_type: SourceType::K8s,
                         status: parts[2].to_string(),
                         extra: parts.get(3).map(|s| s.to_string()),
+                        context: scope.context.clone(),
                     })
                 } else {
                     None
@@ -129,6 +196,7 @@ pub fn discover_k8s_pods(namespace: Option<&str>) -> Result<Vec<DiscoveredSource
                         source_type: SourceType::K8s,
                         status: parts[1].to_string(),
                         extra: parts.get(2).map(|s| s.to_string()),
+                        context: scope.context.clone(),
                     })
                 } else {
                     None
@@ -140,10 +208,90 @@ pub fn discover_k8s_pods(namespace: Option<&str>) -> Result<Vec<DiscoveredSource
     Ok(sources)
 }
 
+/// Run a kubectl command that prints one name per line
+fn kubectl_names(args: &[&str]) -> Result<Vec<String>> {
+    let output = Command::new("kubectl").args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("kubectl {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(parse_names(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// One name per non-empty line (context names may contain `/` and `:`)
+fn parse_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Discover the kubeconfig's contexts, and which one is current
+pub fn discover_k8s_contexts() -> Result<(Vec<String>, Option<String>)> {
+    let contexts = kubectl_names(&["config", "get-contexts", "-o", "name"])?;
+    // Fails when no current context is set
+    let current = kubectl_names(&["config", "current-context"])
+        .ok()
+        .and_then(|names| names.into_iter().next());
+    Ok((contexts, current))
+}
+
+/// Discover the namespaces of a cluster
+pub fn discover_k8s_namespaces(context: Option<&str>) -> Result<Vec<String>> {
+    let mut args = vec![
+        "get",
+        "namespaces",
+        "--no-headers",
+        "-o",
+        "custom-columns=NAME:.metadata.name",
+    ];
+    if let Some(context) = context {
+        args.extend(["--context", context]);
+    }
+    kubectl_names(&args)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_k8s_pod_sources_split_multi_container_pods() {
+        let names = |sources: Vec<LogSourceType>| -> Vec<String> {
+            sources.iter().map(LogSourceType::name).collect()
+        };
+        assert_eq!(
+            names(k8s_pod_sources(
+                "web",
+                &Scope {
+                    context: Some("staging".to_string()),
+                    namespace: Some("prod".to_string()),
+                },
+                Some("nginx,envoy")
+            )),
+            vec!["k8s@staging:prod/web/nginx", "k8s@staging:prod/web/envoy"]
+        );
+        // A single container is left to kubectl's default
+        assert_eq!(
+            names(k8s_pod_sources("web", &Scope::default(), Some("nginx"))),
+            vec!["k8s:web"]
+        );
+        assert_eq!(
+            names(k8s_pod_sources("web", &Scope::default(), None)),
+            vec!["k8s:web"]
+        );
+    }
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!(
+            parse_names("staging\n  arn:aws:eks:eu-west-1:123:cluster/prod \n\n"),
+            vec!["staging", "arn:aws:eks:eu-west-1:123:cluster/prod"]
+        );
+    }
+
     #[test]
     fn test_source_type_display() {
         assert_eq!(format!("{}", SourceType::Docker), "Docker");
@@ -158,6 +306,7 @@ mod tests {
             status: "running".to_string(),
             extra: Some("nginx:latest".to_string()),
             namespace: None,
+            context: None,
         };
         let cloned = source.clone();
         assert_eq!(cloned.name, "test");
@@ -172,6 +321,7 @@ mod tests {
             status: "Running".to_string(),
             extra: Some("nginx".to_string()),
             namespace: Some("production".to_string()),
+            context: None,
         };
         assert_eq!(source.namespace, Some("production".to_string()));
     }
@@ -1,9 +1,15 @@
 //! Log filtering with substring and regex support.
 //!
 //! Provides `ActiveFilter` for real-time log filtering with
-//! case-insensitive substring matching or regex patterns.
+//! case-insensitive substring matching, regex patterns, or field queries
+//! (see `query`).
 
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::app::LogLine;
+use crate::query::{self, Query};
+use crate::sources::LogSourceType;
 
 /// A range representing a match within a line
 #[derive(Clone, Copy, Debug)]
@@ -12,8 +18,10 @@ pub struct MatchRange {
     pub end: usize,
 }
 
-/// A filter that can be applied to log lines
-#[derive(Clone)]
+/// A filter that can be applied to log lines (stored as just its pattern
+/// and mode)
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "FilterSpec", into = "FilterSpec")]
 pub struct ActiveFilter {
     /// The pattern string
     pub pattern: String,
@@ -23,6 +31,10 @@ pub struct ActiveFilter {
     compiled: Option<Regex>,
     /// Lowercase pattern for case-insensitive substring matching
     pattern_lower: String,
+    /// Parsed query (substring mode, when the pattern uses query syntax)
+    query: Option<Query>,
+    /// Why the pattern couldn't be parsed as a query
+    pub error: Option<String>,
 }
 
 impl ActiveFilter {
@@ -33,12 +45,39 @@ impl ActiveFilter {
             None
         };
         let pattern_lower = pattern.to_lowercase();
+        // A query that doesn't parse falls back to substring matching,
+        // like an invalid regex does
+        let (query, error) = if !is_regex && query::looks_like_query(&pattern) {
+            match Query::parse(&pattern) {
+                Ok(query) => (Some(query), None),
+                Err(e) if query::has_keywords(&pattern) => (None, Some(e)),
+                Err(_) => (None, None),
+            }
+        } else {
+            (None, None)
+        };
 
         Self {
             pattern,
             is_regex,
             compiled,
             pattern_lower,
+            query,
+            error,
+        }
+    }
+
+    /// Whether the pattern is a field query rather than plain text
+    pub fn is_query(&self) -> bool {
+        self.query.is_some()
+    }
+
+    /// Check if a log line matches this filter; `sources` names the line's
+    /// source for `source=` queries
+    pub fn matches_line(&self, line: &LogLine, sources: &[LogSourceType]) -> bool {
+        match &self.query {
+            Some(query) => query.matches(line, sources),
+            None => self.matches(&line.raw),
         }
     }
 
@@ -61,7 +100,10 @@ impl ActiveFilter {
     pub fn find_matches(&self, line: &str) -> Vec<MatchRange> {
         let mut matches = Vec::new();
 
-        if self.is_regex {
+        if let Some(query) = &self.query {
+            query.find_matches(line, &mut matches);
+            merge_overlapping(&mut matches);
+        } else if self.is_regex {
             if let Some(ref regex) = self.compiled {
                 for m in regex.find_iter(line) {
                     matches.push(MatchRange {
@@ -71,42 +113,128 @@ impl ActiveFilter {
                 }
             } else {
                 // Invalid regex, fall back to substring
-                self.find_substring_matches(line, &mut matches);
+                find_substring_matches(line, &self.pattern_lower, &mut matches);
             }
         } else {
-            self.find_substring_matches(line, &mut matches);
+            find_substring_matches(line, &self.pattern_lower, &mut matches);
         }
 
         matches
     }
+}
 
-    /// Find all case-insensitive substring matches
-    fn find_substring_matches(&self, line: &str, matches: &mut Vec<MatchRange>) {
-        if self.pattern_lower.is_empty() {
-            return;
+/// What an `ActiveFilter` is saved as
+#[derive(Serialize, Deserialize)]
+struct FilterSpec {
+    pattern: String,
+    #[serde(default)]
+    is_regex: bool,
+}
+
+impl From<FilterSpec> for ActiveFilter {
+    fn from(spec: FilterSpec) -> Self {
+        ActiveFilter::new(spec.pattern, spec.is_regex)
+    }
+}
+
+impl From<ActiveFilter> for FilterSpec {
+    fn from(filter: ActiveFilter) -> Self {
+        FilterSpec {
+            pattern: filter.pattern,
+            is_regex: filter.is_regex,
         }
+    }
+}
 
-        let line_lower = line.to_lowercase();
-        let mut start = 0;
-
-        while let Some(pos) = line_lower[start..].find(&self.pattern_lower) {
-            let match_start = start + pos;
-            let match_end = match_start + self.pattern.len();
-            matches.push(MatchRange {
-                start: match_start,
-                end: match_end,
-            });
-            start = match_end;
+/// Find all case-insensitive matches of an already-lowercased pattern
+pub fn find_substring_matches(line: &str, pattern_lower: &str, matches: &mut Vec<MatchRange>) {
+    if pattern_lower.is_empty() {
+        return;
+    }
+
+    let line_lower = line.to_lowercase();
+    let mut start = 0;
+
+    while let Some(pos) = line_lower[start..].find(pattern_lower) {
+        let match_start = start + pos;
+        let match_end = match_start + pattern_lower.len();
+        matches.push(MatchRange {
+            start: match_start,
+            end: match_end,
+        });
+        start = match_end;
+    }
+}
+
+/// Sort match ranges and merge the ones that overlap, so they can be
+/// highlighted left to right
+pub fn merge_overlapping(matches: &mut Vec<MatchRange>) {
+    matches.sort_by_key(|m| m.start);
+    let mut merged: Vec<MatchRange> = Vec::with_capacity(matches.len());
+    for m in matches.drain(..) {
+        match merged.last_mut() {
+            Some(last) if m.start <= last.end => last.end = last.end.max(m.end),
+            _ => merged.push(m),
         }
     }
+    *matches = merged;
+}
+
+/// A filter pinned to a pane's filter stack
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FilterChip {
+    #[serde(flatten)]
+    pub filter: ActiveFilter,
+    /// Hide matching lines instead of keeping only them
+    #[serde(default)]
+    pub exclude: bool,
+    /// Disabled chips stay in the stack but don't filter
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl FilterChip {
+    pub fn new(filter: ActiveFilter, exclude: bool) -> Self {
+        Self {
+            filter,
+            exclude,
+            enabled: true,
+        }
+    }
+
+    /// Whether a line gets past this chip
+    pub fn admits(&self, line: &LogLine, sources: &[LogSourceType]) -> bool {
+        !self.enabled || self.filter.matches_line(line, sources) != self.exclude
+    }
+
+    /// Short label for the filter bar: `+order`, `-healthz`
+    pub fn label(&self) -> String {
+        format!(
+            "{}{}",
+            if self.exclude { '-' } else { '+' },
+            self.filter.pattern
+        )
+    }
 }
 
 /// A saved filter with a name
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SavedFilter {
     pub name: String,
+    #[serde(default)]
     pub pattern: String,
+    #[serde(default)]
     pub is_regex: bool,
+    /// Pinned filters saved along with the typed one
+    #[serde(default)]
+    pub stack: Vec<FilterChip>,
+    /// Comes from `[[filters]]` in the config file (not saved to state)
+    #[serde(skip)]
+    pub predefined: bool,
 }
 
 #[cfg(test)]
@@ -203,4 +331,105 @@ mod tests {
         let matches = filter.find_matches("has [bad regex");
         assert_eq!(matches.len(), 1);
     }
+
+    // Query filters
+
+    #[test]
+    fn test_query_filter_matches_fields() {
+        let filter = ActiveFilter::new("level>=warn AND svc=api".to_string(), false);
+        assert!(filter.is_query());
+        assert!(filter.matches_line(&LogLine::new("level=error svc=api".to_string()), &[]));
+        assert!(!filter.matches_line(&LogLine::new("level=info svc=api".to_string()), &[]));
+    }
+
+    #[test]
+    fn test_query_syntax_error_falls_back_to_substring() {
+        let filter = ActiveFilter::new("a=b AND".to_string(), false);
+        assert!(!filter.is_query());
+        assert_eq!(filter.error.as_deref(), Some("unexpected end of query"));
+        assert!(filter.matches_line(&LogLine::new("x a=b AND y".to_string()), &[]));
+    }
+
+    #[test]
+    fn test_operators_in_plain_text_still_match() {
+        let filter = ActiveFilter::new("user_id=42".to_string(), false);
+        assert!(filter.matches_line(&LogLine::new("login user_id=42".to_string()), &[]));
+
+        for pattern in ["~/.config", "a->b"] {
+            let filter = ActiveFilter::new(pattern.to_string(), false);
+            assert!(!filter.is_query());
+            assert!(filter.error.is_none());
+            let raw = format!("reading {} now", pattern);
+            assert!(filter.matches_line(&LogLine::new(raw), &[]));
+        }
+    }
+
+    #[test]
+    fn test_uppercase_keywords_in_plain_text_still_match() {
+        let filter = ActiveFilter::new("404 NOT FOUND".to_string(), false);
+        assert!(!filter.is_query());
+        assert!(filter.matches_line(&LogLine::new("GET /x 404 NOT FOUND".to_string()), &[]));
+        assert!(!filter.matches_line(&LogLine::new("GET /x 404".to_string()), &[]));
+    }
+
+    // Filter stack
+
+    #[test]
+    fn test_filter_chips_include_and_exclude() {
+        let line = |raw: &str| LogLine::new(raw.to_string());
+        let include = FilterChip::new(ActiveFilter::new("order".to_string(), false), false);
+        let mut exclude = FilterChip::new(ActiveFilter::new("healthz".to_string(), false), true);
+
+        assert!(include.admits(&line("GET /order/7"), &[]));
+        assert!(!include.admits(&line("GET /cart"), &[]));
+        assert!(!exclude.admits(&line("GET /healthz"), &[]));
+        assert!(exclude.admits(&line("GET /order/7"), &[]));
+        assert_eq!(exclude.label(), "-healthz");
+
+        exclude.enabled = false;
+        assert!(exclude.admits(&line("GET /healthz"), &[]));
+    }
+
+    #[test]
+    fn test_saved_filter_round_trips_through_json() {
+        let saved = SavedFilter {
+            name: "orders".to_string(),
+            pattern: "level>=warn".to_string(),
+            is_regex: false,
+            stack: vec![FilterChip::new(
+                ActiveFilter::new("healthz".to_string(), false),
+                true,
+            )],
+            predefined: false,
+        };
+        let json = serde_json::to_string(&saved).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"orders","pattern":"level>=warn","is_regex":false,"stack":[{"pattern":"healthz","is_regex":false,"exclude":true,"enabled":true}]}"#
+        );
+
+        let loaded: SavedFilter = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.stack[0].label(), "-healthz");
+        assert!(!loaded.stack[0].admits(&LogLine::new("GET /healthz".to_string()), &[]));
+    }
+
+    #[test]
+    fn test_merge_overlapping_matches() {
+        let mut matches = vec![
+            MatchRange { start: 6, end: 9 },
+            MatchRange { start: 0, end: 5 },
+            MatchRange { start: 2, end: 4 },
+            MatchRange { start: 8, end: 12 },
+        ];
+        merge_overlapping(&mut matches);
+        let spans: Vec<_> = matches.iter().map(|m| (m.start, m.end)).collect();
+        assert_eq!(spans, vec![(0, 5), (6, 12)]);
+    }
+
+    #[test]
+    fn test_regex_mode_never_parses_queries() {
+        let filter = ActiveFilter::new("level>=warn".to_string(), true);
+        assert!(!filter.is_query());
+        assert!(filter.error.is_none());
+    }
 }
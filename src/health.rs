@@ -0,0 +1,314 @@
+//! Per-source health tracking.
+//!
+//! Every source gets a `SourceHealth` recording its lifecycle state, how many
+//! lines it has delivered, its recent line rate and a short history of the
+//! errors it reported. The Sources panel and the source detail overlay
+//! render from it.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::{SOURCE_ERROR_HISTORY, SOURCE_STALL_SECS};
+
+/// Seconds of history used for the line rate
+const RATE_WINDOW_SECS: u64 = 10;
+
+/// Lifecycle of a log source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceStatus {
+    /// Started, no data yet
+    Connecting,
+    /// Delivering lines
+    Streaming,
+    /// No data for a while (derived, never stored)
+    Stalled,
+    /// Reported an error and delivered nothing since
+    Errored,
+    /// Connection dropped; waiting before the given retry attempt
+    Reconnecting(u32),
+    /// Stream finished or the source went away
+    Ended,
+}
+
+impl SourceStatus {
+    /// Short label for display
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceStatus::Connecting => "connecting",
+            SourceStatus::Streaming => "streaming",
+            SourceStatus::Stalled => "stalled",
+            SourceStatus::Errored => "errored",
+            SourceStatus::Reconnecting(_) => "reconnecting",
+            SourceStatus::Ended => "ended",
+        }
+    }
+}
+
+/// An error reported by a source
+#[derive(Debug, Clone)]
+pub struct SourceError {
+    pub at: DateTime<Local>,
+    pub message: String,
+}
+
+/// Health of a single source
+#[derive(Debug, Clone)]
+pub struct SourceHealth {
+    /// Last recorded state (`Stalled` is derived in `status`)
+    status: SourceStatus,
+    /// When the current state was entered
+    status_since: Instant,
+    /// Total lines delivered
+    pub line_count: u64,
+    /// When the last line arrived
+    last_line_at: Option<Instant>,
+    /// Reference point for rate buckets
+    started: Instant,
+    /// Lines per second over the last `RATE_WINDOW_SECS`: (second, count)
+    buckets: VecDeque<(u64, u64)>,
+    /// Most recent errors, oldest first
+    errors: VecDeque<SourceError>,
+    /// Newest line timestamp, where a restarted source picks up again
+    pub last_timestamp: Option<DateTime<Local>>,
+}
+
+impl SourceHealth {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            status: SourceStatus::Connecting,
+            status_since: now,
+            line_count: 0,
+            last_line_at: None,
+            started: now,
+            buckets: VecDeque::new(),
+            errors: VecDeque::new(),
+            last_timestamp: None,
+        }
+    }
+
+    /// Current state; a connecting or streaming source with no data for
+    /// `SOURCE_STALL_SECS` counts as stalled
+    pub fn status(&self, now: Instant) -> SourceStatus {
+        match self.status {
+            SourceStatus::Connecting | SourceStatus::Streaming => {
+                let quiet_since = self.last_line_at.unwrap_or(self.status_since);
+                if now.saturating_duration_since(quiet_since)
+                    >= Duration::from_secs(SOURCE_STALL_SECS)
+                {
+                    SourceStatus::Stalled
+                } else {
+                    self.status
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Record a state change reported by the source (or the watcher)
+    pub fn set_status(&mut self, status: SourceStatus, now: Instant) {
+        if self.status != status {
+            self.status = status;
+            self.status_since = now;
+        }
+    }
+
+    /// Record delivered lines
+    pub fn record_lines(&mut self, count: u64, now: Instant) {
+        self.line_count += count;
+        self.last_line_at = Some(now);
+        if self.status != SourceStatus::Ended {
+            self.set_status(SourceStatus::Streaming, now);
+        }
+
+        let second = now.saturating_duration_since(self.started).as_secs();
+        match self.buckets.back_mut() {
+            Some((s, n)) if *s == second => *n += count,
+            _ => self.buckets.push_back((second, count)),
+        }
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(s, _)| *s + RATE_WINDOW_SECS <= second)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Record the timestamp of a delivered line
+    pub fn record_timestamp(&mut self, timestamp: DateTime<Local>) {
+        if self.last_timestamp.is_none_or(|last| timestamp > last) {
+            self.last_timestamp = Some(timestamp);
+        }
+    }
+
+    /// Record an error message
+    pub fn record_error(&mut self, message: String, now: Instant) {
+        if self.status != SourceStatus::Ended {
+            self.set_status(SourceStatus::Errored, now);
+        }
+        if self.errors.len() >= SOURCE_ERROR_HISTORY {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(SourceError {
+            at: Local::now(),
+            message,
+        });
+    }
+
+    /// Lines per second over the last few seconds
+    pub fn rate(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.started).as_secs();
+        let recent: u64 = self
+            .buckets
+            .iter()
+            .filter(|(s, _)| s + RATE_WINDOW_SECS > elapsed)
+            .map(|(_, n)| n)
+            .sum();
+        // Young sources are averaged over their lifetime so far
+        let window = (elapsed + 1).min(RATE_WINDOW_SECS);
+        recent as f64 / window as f64
+    }
+
+    /// Time since the last line, if any arrived
+    pub fn last_line_age(&self, now: Instant) -> Option<Duration> {
+        self.last_line_at
+            .map(|at| now.saturating_duration_since(at))
+    }
+
+    /// Time spent in the current state
+    pub fn status_age(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.status_since)
+    }
+
+    /// The most recent error
+    pub fn last_error(&self) -> Option<&SourceError> {
+        self.errors.back()
+    }
+
+    /// Recent errors, newest first
+    pub fn errors(&self) -> impl Iterator<Item = &SourceError> {
+        self.errors.iter().rev()
+    }
+}
+
+/// Format a duration compactly ("42s", "5m", "3h", "2d")
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}d", secs / 86_400)
+    }
+}
+
+/// Format a line count compactly ("950", "12.3k", "4.1M")
+pub fn format_count(count: u64) -> String {
+    if count < 1000 {
+        count.to_string()
+    } else if count < 1_000_000 {
+        format!("{:.1}k", count as f64 / 1000.0)
+    } else {
+        format!("{:.1}M", count as f64 / 1_000_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_transitions() {
+        let t0 = Instant::now();
+        let mut health = SourceHealth::new(t0);
+        assert_eq!(health.status(t0), SourceStatus::Connecting);
+
+        health.record_lines(1, t0);
+        assert_eq!(health.status(t0), SourceStatus::Streaming);
+
+        health.record_error("connection reset".to_string(), t0);
+        assert_eq!(health.status(t0), SourceStatus::Errored);
+        assert_eq!(health.last_error().unwrap().message, "connection reset");
+
+        // Data flowing again clears the error state but keeps the history
+        health.record_lines(1, t0);
+        assert_eq!(health.status(t0), SourceStatus::Streaming);
+        assert_eq!(health.errors().count(), 1);
+
+        health.set_status(SourceStatus::Ended, t0);
+        health.record_lines(1, t0);
+        health.record_error("late".to_string(), t0);
+        assert_eq!(health.status(t0), SourceStatus::Ended);
+        assert_eq!(health.line_count, 3);
+    }
+
+    #[test]
+    fn test_quiet_source_is_stalled() {
+        let t0 = Instant::now();
+        let mut health = SourceHealth::new(t0);
+        let later = t0 + Duration::from_secs(SOURCE_STALL_SECS);
+        assert_eq!(health.status(later), SourceStatus::Stalled);
+
+        health.record_lines(1, later);
+        assert_eq!(health.status(later), SourceStatus::Streaming);
+        let quiet = later + Duration::from_secs(SOURCE_STALL_SECS);
+        assert_eq!(health.status(quiet), SourceStatus::Stalled);
+
+        // Reconnecting and errored sources are not reported as stalled
+        health.set_status(SourceStatus::Reconnecting(2), quiet);
+        assert_eq!(
+            health.status(quiet + Duration::from_secs(SOURCE_STALL_SECS)),
+            SourceStatus::Reconnecting(2)
+        );
+    }
+
+    #[test]
+    fn test_rate_uses_recent_window() {
+        let t0 = Instant::now();
+        let mut health = SourceHealth::new(t0);
+        for s in 0..RATE_WINDOW_SECS {
+            health.record_lines(5, t0 + Duration::from_secs(s));
+        }
+        let now = t0 + Duration::from_secs(RATE_WINDOW_SECS - 1);
+        assert_eq!(health.rate(now), 5.0);
+
+        // Old buckets fall out of the window
+        let idle = now + Duration::from_secs(RATE_WINDOW_SECS);
+        assert_eq!(health.rate(idle), 0.0);
+    }
+
+    #[test]
+    fn test_error_history_is_bounded() {
+        let t0 = Instant::now();
+        let mut health = SourceHealth::new(t0);
+        for i in 0..SOURCE_ERROR_HISTORY + 5 {
+            health.record_error(format!("error {}", i), t0);
+        }
+        assert_eq!(health.errors().count(), SOURCE_ERROR_HISTORY);
+        let newest = format!("error {}", SOURCE_ERROR_HISTORY + 4);
+        assert_eq!(health.errors().next().unwrap().message, newest);
+    }
+
+    #[test]
+    fn test_last_timestamp_keeps_the_newest() {
+        let mut health = SourceHealth::new(Instant::now());
+        let t = Local::now();
+        health.record_timestamp(t);
+        health.record_timestamp(t - chrono::Duration::seconds(5));
+        assert_eq!(health.last_timestamp, Some(t));
+    }
+
+    #[test]
+    fn test_format_helpers() {
+        assert_eq!(format_age(Duration::from_secs(42)), "42s");
+        assert_eq!(format_age(Duration::from_secs(300)), "5m");
+        assert_eq!(format_age(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_count(950), "950");
+        assert_eq!(format_count(12_345), "12.3k");
+        assert_eq!(format_count(4_100_000), "4.1M");
+    }
+}
@@ -0,0 +1,212 @@
+//! Highlight rules: patterns colored on every line, independent of filters.
+//!
+//! Rules come from `[[highlights]]` in the config file or from the active
+//! filter (`*`). Colors are resolved against the current theme when a line
+//! is drawn, so theme names like "error" follow theme changes.
+
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use std::str::FromStr;
+
+use crate::config::HighlightConfig;
+use crate::filter::{MatchRange, find_substring_matches};
+use crate::theme::Theme;
+
+/// Colors handed out to new rules, in order
+pub const HIGHLIGHT_COLORS: &[&str] = &["yellow", "magenta", "cyan", "green", "blue", "red"];
+
+/// A compiled highlight rule
+#[derive(Clone, Debug)]
+pub struct HighlightRule {
+    pub config: HighlightConfig,
+    /// Compiled regex (if `config.regex` is set)
+    compiled: Option<Regex>,
+    /// Lowercase pattern for case-insensitive substring matching
+    pattern_lower: String,
+}
+
+impl HighlightRule {
+    pub fn new(config: HighlightConfig) -> Result<Self, String> {
+        if config.pattern.is_empty() {
+            return Err("Highlight has an empty pattern".to_string());
+        }
+        let compiled = if config.regex {
+            Some(
+                Regex::new(&config.pattern)
+                    .map_err(|e| format!("Highlight '{}': invalid regex: {}", config.pattern, e))?,
+            )
+        } else {
+            None
+        };
+        if !config.color.is_empty() && !is_color(&config.color) {
+            return Err(format!(
+                "Highlight '{}': unknown color '{}'",
+                config.pattern, config.color
+            ));
+        }
+        parse_modifiers(&config.style)
+            .map_err(|e| format!("Highlight '{}': {}", config.pattern, e))?;
+
+        let pattern_lower = config.pattern.to_lowercase();
+        Ok(Self {
+            config,
+            compiled,
+            pattern_lower,
+        })
+    }
+
+    /// Find all ranges of `line` this rule colors
+    pub fn find_matches(&self, line: &str) -> Vec<MatchRange> {
+        let mut matches = Vec::new();
+        match &self.compiled {
+            Some(regex) => {
+                matches.extend(regex.find_iter(line).filter(|m| !m.is_empty()).map(|m| {
+                    MatchRange {
+                        start: m.start(),
+                        end: m.end(),
+                    }
+                }))
+            }
+            None => find_substring_matches(line, &self.pattern_lower, &mut matches),
+        }
+        matches
+    }
+
+    /// Style drawn over matches, with colors taken from `theme`
+    pub fn style(&self, theme: &Theme) -> Style {
+        let mut style = match self.config.color.as_str() {
+            "" | "match" => Style::default()
+                .fg(theme.highlight_match_fg)
+                .bg(theme.highlight_match_bg),
+            name => Style::default().fg(resolve_color(name, theme).unwrap_or(Color::Reset)),
+        };
+        if let Ok(modifiers) = parse_modifiers(&self.config.style) {
+            style = style.add_modifier(modifiers);
+        }
+        style
+    }
+
+    /// Switch to the next color in `HIGHLIGHT_COLORS`
+    pub fn cycle_color(&mut self) {
+        let next = HIGHLIGHT_COLORS
+            .iter()
+            .position(|c| *c == self.config.color)
+            .map_or(0, |i| (i + 1) % HIGHLIGHT_COLORS.len());
+        self.config.color = HIGHLIGHT_COLORS[next].to_string();
+    }
+}
+
+/// Theme slot names usable as highlight colors
+fn theme_color(name: &str, theme: &Theme) -> Option<Color> {
+    Some(match name {
+        "error" => theme.level_error,
+        "warn" => theme.level_warn,
+        "info" => theme.level_info,
+        "debug" => theme.level_debug,
+        "trace" => theme.level_trace,
+        "match" => theme.highlight_match_bg,
+        "key" => theme.field_key,
+        "value" => theme.field_value,
+        _ => return None,
+    })
+}
+
+fn is_color(name: &str) -> bool {
+    resolve_color(name, &Theme::default()).is_some()
+}
+
+/// A theme slot, color name or `#rrggbb`
+fn resolve_color(name: &str, theme: &Theme) -> Option<Color> {
+    let name = name.trim().to_lowercase();
+    theme_color(&name, theme).or_else(|| Color::from_str(&name).ok())
+}
+
+fn parse_modifiers(style: &str) -> Result<Modifier, String> {
+    let mut modifiers = Modifier::empty();
+    for part in style
+        .split(['+', ','])
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        modifiers |= match part.to_lowercase().as_str() {
+            "bold" => Modifier::BOLD,
+            "italic" => Modifier::ITALIC,
+            "underline" | "underlined" => Modifier::UNDERLINED,
+            "reverse" | "reversed" => Modifier::REVERSED,
+            "dim" => Modifier::DIM,
+            _ => return Err(format!("unknown style '{}'", part)),
+        };
+    }
+    Ok(modifiers)
+}
+
+/// Compile the configured rules, collecting errors for rules that don't
+pub fn compile(configs: &[HighlightConfig]) -> (Vec<HighlightRule>, Vec<String>) {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for config in configs {
+        match HighlightRule::new(config.clone()) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => errors.push(e),
+        }
+    }
+    (rules, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, regex: bool, color: &str, style: &str) -> Result<HighlightRule, String> {
+        HighlightRule::new(HighlightConfig {
+            pattern: pattern.to_string(),
+            regex,
+            color: color.to_string(),
+            style: style.to_string(),
+            enabled: true,
+        })
+    }
+
+    #[test]
+    fn test_highlight_rule_matches_and_styles() {
+        let theme = Theme::default();
+        let user = rule("USER_ID=42", false, "magenta", "bold+underline").unwrap();
+        let spans: Vec<_> = user
+            .find_matches("login user_id=42 ok")
+            .iter()
+            .map(|m| (m.start, m.end))
+            .collect();
+        assert_eq!(spans, vec![(6, 16)]);
+        let style = user.style(&theme);
+        assert_eq!(style.fg, Some(Color::Magenta));
+        assert!(
+            style
+                .add_modifier
+                .contains(Modifier::BOLD | Modifier::UNDERLINED)
+        );
+
+        let timeout = rule(r"time(d )?out", true, "error", "").unwrap();
+        assert_eq!(timeout.find_matches("timed out; timeout").len(), 2);
+        assert_eq!(timeout.style(&theme).fg, Some(theme.level_error));
+
+        let hex = rule("x", false, "#ff8800", "").unwrap();
+        assert_eq!(hex.style(&theme).fg, Some(Color::Rgb(255, 136, 0)));
+    }
+
+    #[test]
+    fn test_highlight_rule_rejects_bad_config() {
+        assert!(rule("[bad", true, "", "").is_err());
+        assert!(rule("x", false, "chartreuse-ish", "").is_err());
+        assert!(rule("x", false, "", "blinking").is_err());
+        assert!(rule("", false, "", "").is_err());
+    }
+
+    #[test]
+    fn test_cycle_color() {
+        let mut r = rule("x", false, "", "").unwrap();
+        r.cycle_color();
+        assert_eq!(r.config.color, "yellow");
+        r.cycle_color();
+        assert_eq!(r.config.color, "magenta");
+    }
+}
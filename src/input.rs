@@ -6,8 +6,11 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKi
 use regex::Regex;
 use tui_textarea::Input;
 
-use crate::app::{AppState, FocusedPanel, InputMode, PickerMode, SourceViewMode};
+use crate::app::{AppState, FocusedPanel, InputMode, PickerMode, ScopeKind, SourceViewMode};
 use crate::config::MOUSE_SCROLL_LINES;
+use crate::discovery::{DiscoveredSource, SourceType, k8s_pod_sources};
+use crate::sources::LogSourceType;
+use crate::sources::k8s::Scope;
 
 /// Strip ANSI escape codes from a string
 fn strip_ansi_codes(s: &str) -> String {
@@ -74,12 +77,29 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent, page_size: usize) {
         return;
     }
 
+    // Source detail overlay
+    if state.source_detail.is_some() {
+        if matches!(
+            key.code,
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('i')
+        ) {
+            state.source_detail = None;
+        }
+        return;
+    }
+
     // Settings overlay
     if state.settings.visible {
         handle_settings_input(state, key);
         return;
     }
 
+    // Column picker overlay
+    if state.column_picker.visible {
+        handle_column_picker_input(state, key);
+        return;
+    }
+
     // Note: Picker mode is handled separately by main loop
 
     match state.mode {
@@ -131,6 +151,11 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
                     state.selected_filter_idx += 1;
                 }
             }
+            FocusedPanel::Highlights => {
+                if state.selected_highlight_idx + 1 < state.highlights.len() {
+                    state.selected_highlight_idx += 1;
+                }
+            }
         },
         KeyCode::Char('k') | KeyCode::Up => match state.focused_panel {
             FocusedPanel::LogView => state.scroll_up(),
@@ -144,6 +169,11 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
                     state.selected_filter_idx -= 1;
                 }
             }
+            FocusedPanel::Highlights => {
+                if state.selected_highlight_idx > 0 {
+                    state.selected_highlight_idx -= 1;
+                }
+            }
         },
 
         // Horizontal scrolling (when line wrap is off)
@@ -167,6 +197,11 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
             state.scroll_home();
         }
 
+        // Enter in table view - show/hide the selected line's fields
+        KeyCode::Enter if state.table_mode && state.focused_panel == FocusedPanel::LogView => {
+            state.toggle_field_detail();
+        }
+
         // Enter to apply selected saved filter
         KeyCode::Enter => {
             if state.focused_panel == FocusedPanel::Filters && !state.saved_filters.is_empty() {
@@ -174,6 +209,22 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
             }
         }
 
+        // Highlights panel: Space toggles, c recolors, x deletes the selected rule
+        KeyCode::Char(' ') if state.focused_panel == FocusedPanel::Highlights => {
+            state.toggle_selected_highlight();
+        }
+        KeyCode::Char('c') if state.focused_panel == FocusedPanel::Highlights => {
+            state.cycle_selected_highlight_color();
+        }
+        KeyCode::Char('x') | KeyCode::Delete if state.focused_panel == FocusedPanel::Highlights => {
+            state.remove_selected_highlight();
+        }
+
+        // Keep the active filter as a highlight rule instead
+        KeyCode::Char('*') => {
+            state.highlight_active_filter();
+        }
+
         // Space - toggle source visibility (in Sources panel)
         KeyCode::Char(' ') => {
             if state.focused_panel == FocusedPanel::Sources {
@@ -228,6 +279,11 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
             }
         }
 
+        // 'i' - health and error history of the selected source
+        KeyCode::Char('i') => {
+            state.show_source_detail();
+        }
+
         KeyCode::PageDown | KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             state.scroll_page_down(page_size);
         }
@@ -255,6 +311,47 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
             state.toggle_regex_mode();
         }
 
+        // Filter stack: pin the active filter as include/exclude, toggle by number
+        KeyCode::Char('f') => {
+            state.push_filter(false);
+        }
+        KeyCode::Char('F') => {
+            state.push_filter(true);
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            state.toggle_filter_chip(c as usize - '1' as usize);
+        }
+        KeyCode::Backspace => {
+            state.pop_filter_chip();
+        }
+        KeyCode::Char('X') => {
+            state.clear_filter_stack();
+        }
+
+        // Level threshold
+        KeyCode::Char('+') => {
+            state.raise_min_level();
+        }
+        KeyCode::Char('-') => {
+            state.lower_min_level();
+        }
+
+        // Fold/unfold multi-line events
+        KeyCode::Char('z') => {
+            state.toggle_fold();
+        }
+        KeyCode::Char('Z') => {
+            state.toggle_fold_all();
+        }
+
+        // Context lines around matches
+        KeyCode::Char('}') => {
+            state.grow_context();
+        }
+        KeyCode::Char('{') => {
+            state.shrink_context();
+        }
+
         // Next match
         KeyCode::Char('n') => {
             if state.panes[state.active_pane].active_filter.is_some() {
@@ -303,11 +400,26 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
             state.toggle_relative_time();
         }
 
+        // Toggle timestamp/arrival order for multiple sources
+        KeyCode::Char('o') => {
+            state.toggle_merge_order();
+        }
+
         // Toggle JSON pretty-printing
         KeyCode::Char('J') => {
             state.toggle_json_pretty();
         }
 
+        // Toggle table view of structured fields
+        KeyCode::Char('T') => {
+            state.toggle_table_mode();
+        }
+
+        // Choose table columns
+        KeyCode::Char('C') => {
+            state.open_column_picker();
+        }
+
         // Toggle pause (stop following new logs)
         KeyCode::Char('p') => {
             state.panes[state.active_pane].stick_to_bottom =
@@ -334,13 +446,21 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
 
         // Save current filter
         KeyCode::Char('s') => {
-            if state.panes[state.active_pane].active_filter.is_some() {
-                // Simple auto-naming based on pattern
-                let pattern = state.filter_input();
-                let name = if pattern.len() > 10 {
-                    format!("{}...", &pattern[..10])
-                } else {
-                    pattern
+            let pane = &state.panes[state.active_pane];
+            if pane.active_filter.is_some() || !pane.filter_stack.is_empty() {
+                // Simple auto-naming based on pattern (or the pinned filters)
+                let mut pattern = state.filter_input();
+                if pattern.is_empty() {
+                    pattern = pane
+                        .filter_stack
+                        .iter()
+                        .map(|chip| chip.label())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                }
+                let name = match pattern.char_indices().nth(10) {
+                    Some((end, _)) => format!("{}...", &pattern[..end]),
+                    None => pattern,
                 };
                 state.save_current_filter(name);
             } else {
@@ -351,13 +471,7 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
         // Delete selected saved filter
         KeyCode::Char('x') | KeyCode::Delete => {
             if state.focused_panel == FocusedPanel::Filters && !state.saved_filters.is_empty() {
-                state.saved_filters.remove(state.selected_filter_idx);
-                if state.selected_filter_idx >= state.saved_filters.len()
-                    && state.selected_filter_idx > 0
-                {
-                    state.selected_filter_idx -= 1;
-                }
-                state.status_message = Some("Filter deleted".to_string());
+                state.delete_saved_filter();
             }
         }
 
@@ -376,6 +490,11 @@ fn handle_normal_mode(state: &mut AppState, key: KeyEvent, page_size: usize) {
             }
         }
 
+        // Sources cannot be added while browsing a single file
+        KeyCode::Char('D') | KeyCode::Char('K') if state.browse.is_some() => {
+            state.status_message = Some("Cannot add sources while browsing a file".to_string());
+        }
+
         // Open Docker picker
         KeyCode::Char('D') => {
             state.picker.open(PickerMode::Docker);
@@ -555,11 +674,69 @@ fn handle_settings_input(state: &mut AppState, key: KeyEvent) {
     }
 }
 
+/// Handle input when the column picker is open
+fn handle_column_picker_input(state: &mut AppState, key: KeyEvent) {
+    match key.code {
+        // Navigation
+        KeyCode::Char('j') | KeyCode::Down => {
+            state.column_picker.down();
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.column_picker.up();
+        }
+
+        // Add or remove the highlighted field
+        KeyCode::Char(' ') => {
+            state.column_picker.toggle_selected();
+        }
+
+        // Use the chosen columns
+        KeyCode::Enter => {
+            state.apply_column_picker();
+        }
+
+        // Close without changes
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => {
+            state.column_picker.close();
+        }
+
+        _ => {}
+    }
+}
+
 /// Selected source info from picker
 #[derive(Debug, Clone)]
 pub struct SelectedSource {
     pub name: String,
     pub namespace: Option<String>,
+    /// kubeconfig context (K8s pods from a context other than the current one)
+    pub context: Option<String>,
+    /// Comma-separated container names (K8s pods)
+    pub containers: Option<String>,
+}
+
+impl SelectedSource {
+    fn new(source: &DiscoveredSource) -> Self {
+        Self {
+            name: source.name.clone(),
+            namespace: source.namespace.clone(),
+            context: source.context.clone(),
+            // The extra column lists a pod's containers (a container's image for Docker)
+            containers: match source.source_type {
+                SourceType::K8s => source.extra.clone(),
+                SourceType::Docker => None,
+            },
+        }
+    }
+
+    /// One source per container of a selected pod
+    pub fn k8s_sources(&self) -> Vec<LogSourceType> {
+        let scope = Scope {
+            context: self.context.clone(),
+            namespace: self.namespace.clone(),
+        };
+        k8s_pod_sources(&self.name, &scope, self.containers.as_deref())
+    }
 }
 
 /// Picker action to be returned for main loop to handle
@@ -579,6 +756,18 @@ pub enum PickerAction {
 
 /// Handle picker mode input - returns action for main loop
 pub fn handle_picker_input(state: &mut AppState, key: KeyEvent) -> PickerAction {
+    if state.picker.scope_chooser.is_some() {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => state.picker.down(),
+            KeyCode::Char('k') | KeyCode::Up => state.picker.up(),
+            KeyCode::Enter => state.picker.choose_scope(),
+            // Back to the pod list
+            KeyCode::Esc | KeyCode::Char('q') => state.picker.scope_chooser = None,
+            _ => {}
+        }
+        return PickerAction::None;
+    }
+
     match key.code {
         // Navigation
         KeyCode::Char('j') | KeyCode::Down => {
@@ -596,6 +785,16 @@ pub fn handle_picker_input(state: &mut AppState, key: KeyEvent) -> PickerAction
             PickerAction::None
         }
 
+        // Switch cluster or namespace (K8s picker)
+        KeyCode::Char('c') => {
+            state.picker.open_scope_chooser(ScopeKind::Context);
+            PickerAction::None
+        }
+        KeyCode::Char('n') => {
+            state.picker.open_scope_chooser(ScopeKind::Namespace);
+            PickerAction::None
+        }
+
         // Confirm selection - add/remove sources
         KeyCode::Enter => {
             if state.picker.sources.is_empty() {
@@ -621,10 +820,7 @@ pub fn handle_picker_input(state: &mut AppState, key: KeyEvent) -> PickerAction
                             .copied()
                             .unwrap_or(false)
                 })
-                .map(|(_, s)| SelectedSource {
-                    name: s.name.clone(),
-                    namespace: s.namespace.clone(),
-                })
+                .map(|(_, s)| SelectedSource::new(s))
                 .collect();
 
             // Get sources to remove (initially checked but now unchecked)
@@ -632,10 +828,7 @@ pub fn handle_picker_input(state: &mut AppState, key: KeyEvent) -> PickerAction
                 .picker
                 .get_unchecked_sources()
                 .iter()
-                .map(|s| SelectedSource {
-                    name: s.name.clone(),
-                    namespace: s.namespace.clone(),
-                })
+                .map(|s| SelectedSource::new(s))
                 .collect();
 
             state.picker.close();
@@ -653,10 +846,7 @@ pub fn handle_picker_input(state: &mut AppState, key: KeyEvent) -> PickerAction
                         .unwrap_or(false);
                     if !was_initial {
                         return PickerAction::ModifySources {
-                            add: vec![SelectedSource {
-                                name: source.name.clone(),
-                                namespace: source.namespace.clone(),
-                            }],
+                            add: vec![SelectedSource::new(source)],
                             remove: vec![],
                             mode,
                         };
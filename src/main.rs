@@ -1,8 +1,16 @@
 mod app;
+mod browse;
 mod config;
 mod discovery;
 mod filter;
+mod health;
+mod highlight;
 mod input;
+mod merge;
+mod multiline;
+mod parse;
+mod query;
+mod session;
 mod sources;
 mod theme;
 mod ui;
@@ -19,14 +27,26 @@ use crossterm::{
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 
-use app::{AppState, LogLine, PickerMode};
+use app::{AppState, LogLine, PickerMode, ScopeKind};
+use browse::FileBrowser;
 use config::Config;
-use discovery::{discover_docker_containers, discover_k8s_pods};
+use discovery::{
+    discover_docker_containers, discover_k8s_contexts, discover_k8s_namespaces, discover_k8s_pods,
+};
+use health::SourceStatus;
 use input::{PickerAction, handle_picker_input};
 use sources::{
-    LogEvent, LogSource, LogSourceType, SourcedLogEvent, file::FileSource, manager::SourceManager,
+    LogEvent, LogSource, LogSourceType, SourcedLogEvent,
+    docker_api::{ContainerPattern, DockerHost},
+    file::FileSource,
+    k8s::{ContainerSelection, Scope},
+    manager::SourceManager,
+    watch::{self, WatchEvent, WatchSpec},
 };
 
+/// Contexts or namespaces listed for the K8s picker, plus the current context
+type ScopeChoices = anyhow::Result<(Vec<String>, Option<String>)>;
+
 /// Parsed source with its type and implementation
 struct ParsedSource {
     source_type: LogSourceType,
@@ -56,17 +76,45 @@ async fn main() -> Result<()> {
     // Load config first (needed for SSH settings)
     let config = Config::from_env();
 
+    // Browse mode reads a single file from disk instead of following sources
+    let browse_path = parse_browse(&args)?;
+
     // Parse all sources from command line (or empty if none specified)
-    let (parsed_sources, open_picker_mode) = parse_sources(&args, &config)?;
+    let (parsed_sources, open_picker_mode, watches) = if browse_path.is_some() {
+        (Vec::new(), None, Vec::new())
+    } else {
+        parse_sources(&args, &config)?
+    };
 
     // Extract source types for AppState
-    let source_types: Vec<LogSourceType> = parsed_sources
+    let mut source_types: Vec<LogSourceType> = parsed_sources
         .iter()
         .map(|p| p.source_type.clone())
         .collect();
+    if let Some(path) = &browse_path {
+        source_types.push(LogSourceType::File {
+            path: path.clone(),
+            rotated: false,
+        });
+    }
 
     // Initialize state
     let mut state = AppState::new(&config, source_types);
+    if let Some(path) = Config::state_path() {
+        state.restore_session(path);
+    }
+
+    if let Some(path) = browse_path {
+        if let Ok(Some(_)) = sources::archive::detect_compression(&path) {
+            anyhow::bail!(
+                "{} is compressed; open it without --browse to read it",
+                path.display()
+            );
+        }
+        let browser = FileBrowser::open(&path)
+            .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", path.display(), e))?;
+        state.start_browse(browser);
+    }
 
     // Open picker on startup if requested
     if let Some(mode) = open_picker_mode {
@@ -79,6 +127,14 @@ async fn main() -> Result<()> {
         source_manager.add_source(idx, parsed.source).await;
     }
 
+    // Watch globs, directories, pod sets and Docker events for sources that
+    // come and go
+    let (watch_tx, mut watch_rx) = tokio::sync::mpsc::channel(64);
+    for spec in watches {
+        watch::spawn(spec, watch_tx.clone());
+    }
+    drop(watch_tx);
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -99,7 +155,9 @@ async fn main() -> Result<()> {
         &mut terminal,
         &mut state,
         &mut event_rx,
+        &mut watch_rx,
         &mut source_manager,
+        &config,
     )
     .await;
 
@@ -117,18 +175,41 @@ async fn main() -> Result<()> {
     result
 }
 
+/// Parse `--browse <path>`, which must be the only argument
+fn parse_browse(args: &[String]) -> Result<Option<PathBuf>> {
+    if !args.iter().any(|a| a == "--browse") {
+        return Ok(None);
+    }
+    match args {
+        [_, flag, path] if flag == "--browse" && !path.starts_with('-') => {
+            Ok(Some(PathBuf::from(path)))
+        }
+        _ => {
+            anyhow::bail!("--browse takes a single file and cannot be combined with other sources")
+        }
+    }
+}
+
 /// Parse command line arguments into sources
-/// Returns (sources, optional picker mode to open on startup)
+/// Returns (sources, optional picker mode to open on startup, source sets to watch)
 fn parse_sources(
     args: &[String],
     config: &Config,
-) -> Result<(Vec<ParsedSource>, Option<PickerMode>)> {
+) -> Result<(Vec<ParsedSource>, Option<PickerMode>, Vec<WatchSpec>)> {
     let mut sources: Vec<ParsedSource> = Vec::new();
+    let mut watches: Vec<WatchSpec> = Vec::new();
     let mut i = 1;
 
-    // No args - open picker
+    // No args - read piped input, or open picker
     if args.len() < 2 {
-        return Ok((sources, Some(PickerMode::Docker)));
+        if sources::stdin::stdin_is_piped() {
+            sources.push(ParsedSource {
+                source_type: LogSourceType::Stdin,
+                source: Box::new(sources::stdin::StdinSource),
+            });
+            return Ok((sources, None, watches));
+        }
+        return Ok((sources, Some(PickerMode::Docker), watches));
     }
 
     while i < args.len() {
@@ -137,28 +218,19 @@ fn parse_sources(
                 // Discover all Docker containers
                 if let Ok(docker_sources) = discover_docker_containers() {
                     for ds in docker_sources {
+                        let source_type = LogSourceType::Docker { container: ds.name };
                         sources.push(ParsedSource {
-                            source_type: LogSourceType::Docker {
-                                container: ds.name.clone(),
-                            },
-                            source: Box::new(sources::docker::DockerSource::new(ds.name)),
+                            source: sources::create_source(&source_type, config),
+                            source_type,
                         });
                     }
                 }
                 // Discover all K8s pods
-                if let Ok(k8s_sources) = discover_k8s_pods(None) {
-                    for ds in k8s_sources {
+                if let Ok(k8s_sources) = discover_k8s_pods(&Scope::default()) {
+                    for source_type in k8s_sources.iter().flat_map(|ds| ds.k8s_sources()) {
                         sources.push(ParsedSource {
-                            source_type: LogSourceType::K8s {
-                                pod: ds.name.clone(),
-                                namespace: ds.namespace.clone(),
-                                container: None,
-                            },
-                            source: Box::new(sources::k8s::K8sSource::new(
-                                ds.name,
-                                ds.namespace,
-                                None,
-                            )),
+                            source: sources::create_source(&source_type, config),
+                            source_type,
                         });
                     }
                 }
@@ -176,22 +248,20 @@ fn parse_sources(
                         anyhow::bail!("{}", e);
                     }
 
+                    let source_type = LogSourceType::Docker { container };
                     sources.push(ParsedSource {
-                        source_type: LogSourceType::Docker {
-                            container: container.clone(),
-                        },
-                        source: Box::new(sources::docker::DockerSource::new(container)),
+                        source: sources::create_source(&source_type, config),
+                        source_type,
                     });
                     i += 2;
                 } else {
                     // --docker without name: discover all Docker containers
                     if let Ok(docker_sources) = discover_docker_containers() {
                         for ds in docker_sources {
+                            let source_type = LogSourceType::Docker { container: ds.name };
                             sources.push(ParsedSource {
-                                source_type: LogSourceType::Docker {
-                                    container: ds.name.clone(),
-                                },
-                                source: Box::new(sources::docker::DockerSource::new(ds.name)),
+                                source: sources::create_source(&source_type, config),
+                                source_type,
                             });
                         }
                     }
@@ -212,67 +282,87 @@ fn parse_sources(
                     if let Err(e) = sources::k8s::validate_pod_name(&pod) {
                         anyhow::bail!("{}", e);
                     }
-                    let mut namespace: Option<String> = None;
-                    let mut container: Option<String> = None;
                     i += 2;
+                    let (scope, containers) = parse_k8s_options(args, &mut i);
 
-                    // Parse optional -n and -c following this --k8s
-                    while i < args.len() {
-                        match args[i].as_str() {
-                            "-n" | "--namespace" if i + 1 < args.len() => {
-                                namespace = Some(args[i + 1].clone());
-                                i += 2;
-                            }
-                            "-c" | "--container" if i + 1 < args.len() => {
-                                container = Some(args[i + 1].clone());
-                                i += 2;
-                            }
-                            // Stop at next source or unknown arg
-                            _ => break,
-                        }
+                    // deploy/api and friends: follow the workload's pods
+                    if let Some((kind, name)) = sources::k8s::parse_workload(&pod) {
+                        let selector = sources::k8s::resolve_workload_selector(kind, name, &scope)
+                            .map_err(|e| anyhow::anyhow!(e))?;
+                        add_pod_set_sources(
+                            &mut sources,
+                            &mut watches,
+                            config,
+                            selector,
+                            scope,
+                            containers,
+                        )?;
+                        continue;
                     }
 
-                    sources.push(ParsedSource {
-                        source_type: LogSourceType::K8s {
-                            pod: pod.clone(),
-                            namespace: namespace.clone(),
-                            container: container.clone(),
-                        },
-                        source: Box::new(sources::k8s::K8sSource::new(pod, namespace, container)),
-                    });
+                    let pod_sources = sources::k8s::pod_sources(&pod, &scope, &containers)
+                        .map_err(|e| anyhow::anyhow!(e))?;
+                    for source_type in pod_sources {
+                        sources.push(ParsedSource {
+                            source: sources::create_source(&source_type, config),
+                            source_type,
+                        });
+                    }
                 } else {
-                    // --k8s without pod name: parse optional namespace, then discover all
-                    let mut namespace: Option<String> = None;
+                    // --k8s without pod name: parse optional namespace and
+                    // context, then discover all
                     i += 1;
-
-                    // Check for -n flag
-                    if i < args.len()
-                        && (args[i] == "-n" || args[i] == "--namespace")
-                        && i + 1 < args.len()
-                    {
-                        namespace = Some(args[i + 1].clone());
-                        i += 2;
+                    let (scope, containers) = parse_k8s_options(args, &mut i);
+                    if containers != ContainerSelection::default() {
+                        anyhow::bail!(
+                            "-c, --all-containers and --previous need a pod or --k8s-selector"
+                        );
                     }
 
                     // Discover all K8s pods in namespace
-                    if let Ok(k8s_sources) = discover_k8s_pods(namespace.as_deref()) {
-                        for ds in k8s_sources {
+                    if let Ok(k8s_sources) = discover_k8s_pods(&scope) {
+                        for source_type in k8s_sources.iter().flat_map(|ds| ds.k8s_sources()) {
                             sources.push(ParsedSource {
-                                source_type: LogSourceType::K8s {
-                                    pod: ds.name.clone(),
-                                    namespace: ds.namespace.clone(),
-                                    container: None,
-                                },
-                                source: Box::new(sources::k8s::K8sSource::new(
-                                    ds.name,
-                                    ds.namespace,
-                                    None,
-                                )),
+                                source: sources::create_source(&source_type, config),
+                                source_type,
                             });
                         }
                     }
                 }
             }
+            "--k8s-selector" => {
+                if i + 1 >= args.len() {
+                    anyhow::bail!("--k8s-selector requires <label selector>");
+                }
+                let selector = args[i + 1].clone();
+                if let Err(e) = sources::k8s::validate_selector(&selector) {
+                    anyhow::bail!("{}", e);
+                }
+                i += 2;
+                let (scope, containers) = parse_k8s_options(args, &mut i);
+                add_pod_set_sources(
+                    &mut sources,
+                    &mut watches,
+                    config,
+                    selector,
+                    scope,
+                    containers,
+                )?;
+            }
+            "--docker-watch" => {
+                // Optional pattern; every container when none is given
+                let pattern = match args.get(i + 1).filter(|arg| !arg.starts_with('-')) {
+                    Some(arg) => {
+                        i += 2;
+                        ContainerPattern::parse(arg).map_err(|e| anyhow::anyhow!(e))?
+                    }
+                    None => {
+                        i += 1;
+                        ContainerPattern::All
+                    }
+                };
+                add_container_watch_sources(&mut sources, &mut watches, config, pattern)?;
+            }
             "--ssh" => {
                 if i + 2 >= args.len() {
                     anyhow::bail!("--ssh requires <host> <remote_path>");
@@ -303,10 +393,105 @@ fn parse_sources(
                 });
                 i += 3;
             }
+            "--journald" => {
+                let mut unit: Option<String> = None;
+                let mut priority: Option<String> = None;
+                let mut since: Option<String> = None;
+                i += 1;
+
+                // Parse optional -u, -p and --since following this --journald
+                while i + 1 < args.len() {
+                    let (kind, slot) = match args[i].as_str() {
+                        "-u" | "--unit" => ("unit", &mut unit),
+                        "-p" | "--priority" => ("priority", &mut priority),
+                        "--since" => ("since", &mut since),
+                        // Stop at next source or unknown arg
+                        _ => break,
+                    };
+                    let value = args[i + 1].clone();
+                    if let Err(e) = sources::journald::validate_filter_value(kind, &value) {
+                        anyhow::bail!("{}", e);
+                    }
+                    *slot = Some(value);
+                    i += 2;
+                }
+
+                sources.push(ParsedSource {
+                    source_type: LogSourceType::Journald {
+                        unit: unit.clone(),
+                        priority: priority.clone(),
+                        since: since.clone(),
+                    },
+                    source: Box::new(sources::journald::JournaldSource::new(
+                        unit, priority, since,
+                    )),
+                });
+            }
+            "--cmd" => {
+                if i + 1 >= args.len() {
+                    anyhow::bail!("--cmd requires \"<command line>\"");
+                }
+                let argv = match sources::command::split_command(&args[i + 1]) {
+                    Ok(argv) => argv,
+                    Err(e) => anyhow::bail!("{}", e),
+                };
+                sources.push(ParsedSource {
+                    source_type: LogSourceType::Command { argv: argv.clone() },
+                    source: Box::new(sources::command::CommandSource::new(argv)),
+                });
+                i += 2;
+            }
+            "--rotated" => {
+                if i + 1 >= args.len() || args[i + 1].starts_with('-') {
+                    anyhow::bail!("--rotated requires <file_path>");
+                }
+                let path = PathBuf::from(&args[i + 1]);
+                sources.push(ParsedSource {
+                    source_type: LogSourceType::File {
+                        path: path.clone(),
+                        rotated: true,
+                    },
+                    source: Box::new(FileSource::with_rotation_set(path)),
+                });
+                i += 2;
+            }
+            "--dir" => {
+                if i + 1 >= args.len() || args[i + 1].starts_with('-') {
+                    anyhow::bail!("--dir requires <directory>");
+                }
+                let dir = PathBuf::from(&args[i + 1]);
+                if !dir.is_dir() {
+                    anyhow::bail!("Not a directory: {}", dir.display());
+                }
+                add_glob_sources(&mut sources, &mut watches, watch::dir_pattern(&dir))?;
+                i += 2;
+            }
+            "-" => {
+                if !sources::stdin::stdin_is_piped() {
+                    anyhow::bail!("'-' reads from standard input, but nothing is piped in");
+                }
+                if !sources
+                    .iter()
+                    .any(|s| s.source_type == LogSourceType::Stdin)
+                {
+                    sources.push(ParsedSource {
+                        source_type: LogSourceType::Stdin,
+                        source: Box::new(sources::stdin::StdinSource),
+                    });
+                }
+                i += 1;
+            }
+            pattern if !pattern.starts_with('-') && watch::is_glob(pattern) => {
+                add_glob_sources(&mut sources, &mut watches, pattern.to_string())?;
+                i += 1;
+            }
             path if !path.starts_with('-') => {
                 let path = PathBuf::from(path);
                 sources.push(ParsedSource {
-                    source_type: LogSourceType::File { path: path.clone() },
+                    source_type: LogSourceType::File {
+                        path: path.clone(),
+                        rotated: false,
+                    },
                     source: Box::new(FileSource::new(path)),
                 });
                 i += 1;
@@ -317,7 +502,125 @@ fn parse_sources(
         }
     }
 
-    Ok((sources, None))
+    Ok((sources, None, watches))
+}
+
+/// Open every file currently matching a glob and keep watching it for changes
+fn add_glob_sources(
+    sources: &mut Vec<ParsedSource>,
+    watches: &mut Vec<WatchSpec>,
+    pattern: String,
+) -> Result<()> {
+    let known = watch::expand_glob(&pattern).map_err(|e| anyhow::anyhow!(e))?;
+    for path in &known {
+        let source_type = LogSourceType::File {
+            path: path.clone(),
+            rotated: false,
+        };
+        if !sources.iter().any(|s| s.source_type == source_type) {
+            sources.push(ParsedSource {
+                source_type,
+                source: Box::new(FileSource::new(path.clone())),
+            });
+        }
+    }
+    watches.push(WatchSpec::Files { pattern, known });
+    Ok(())
+}
+
+/// Parse the options following a Kubernetes source: -n, --context, -c,
+/// --all-containers and --previous
+fn parse_k8s_options(args: &[String], i: &mut usize) -> (Scope, ContainerSelection) {
+    let mut scope = Scope::default();
+    let mut containers = ContainerSelection::default();
+    while *i < args.len() {
+        match args[*i].as_str() {
+            "-n" | "--namespace" if *i + 1 < args.len() => {
+                scope.namespace = Some(args[*i + 1].clone());
+                *i += 2;
+            }
+            "--context" if *i + 1 < args.len() => {
+                scope.context = Some(args[*i + 1].clone());
+                *i += 2;
+            }
+            "-c" | "--container" if *i + 1 < args.len() => {
+                containers.container = Some(args[*i + 1].clone());
+                *i += 2;
+            }
+            "--all-containers" => {
+                containers.all_containers = true;
+                *i += 1;
+            }
+            "-p" | "--previous" => {
+                containers.previous = true;
+                *i += 1;
+            }
+            // Stop at next source or unknown arg
+            _ => break,
+        }
+    }
+    (scope, containers)
+}
+
+/// Open every container of the pods currently matching a label selector and
+/// keep watching it as pods come and go
+fn add_pod_set_sources(
+    sources: &mut Vec<ParsedSource>,
+    watches: &mut Vec<WatchSpec>,
+    config: &Config,
+    selector: String,
+    scope: Scope,
+    containers: ContainerSelection,
+) -> Result<()> {
+    let pods = sources::k8s::list_pod_set(&selector, &scope, &containers)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    for source_type in &pods {
+        if !sources.iter().any(|s| s.source_type == *source_type) {
+            sources.push(ParsedSource {
+                source_type: source_type.clone(),
+                source: sources::create_source(source_type, config),
+            });
+        }
+    }
+    watches.push(WatchSpec::Pods {
+        selector,
+        scope,
+        containers,
+        known: pods,
+    });
+    Ok(())
+}
+
+/// Open the running containers matching a pattern and follow the Docker
+/// event stream for ones started or stopped later
+fn add_container_watch_sources(
+    sources: &mut Vec<ParsedSource>,
+    watches: &mut Vec<WatchSpec>,
+    config: &Config,
+    pattern: ContainerPattern,
+) -> Result<()> {
+    let host = DockerHost::from_env().ok_or_else(|| {
+        anyhow::anyhow!(
+            "--docker-watch needs the Docker daemon socket ({}, or a unix:// or tcp:// DOCKER_HOST)",
+            sources::docker_api::DEFAULT_SOCKET
+        )
+    })?;
+    let containers = sources::docker_api::list_containers(&host).map_err(|e| anyhow::anyhow!(e))?;
+    let known = watch::matching_containers(containers, &pattern);
+    for source_type in &known {
+        if !sources.iter().any(|s| s.source_type == *source_type) {
+            sources.push(ParsedSource {
+                source_type: source_type.clone(),
+                source: sources::create_source(source_type, config),
+            });
+        }
+    }
+    watches.push(WatchSpec::Containers {
+        host,
+        pattern,
+        known,
+    });
+    Ok(())
 }
 
 fn print_help() {
@@ -330,10 +633,21 @@ fn print_help() {
     println!("    bark                                      # Open source picker");
     println!("    bark --docker                             # All Docker containers");
     println!("    bark --docker <container>                 # Specific container");
+    println!("    bark --docker-watch [pattern]             # Containers as they start");
     println!("    bark --k8s                                # All K8s pods");
     println!("    bark --k8s <pod> [-n namespace]           # Specific pod");
+    println!("    bark --k8s <kind>/<name> [-n namespace]   # Pods of a workload");
+    println!("    bark --k8s-selector <labels> [-n ns] [-c container]  # Pods by label");
+    println!("    bark --k8s <pod> --all-containers [--previous]  # Every container");
     println!("    bark --all                                # All Docker + K8s");
     println!("    bark <file_path>                          # Tail a file");
+    println!("    bark --browse <file_path>                 # Browse a whole file");
+    println!("    bark --rotated <file_path>                # Rotated archives, then tail");
+    println!("    bark '<glob>'                             # Files matching a pattern");
+    println!("    <command> | bark [-]                      # Piped standard input");
+    println!("    bark --journald [-u unit] [-p prio] [--since time]  # systemd journal");
+    println!("    bark --dir <directory>                    # Every file in a directory");
+    println!("    bark --cmd \"<command line>\"               # Output of any command");
     println!("    bark --ssh <host> <remote_path>           # Remote file via SSH");
     println!();
     println!("OPTIONS:");
@@ -342,9 +656,26 @@ fn print_help() {
     println!("    --all            Discover all Docker containers and K8s pods");
     println!();
     println!("SOURCES:");
-    println!("    <file_path>      Tail a local log file");
-    println!("    --docker         Follow Docker container logs (all if no name given)");
-    println!("    --k8s            Follow Kubernetes pod logs (all if no name given)");
+    println!("    <file_path>      Tail a local log file (.gz/.zst/.bz2 are read once)");
+    println!("    --rotated        Replay app.log.N[.gz] oldest first, then tail app.log");
+    println!("    '<glob>'         Tail matching files; new files are added, deleted ones end");
+    println!("    --dir            Tail every file in a directory, picking up new ones");
+    println!("    -                Read standard input (default when input is piped)");
+    println!("    --journald       Follow the systemd journal (-u unit, -p priority, --since)");
+    println!("    --cmd            Run a command (no shell) and follow its stdout and stderr");
+    println!("    --browse         Page through a whole file (any size) from the beginning");
+    println!("    --docker         Follow Docker container logs (all if no name given),");
+    println!("                     through the daemon socket or else the docker CLI");
+    println!("    --docker-watch   Follow containers matching a name glob, label=key[=value]");
+    println!("                     or image=glob (all if none given); containers started");
+    println!("                     later are added, stopped ones end (needs the socket)");
+    println!("    --k8s            Follow Kubernetes pod logs (all if no name given);");
+    println!("                     deploy/api follows the workload's pods across rollouts");
+    println!("    --k8s-selector   Follow every pod matching a label selector as pods come and go");
+    println!("                     (after --k8s or --k8s-selector: -n namespace, --context name,");
+    println!("                     -c container, --all-containers to add init containers,");
+    println!("                     --previous/-p to add the previous instance of restarted");
+    println!("                     containers)");
     println!("    --ssh            Tail a remote file via SSH");
     println!();
     println!("EXAMPLES:");
@@ -352,27 +683,47 @@ fn print_help() {
     println!("    bark --docker                             # All running containers");
     println!("    bark --docker nginx                       # Specific container");
     println!("    bark --docker nginx --docker redis        # Multiple containers");
+    println!("    bark --docker-watch 'shop-api-*'          # Replicas as they scale");
+    println!("    bark --docker-watch label=com.docker.compose.project=shop");
     println!("    bark --k8s -n production                  # All pods in namespace");
     println!("    bark --k8s my-app -n production           # Specific pod");
+    println!("    bark --k8s deploy/api -n production       # Survives rollouts");
+    println!("    bark --k8s-selector app=api,tier=web      # Pods by label");
+    println!("    bark --k8s deploy/api --context staging --k8s deploy/api --context prod");
     println!("    bark --all                                # Everything");
     println!("    bark /var/log/app.log --docker nginx      # Mixed sources");
     println!("    bark --ssh user@server /var/log/app.log   # Remote file");
+    println!("    bark --browse /var/log/app.log.1          # Yesterday's 5 GB log");
+    println!("    bark '/var/log/myapp/*.log'               # Watch a pattern");
+    println!("    journalctl -f | bark - --docker nginx     # Pipe plus other sources");
+    println!("    bark --journald -u nginx -p warning       # Unit warnings and worse");
+    println!("    bark --cmd \"aws logs tail --follow grp\"  # CloudWatch via the AWS CLI");
     println!();
     println!("KEYBOARD SHORTCUTS:");
     println!("    j/k              Scroll down/up");
     println!("    g/G              Go to top/bottom");
-    println!("    /                Start filter input");
+    println!("    /                Start filter input (text, regex or query:");
+    println!("                     level>=warn AND svc=api AND NOT msg~\"health\")");
     println!("    n/N              Next/previous match");
+    println!("    f/F              Pin filter as include/exclude (1-9 toggle, X clear)");
+    println!("    *                Highlight the filter text instead of filtering");
+    println!("    +/-              Raise/lower minimum log level");
+    println!("    {{/}}              Fewer/more context lines around matches");
     println!("    m                Toggle bookmark");
     println!("    [/]              Previous/next bookmark");
     println!("    t                Toggle relative time");
+    println!("    o                Toggle timestamp/arrival order");
     println!("    J                Toggle JSON pretty-print");
+    println!("    z/Z              Fold/unfold multi-line event (all events)");
+    println!("    T                Toggle table view of JSON fields");
+    println!("    C                Choose table columns");
     println!("    w                Toggle line wrap");
     println!("    b                Toggle side panel");
     println!("    Tab              Cycle panel focus");
     println!("    Space            Toggle source visibility (in Sources panel)");
+    println!("    i                Source details and recent errors");
     println!("    D                Open Docker container picker");
-    println!("    K                Open Kubernetes pod picker");
+    println!("    K                Open Kubernetes pod picker (c/n pick context/namespace)");
     println!("    e                Export filtered lines");
     println!("    ?                Show full help");
     println!("    q                Quit");
@@ -382,9 +733,14 @@ fn print_help() {
     println!("    BARK_THEME          Color theme (default, kawaii, cyber, dracula, monochrome)");
     println!("    BARK_LEVEL_COLORS   Enable level coloring (1/true or 0/false)");
     println!("    BARK_LINE_WRAP      Enable line wrapping (1/true or 0/false)");
+    println!("    BARK_TABLE_COLUMNS  Table view columns, comma-separated (e.g. ts,level,msg)");
+    println!(
+        "    DOCKER_HOST         Docker daemon (unix:// or tcp://; others use the docker CLI)"
+    );
     println!();
     println!("CONFIG:");
     println!("    ~/.config/barklog/config.toml");
+    println!("    ~/.config/barklog/state.json   (saved filters and history)");
     println!();
     println!("For more information, see: https://github.com/lance0/barklog");
 }
@@ -399,12 +755,16 @@ async fn run_event_loop<'a>(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     state: &mut AppState<'a>,
     event_rx: &mut tokio::sync::mpsc::Receiver<SourcedLogEvent>,
+    watch_rx: &mut tokio::sync::mpsc::Receiver<WatchEvent>,
     source_manager: &mut SourceManager,
+    config: &Config,
 ) -> Result<()> {
     // Track pending discovery task to avoid blocking UI
     let mut discovery_rx: Option<
         tokio::sync::oneshot::Receiver<anyhow::Result<Vec<discovery::DiscoveredSource>>>,
     > = None;
+    // Pending context/namespace listing for the K8s picker's chooser
+    let mut choices_rx: Option<(ScopeKind, tokio::sync::oneshot::Receiver<ScopeChoices>)> = None;
 
     // Track when we last drew for frame rate limiting
     let mut last_draw = std::time::Instant::now();
@@ -413,6 +773,12 @@ async fn run_event_loop<'a>(
         // Check filter debounce before drawing
         state.check_filter_debounce();
 
+        // Index and filter more of the browsed file (bounded work per frame)
+        state.tick_browse();
+
+        // Release lines held back for timestamp ordering
+        state.tick_merge();
+
         // Clear pending discovery if picker was closed
         if !state.picker.visible && discovery_rx.is_some() {
             discovery_rx = None;
@@ -421,6 +787,7 @@ async fn run_event_loop<'a>(
         // Check if picker needs to trigger discovery (non-blocking)
         if state.picker.visible && state.picker.loading && discovery_rx.is_none() {
             let mode = state.picker.mode;
+            let scope = state.picker.k8s_scope();
             let (tx, rx) = tokio::sync::oneshot::channel();
             discovery_rx = Some(rx);
 
@@ -428,7 +795,7 @@ async fn run_event_loop<'a>(
             tokio::spawn(async move {
                 let result = tokio::task::spawn_blocking(move || match mode {
                     PickerMode::Docker => discover_docker_containers(),
-                    PickerMode::K8s => discover_k8s_pods(None),
+                    PickerMode::K8s => discover_k8s_pods(&scope),
                 })
                 .await
                 .unwrap_or_else(|e| Err(anyhow::anyhow!("Discovery task panicked: {}", e)));
@@ -457,6 +824,52 @@ async fn run_event_loop<'a>(
             }
         }
 
+        // List contexts or namespaces for the chooser (non-blocking)
+        let chooser = state
+            .picker
+            .scope_chooser
+            .as_ref()
+            .map(|c| (c.kind, c.loading));
+        if choices_rx
+            .as_ref()
+            .is_some_and(|(kind, _)| chooser.map(|(k, _)| k) != Some(*kind))
+        {
+            choices_rx = None;
+        }
+        if let (Some((kind, true)), None) = (chooser, &choices_rx) {
+            let context = state.picker.k8s_context.clone();
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            choices_rx = Some((kind, rx));
+            tokio::spawn(async move {
+                let result = tokio::task::spawn_blocking(move || match kind {
+                    ScopeKind::Context => discover_k8s_contexts(),
+                    ScopeKind::Namespace => {
+                        discover_k8s_namespaces(context.as_deref()).map(|names| (names, None))
+                    }
+                })
+                .await
+                .unwrap_or_else(|e| Err(anyhow::anyhow!("Discovery task panicked: {}", e)));
+                let _ = tx.send(result);
+            });
+        }
+        if let Some((_, rx)) = &mut choices_rx {
+            match rx.try_recv() {
+                Ok(result) => {
+                    state
+                        .picker
+                        .set_scope_choices(result.map_err(|e| e.to_string()));
+                    choices_rx = None;
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => {}
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    state
+                        .picker
+                        .set_scope_choices(Err("Discovery task failed".to_string()));
+                    choices_rx = None;
+                }
+            }
+        }
+
         // Throttled drawing - only draw if enough time has passed
         let elapsed = last_draw.elapsed();
         if elapsed >= FRAME_DURATION {
@@ -494,11 +907,14 @@ async fn run_event_loop<'a>(
                                                     (LogSourceType::Docker { container }, PickerMode::Docker) => {
                                                         container == &to_remove.name
                                                     }
-                                                    (LogSourceType::K8s { pod, namespace, .. }, PickerMode::K8s) => {
-                                                        pod == &to_remove.name && *namespace == to_remove.namespace
+                                                    (LogSourceType::K8s { pod, context, namespace, .. }, PickerMode::K8s) => {
+                                                        pod == &to_remove.name
+                                                            && *namespace == to_remove.namespace
+                                                            && *context == to_remove.context
                                                     }
                                                     _ => false,
                                                 };
+                                                // Every container of a pod is its own source
                                                 if matches {
                                                     // Hide in all panes
                                                     for pane in &mut state.panes {
@@ -507,43 +923,28 @@ async fn run_event_loop<'a>(
                                                         }
                                                     }
                                                     removed_count += 1;
-                                                    break;
                                                 }
                                             }
                                         }
 
                                         // Add new sources
                                         for selected in add {
-                                            let source_id = state.sources.len();
-                                            let (source_type, source): (LogSourceType, Box<dyn LogSource>) = match mode {
-                                                PickerMode::Docker => {
-                                                    (
-                                                        LogSourceType::Docker { container: selected.name.clone() },
-                                                        Box::new(sources::docker::DockerSource::new(selected.name)),
-                                                    )
-                                                }
-                                                PickerMode::K8s => {
-                                                    (
-                                                        LogSourceType::K8s {
-                                                            pod: selected.name.clone(),
-                                                            namespace: selected.namespace.clone(),
-                                                            container: None,
-                                                        },
-                                                        Box::new(sources::k8s::K8sSource::new(
-                                                            selected.name,
-                                                            selected.namespace,
-                                                            None,
-                                                        )),
-                                                    )
-                                                }
+                                            let source_types = match mode {
+                                                PickerMode::Docker => vec![LogSourceType::Docker {
+                                                    container: selected.name.clone(),
+                                                }],
+                                                PickerMode::K8s => selected.k8s_sources(),
                                             };
+                                            for source_type in source_types {
+                                                let source = sources::create_source(&source_type, config);
 
-                                            // Add to app state
-                                            state.add_source(source_type);
+                                                // Add to app state
+                                                let source_id = state.add_source(source_type);
 
-                                            // Add to source manager
-                                            source_manager.add_source(source_id, source).await;
-                                            added_count += 1;
+                                                // Add to source manager
+                                                source_manager.add_source(source_id, source).await;
+                                                added_count += 1;
+                                            }
                                         }
 
                                         // Status message
@@ -574,59 +975,26 @@ async fn run_event_loop<'a>(
                 let mut batch: Vec<LogLine> = Vec::new();
 
                 // Process the first event
-                match sourced_event.event {
-                    LogEvent::Line(line) => {
-                        batch.push(line.with_source_id(sourced_event.source_id));
-                    }
-                    LogEvent::Error(msg) => {
-                        let source_name = state.sources
-                            .get(sourced_event.source_id)
-                            .map(|s| s.name())
-                            .unwrap_or_else(|| "unknown".to_string());
-                        state.status_message = Some(format!("[{}] Error: {}", source_name, msg));
-                    }
-                    LogEvent::EndOfStream => {
-                        let source_name = state.sources
-                            .get(sourced_event.source_id)
-                            .map(|s| s.name())
-                            .unwrap_or_else(|| "unknown".to_string());
-                        state.status_message = Some(format!("[{}] Stream ended", source_name));
-                    }
-                }
+                handle_source_event(state, sourced_event, &mut batch);
 
                 // Drain any additional available events (non-blocking)
                 while batch.len() < MAX_BATCH_SIZE {
                     match event_rx.try_recv() {
-                        Ok(sourced_event) => {
-                            match sourced_event.event {
-                                LogEvent::Line(line) => {
-                                    batch.push(line.with_source_id(sourced_event.source_id));
-                                }
-                                LogEvent::Error(msg) => {
-                                    let source_name = state.sources
-                                        .get(sourced_event.source_id)
-                                        .map(|s| s.name())
-                                        .unwrap_or_else(|| "unknown".to_string());
-                                    state.status_message = Some(format!("[{}] Error: {}", source_name, msg));
-                                }
-                                LogEvent::EndOfStream => {
-                                    let source_name = state.sources
-                                        .get(sourced_event.source_id)
-                                        .map(|s| s.name())
-                                        .unwrap_or_else(|| "unknown".to_string());
-                                    state.status_message = Some(format!("[{}] Stream ended", source_name));
-                                }
-                            }
-                        }
+                        Ok(sourced_event) => handle_source_event(state, sourced_event, &mut batch),
                         Err(_) => break, // No more events available
                     }
                 }
 
                 // Push all batched lines at once
                 if !batch.is_empty() {
-                    state.push_lines(batch);
+                    state.receive_lines(batch);
                 }
             }
+
+            // Sources appearing or disappearing under a watched glob/directory
+            Some(watch_event) = watch_rx.recv() => {
+                handle_watch_event(state, source_manager, config, watch_event).await;
+            }
         }
 
         // Check if we should quit
@@ -637,3 +1005,82 @@ async fn run_event_loop<'a>(
 
     Ok(())
 }
+
+/// Apply a single source event: lines are collected into the batch,
+/// everything else updates the status line
+fn handle_source_event(
+    state: &mut AppState,
+    sourced_event: SourcedLogEvent,
+    batch: &mut Vec<LogLine>,
+) {
+    let source_name = || {
+        state
+            .sources
+            .get(sourced_event.source_id)
+            .map(|s| s.name())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+
+    match sourced_event.event {
+        LogEvent::Line(line) => {
+            state.record_source_lines(sourced_event.source_id, 1);
+            if let Some(timestamp) = line.timestamp {
+                state.record_source_timestamp(sourced_event.source_id, timestamp);
+            }
+            batch.push(line.with_source_id(sourced_event.source_id));
+        }
+        LogEvent::Error(msg) => {
+            state.status_message = Some(format!("[{}] Error: {}", source_name(), msg));
+            state.record_source_error(sourced_event.source_id, msg);
+        }
+        LogEvent::Notice(msg) => {
+            state.status_message = Some(format!("[{}] {}", source_name(), msg));
+        }
+        LogEvent::Reconnecting { attempt } => {
+            state.status_message = Some(format!(
+                "[{}] Connection lost, reconnecting (attempt {})",
+                source_name(),
+                attempt
+            ));
+            state.set_source_status(sourced_event.source_id, SourceStatus::Reconnecting(attempt));
+        }
+        LogEvent::EndOfStream => {
+            state.status_message = Some(format!("[{}] Stream ended", source_name()));
+            state.set_source_status(sourced_event.source_id, SourceStatus::Ended);
+        }
+    }
+}
+
+/// Start or stop sources reported by a watcher
+async fn handle_watch_event(
+    state: &mut AppState<'_>,
+    source_manager: &mut SourceManager,
+    config: &Config,
+    watch_event: WatchEvent,
+) {
+    match watch_event {
+        WatchEvent::Added(source_type) => {
+            let Some(source_id) = state.watched_source_added(&source_type) else {
+                return;
+            };
+            // A restarted container picks up after the lines already shown
+            let after = state.source_last_timestamp(source_id);
+            source_manager
+                .add_source(
+                    source_id,
+                    sources::create_resumed_source(&source_type, config, after),
+                )
+                .await;
+            state.status_message = Some(format!("New source: {}", source_type.name()));
+        }
+        WatchEvent::Removed(source_type) => {
+            if let Some(idx) = state.watched_source_removed(&source_type) {
+                source_manager.remove_source(idx);
+                state.status_message = Some(format!("Source ended: {}", source_type.name()));
+            }
+        }
+        WatchEvent::Error(message) => {
+            state.status_message = Some(message);
+        }
+    }
+}
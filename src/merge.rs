@@ -0,0 +1,194 @@
+//! Timestamp-ordered merging of lines from several sources.
+//!
+//! Sources deliver lines at different speeds (a slow `ssh` link lags behind a
+//! local Docker daemon), so arrival order is not time order. `ReorderBuffer`
+//! holds each line for a short window and releases lines sorted by
+//! `LogLine.timestamp`. A line without a timestamp sorts right after the
+//! previous line of the same source (keeping stack traces attached), or by its
+//! arrival time when the source has not produced a timestamp yet.
+
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::app::LogLine;
+
+/// Sort key: timestamp, then arrival sequence to keep ties stable
+type MergeKey = (DateTime<Local>, u64);
+
+/// Holds lines back for a short window and releases them in timestamp order
+pub struct ReorderBuffer {
+    /// How long a line is held before it is released
+    window: Duration,
+    /// Held lines, in release order
+    pending: BTreeMap<MergeKey, LogLine>,
+    /// Arrival time and key of each held line, oldest first
+    arrivals: VecDeque<(Instant, MergeKey)>,
+    /// Last timestamp seen from each source
+    last_timestamp: HashMap<usize, DateTime<Local>>,
+    /// Next arrival sequence number
+    seq: u64,
+}
+
+impl ReorderBuffer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: BTreeMap::new(),
+            arrivals: VecDeque::new(),
+            last_timestamp: HashMap::new(),
+            seq: 0,
+        }
+    }
+
+    /// Whether no lines are held back
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Add newly arrived lines
+    pub fn push(&mut self, lines: Vec<LogLine>, now: Instant) {
+        for line in lines {
+            let source_id = line.source_id;
+            let timestamp = match line.timestamp {
+                Some(ts) => {
+                    self.last_timestamp.insert(source_id, ts);
+                    ts
+                }
+                None => self
+                    .last_timestamp
+                    .get(&source_id)
+                    .copied()
+                    .unwrap_or_else(Local::now),
+            };
+            let key = (timestamp, self.seq);
+            self.seq += 1;
+            self.pending.insert(key, line);
+            self.arrivals.push_back((now, key));
+        }
+    }
+
+    /// Release the lines that have been held for the full window, plus any
+    /// held line that sorts before them
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<LogLine> {
+        let mut watermark: Option<MergeKey> = None;
+        while let Some(&(arrived, key)) = self.arrivals.front() {
+            if now.saturating_duration_since(arrived) < self.window {
+                break;
+            }
+            self.arrivals.pop_front();
+            watermark = watermark.max(Some(key));
+        }
+        let Some(watermark) = watermark else {
+            return Vec::new();
+        };
+
+        // Everything up to and including the watermark goes out in order
+        let rest = self.pending.split_off(&(watermark.0, watermark.1 + 1));
+        let ready = std::mem::replace(&mut self.pending, rest);
+        ready.into_values().collect()
+    }
+
+    /// Release every held line in order (e.g. when switching to arrival order)
+    pub fn drain_all(&mut self) -> Vec<LogLine> {
+        self.arrivals.clear();
+        std::mem::take(&mut self.pending).into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn line(source_id: usize, text: &str, secs: Option<i64>) -> LogLine {
+        let line = LogLine::new(text.to_string()).with_source_id(source_id);
+        match secs {
+            Some(s) => line.with_timestamp(Local.timestamp_opt(1_700_000_000 + s, 0).unwrap()),
+            None => line,
+        }
+    }
+
+    fn texts(lines: &[LogLine]) -> Vec<&str> {
+        lines.iter().map(|l| l.raw.as_str()).collect()
+    }
+
+    #[test]
+    fn test_late_source_is_merged_by_timestamp() {
+        let window = Duration::from_millis(500);
+        let mut buffer = ReorderBuffer::new(window);
+        let t0 = Instant::now();
+
+        buffer.push(
+            vec![line(0, "docker 2", Some(2)), line(0, "docker 4", Some(4))],
+            t0,
+        );
+        // The slow source delivers older lines a moment later
+        buffer.push(
+            vec![line(1, "ssh 1", Some(1)), line(1, "ssh 3", Some(3))],
+            t0 + Duration::from_millis(200),
+        );
+
+        assert!(
+            buffer
+                .drain_ready(t0 + Duration::from_millis(100))
+                .is_empty()
+        );
+        let ready = buffer.drain_ready(t0 + window + Duration::from_millis(200));
+        assert_eq!(
+            texts(&ready),
+            vec!["ssh 1", "docker 2", "ssh 3", "docker 4"]
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_aged_line_releases_older_lines_first() {
+        let window = Duration::from_millis(500);
+        let mut buffer = ReorderBuffer::new(window);
+        let t0 = Instant::now();
+
+        buffer.push(vec![line(0, "a 5", Some(5))], t0);
+        buffer.push(
+            vec![line(1, "b 1", Some(1)), line(1, "b 9", Some(9))],
+            t0 + Duration::from_millis(300),
+        );
+
+        // "a 5" has aged: it goes out with the older "b 1"; "b 9" waits
+        let ready = buffer.drain_ready(t0 + window);
+        assert_eq!(texts(&ready), vec!["b 1", "a 5"]);
+        assert_eq!(texts(&buffer.drain_all()), vec!["b 9"]);
+    }
+
+    #[test]
+    fn test_lines_without_timestamp_follow_their_source() {
+        let mut buffer = ReorderBuffer::new(Duration::ZERO);
+        let t0 = Instant::now();
+
+        buffer.push(
+            vec![
+                line(0, "error at 5", Some(5)),
+                line(0, "  at frame 1", None),
+                line(0, "  at frame 2", None),
+                line(1, "other at 6", Some(6)),
+            ],
+            t0,
+        );
+        let ready = buffer.drain_ready(t0);
+        assert_eq!(
+            texts(&ready),
+            vec!["error at 5", "  at frame 1", "  at frame 2", "other at 6"]
+        );
+    }
+
+    #[test]
+    fn test_drain_all_keeps_order() {
+        let mut buffer = ReorderBuffer::new(Duration::from_secs(60));
+        let t0 = Instant::now();
+        buffer.push(vec![line(0, "two", Some(2)), line(1, "one", Some(1))], t0);
+
+        assert!(buffer.drain_ready(t0).is_empty());
+        assert_eq!(texts(&buffer.drain_all()), vec!["one", "two"]);
+        assert!(buffer.is_empty());
+    }
+}
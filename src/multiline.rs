@@ -0,0 +1,360 @@
+//! Multi-line events: stack traces, pretty-printed JSON and other output
+//! that spans several lines.
+//!
+//! `[[multiline]]` rules in the config file say which lines continue the
+//! event before them. Continuation lines are joined into that event (with
+//! `\n`) before it's stored, so filtering, bookmarks, yank and export see
+//! the whole event. The last event of each source is held until the next
+//! one starts or its source goes quiet for a moment; lines that arrived
+//! after it, from any source, wait behind it so arrival order is kept.
+
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::app::LogLine;
+use crate::config::MultilineConfig;
+use crate::sources::LogSourceType;
+
+/// How a rule tells continuation lines apart
+#[derive(Debug)]
+enum Boundary {
+    /// Lines matching the regex continue the previous event
+    Continuation(Regex),
+    /// Lines matching the regex start a new event
+    Start(Regex),
+}
+
+/// A compiled `[[multiline]]` rule
+#[derive(Debug)]
+pub struct MultilineRule {
+    boundary: Boundary,
+    max_lines: usize,
+    sources: glob::Pattern,
+}
+
+impl MultilineRule {
+    pub fn new(config: &MultilineConfig) -> Result<Self, String> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("Multiline rule: invalid regex: {}", e))
+        };
+        let boundary = match (&config.continuation, &config.start) {
+            (Some(pattern), None) => Boundary::Continuation(compile(pattern)?),
+            (None, Some(pattern)) => Boundary::Start(compile(pattern)?),
+            _ => {
+                return Err("Multiline rule: set either `continuation` or `start`".to_string());
+            }
+        };
+        let sources = glob::Pattern::new(&config.sources)
+            .map_err(|e| format!("Multiline rule: invalid sources glob: {}", e))?;
+        Ok(Self {
+            boundary,
+            max_lines: config.max_lines.max(1),
+            sources,
+        })
+    }
+
+    /// Whether `raw` belongs to the event before it
+    fn continues(&self, raw: &str) -> bool {
+        match &self.boundary {
+            Boundary::Continuation(regex) => regex.is_match(raw),
+            Boundary::Start(regex) => !regex.is_match(raw),
+        }
+    }
+}
+
+/// An event still waiting for continuation lines
+struct Pending {
+    /// Arrival number of the first line
+    seq: u64,
+    first: LogLine,
+    rest: Vec<String>,
+    /// When the last line was added
+    updated: Instant,
+}
+
+impl Pending {
+    /// The event as one line: level, timestamp and fields come from its
+    /// first line, unless the whole event is a JSON document
+    fn finish(self) -> LogLine {
+        let Pending { first, rest, .. } = self;
+        if rest.is_empty() {
+            return first;
+        }
+        let mut raw = first.raw;
+        for line in rest {
+            raw.push('\n');
+            raw.push_str(&line);
+        }
+        let whole = LogLine::new(raw);
+        if whole.is_json {
+            return whole.with_source_id(first.source_id);
+        }
+        LogLine {
+            has_ansi: whole.has_ansi,
+            raw: whole.raw,
+            ..first
+        }
+    }
+}
+
+/// Joins continuation lines into events, per source
+pub struct EventGrouper {
+    rules: Vec<MultilineRule>,
+    /// Index into `rules` for each source
+    by_source: Vec<Option<usize>>,
+    /// Last event of each source, by source id
+    pending: HashMap<usize, Pending>,
+    /// Finished events and lines of sources without a rule that arrived
+    /// after a pending event, by arrival number
+    finished: BTreeMap<u64, LogLine>,
+    /// Arrival number of the next event
+    next_seq: u64,
+    /// How long an event waits for more lines
+    idle: Duration,
+}
+
+impl EventGrouper {
+    /// Compile the configured rules; invalid ones are skipped and reported
+    pub fn new(configs: &[MultilineConfig], idle: Duration) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let rules = configs
+            .iter()
+            .filter_map(|config| MultilineRule::new(config).map_err(|e| errors.push(e)).ok())
+            .collect();
+        (
+            Self {
+                rules,
+                by_source: Vec::new(),
+                pending: HashMap::new(),
+                finished: BTreeMap::new(),
+                next_seq: 0,
+                idle,
+            },
+            errors,
+        )
+    }
+
+    /// Register the next source (sources are numbered in the order added);
+    /// the first rule whose glob matches it applies
+    pub fn add_source(&mut self, source: &LogSourceType) {
+        let rule = self
+            .rules
+            .iter()
+            .position(|rule| source.matches_glob(&rule.sources));
+        self.by_source.push(rule);
+    }
+
+    /// Whether any event is waiting for more lines
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.finished.is_empty()
+    }
+
+    /// Add newly arrived lines, returning the events they complete (and
+    /// lines of sources without a rule) in arrival order, up to the oldest
+    /// event still waiting for more lines
+    pub fn push(&mut self, lines: Vec<LogLine>, now: Instant) -> Vec<LogLine> {
+        for line in lines {
+            let source_id = line.source_id;
+            let rule = self.by_source.get(source_id).copied().flatten();
+            if let (Some(rule), Some(pending)) = (rule, self.pending.get_mut(&source_id)) {
+                let rule = &self.rules[rule];
+                if pending.rest.len() + 1 < rule.max_lines && rule.continues(&line.raw) {
+                    pending.rest.push(line.raw);
+                    pending.updated = now;
+                    continue;
+                }
+            }
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            if rule.is_none() {
+                self.finished.insert(seq, line);
+                continue;
+            }
+            let next = Pending {
+                seq,
+                first: line,
+                rest: Vec::new(),
+                updated: now,
+            };
+            if let Some(previous) = self.pending.insert(source_id, next) {
+                self.finished.insert(previous.seq, previous.finish());
+            }
+        }
+        self.release()
+    }
+
+    /// Release events whose source has been quiet for the idle window, and
+    /// the lines that were waiting behind them
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<LogLine> {
+        let ready: Vec<usize> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| now.saturating_duration_since(pending.updated) >= self.idle)
+            .map(|(&source_id, _)| source_id)
+            .collect();
+        for source_id in ready {
+            if let Some(pending) = self.pending.remove(&source_id) {
+                self.finished.insert(pending.seq, pending.finish());
+            }
+        }
+        self.release()
+    }
+
+    /// Finished lines that no pending event arrived before, in arrival order
+    fn release(&mut self) -> Vec<LogLine> {
+        let oldest_pending = self.pending.values().map(|p| p.seq).min();
+        let held = match oldest_pending {
+            Some(seq) => self.finished.split_off(&seq),
+            None => BTreeMap::new(),
+        };
+        std::mem::replace(&mut self.finished, held)
+            .into_values()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogLevel;
+
+    fn grouper(continuation: Option<&str>, start: Option<&str>) -> EventGrouper {
+        let config = MultilineConfig {
+            continuation: continuation.map(str::to_string),
+            start: start.map(str::to_string),
+            max_lines: 4,
+            sources: "*".to_string(),
+        };
+        let (mut grouper, errors) = EventGrouper::new(&[config], Duration::from_millis(300));
+        assert!(errors.is_empty());
+        grouper.add_source(&LogSourceType::Stdin);
+        grouper
+    }
+
+    fn lines(raws: &[&str]) -> Vec<LogLine> {
+        raws.iter()
+            .map(|raw| LogLine::new(raw.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_continuation_lines_join_the_previous_event() {
+        let mut events = grouper(Some(r"^(\s|Caused by)"), None);
+        let now = Instant::now();
+        let done = events.push(
+            lines(&[
+                "ERROR request failed",
+                "java.lang.IllegalStateException: boom",
+                "    at com.example.Api.handle(Api.java:42)",
+                "Caused by: java.io.IOException: reset",
+                "INFO next request",
+            ]),
+            now,
+        );
+        assert_eq!(done.len(), 2);
+        assert_eq!(done[0].raw, "ERROR request failed");
+        assert!(done[1].raw.starts_with("java.lang.IllegalStateException"));
+        assert_eq!(done[1].raw.lines().count(), 3);
+
+        // The last event waits for more lines, then goes out when idle
+        assert!(events.drain_ready(now).is_empty());
+        let done = events.drain_ready(now + Duration::from_secs(1));
+        assert_eq!(done[0].raw, "INFO next request");
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_start_pattern_keeps_first_line_level_and_limits_size() {
+        let mut events = grouper(None, Some(r"^\d{4}-"));
+        let now = Instant::now();
+        let mut done = events.push(
+            lines(&[
+                "2024-03-01T12:00:00Z WARN retrying",
+                "  attempt 1 INFO",
+                "  attempt 2",
+                "  attempt 3",
+                "  attempt 4",
+            ]),
+            now,
+        );
+        done.extend(events.drain_ready(now + Duration::from_secs(1)));
+        assert_eq!(done.len(), 2);
+        assert_eq!(done[0].raw.lines().count(), 4);
+        assert_eq!(done[0].level, LogLevel::Warn);
+        assert!(done[0].timestamp.is_some());
+        assert_eq!(done[1].raw, "  attempt 4");
+    }
+
+    #[test]
+    fn test_pretty_json_event_is_parsed_whole() {
+        let mut events = grouper(Some(r"^(\s|\})"), None);
+        let now = Instant::now();
+        events.push(
+            lines(&["{", r#"  "level": "error","#, r#"  "msg": "boom""#, "}"]),
+            now,
+        );
+        let done = events.drain_ready(now + Duration::from_secs(1));
+        assert!(done[0].is_json);
+        assert_eq!(done[0].level, LogLevel::Error);
+    }
+
+    #[test]
+    fn test_lines_without_a_rule_wait_behind_older_events() {
+        let config = MultilineConfig {
+            continuation: Some(r"^\s".to_string()),
+            start: None,
+            max_lines: 100,
+            sources: "docker:*".to_string(),
+        };
+        let (mut events, _) = EventGrouper::new(&[config], Duration::from_millis(300));
+        events.add_source(&LogSourceType::Docker {
+            container: "api".to_string(),
+        });
+        events.add_source(&LogSourceType::Stdin);
+        let line = |source_id, raw: &str| LogLine::new(raw.to_string()).with_source_id(source_id);
+
+        let now = Instant::now();
+        let done = events.push(
+            vec![
+                line(1, "stdin before"),
+                line(0, "ERROR boom"),
+                line(1, "stdin during"),
+                line(0, "    at handler"),
+            ],
+            now,
+        );
+        // The trace may still grow, so what came after it waits
+        let raw: Vec<&str> = done.iter().map(|l| l.raw.as_str()).collect();
+        assert_eq!(raw, vec!["stdin before"]);
+        assert!(!events.is_empty());
+
+        let done = events.drain_ready(now + Duration::from_secs(1));
+        let raw: Vec<&str> = done.iter().map(|l| l.raw.as_str()).collect();
+        assert_eq!(raw, vec!["ERROR boom\n    at handler", "stdin during"]);
+        assert!(events.is_empty());
+
+        // With nothing pending, lines without a rule go straight through
+        let done = events.push(vec![line(1, "stdin after")], now);
+        assert_eq!(done[0].raw, "stdin after");
+    }
+
+    #[test]
+    fn test_invalid_rules_are_reported() {
+        let config = |continuation: Option<&str>, start: Option<&str>| MultilineConfig {
+            continuation: continuation.map(str::to_string),
+            start: start.map(str::to_string),
+            max_lines: 500,
+            sources: "*".to_string(),
+        };
+        let (_, errors) = EventGrouper::new(
+            &[
+                config(None, None),
+                config(Some("("), None),
+                config(Some("a"), Some("b")),
+            ],
+            Duration::ZERO,
+        );
+        assert_eq!(errors.len(), 3);
+    }
+}
@@ -0,0 +1,597 @@
+//! Structured field extraction.
+//!
+//! Lines that carry structure (JSON objects and logfmt) are split into
+//! key/value fields once, when the line is ingested. Nested JSON objects are
+//! flattened into dotted keys (`http.status`). Well-known keys supply the
+//! line's level, timestamp and message, so structured logs don't depend on
+//! the text heuristics in `LogLevel::detect`.
+//!
+//! Formats bark can't recognize on its own are described in the config file
+//! as regexes with named groups (`LineParser`), applied per source.
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, TimeZone};
+use regex::Regex;
+use serde_json::Value;
+use std::ops::Range;
+
+use crate::app::{LogLevel, LogLine};
+use crate::config::ParserConfig;
+use crate::sources::LogSourceType;
+
+/// Ordered key/value pairs extracted from a line
+pub type Fields = Vec<(String, String)>;
+
+/// Keys that hold the level, most common first
+pub const LEVEL_KEYS: &[&str] = &["level", "lvl", "severity", "log.level", "loglevel"];
+
+/// Keys that hold the timestamp, most common first
+pub const TIMESTAMP_KEYS: &[&str] = &["ts", "time", "timestamp", "@timestamp", "t"];
+
+/// Keys that hold the human-readable message, most common first
+pub const MESSAGE_KEYS: &[&str] = &["msg", "message", "@message", "event"];
+
+/// Look up a field value by key
+pub fn field<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+/// The first of `keys` present in `fields`
+pub fn first_key<'a>(fields: &[(String, String)], keys: &[&'a str]) -> Option<&'a str> {
+    keys.iter()
+        .copied()
+        .find(|key| fields.iter().any(|(k, _)| k == key))
+}
+
+/// Timestamp, level and message columns for the first structured line
+pub fn default_columns<'a>(rows: impl IntoIterator<Item = &'a Fields>) -> Vec<String> {
+    let Some(fields) = rows.into_iter().find(|f| !f.is_empty()) else {
+        return Vec::new();
+    };
+    [TIMESTAMP_KEYS, LEVEL_KEYS, MESSAGE_KEYS]
+        .iter()
+        .filter_map(|keys| first_key(fields, keys))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse a JSON object line into flattened fields
+pub fn parse_json_fields(line: &str) -> Option<Fields> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    let Value::Object(map) = serde_json::from_str::<Value>(trimmed).ok()? else {
+        return None;
+    };
+    let mut fields = Fields::new();
+    for (key, value) in map {
+        flatten(&key, value, &mut fields);
+    }
+    Some(fields)
+}
+
+fn flatten(key: &str, value: Value, out: &mut Fields) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                flatten(&format!("{}.{}", key, k), v, out);
+            }
+        }
+        Value::String(s) => out.push((key.to_string(), s)),
+        other => out.push((key.to_string(), other.to_string())),
+    }
+}
+
+/// Byte ranges of one logfmt `key=value` pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogfmtPair {
+    pub key: Range<usize>,
+    /// The value as written, including any quotes
+    pub value: Range<usize>,
+}
+
+/// Split a logfmt line (`level=info msg="started" dur=3ms`) into pairs.
+/// Every word must be a `key=value` pair and there must be at least two,
+/// so prose that happens to contain an `=` is not mistaken for logfmt.
+pub fn scan_logfmt(line: &str) -> Option<Vec<LogfmtPair>> {
+    let bytes = line.as_bytes();
+    let mut pairs = Vec::new();
+    let mut i = 0;
+
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == bytes.len() {
+            break;
+        }
+
+        let key_start = i;
+        while i < bytes.len() && !matches!(bytes[i], b'=' | b'"' | b' ' | b'\t') {
+            i += 1;
+        }
+        if i == key_start || bytes.get(i) != Some(&b'=') {
+            return None;
+        }
+        let key = key_start..i;
+        i += 1;
+
+        let value_start = i;
+        if bytes.get(i) == Some(&b'"') {
+            i += 1;
+            loop {
+                match bytes.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => break,
+                    _ => i += 1,
+                }
+            }
+            i += 1;
+        } else {
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                if bytes[i] == b'"' {
+                    return None;
+                }
+                i += 1;
+            }
+        }
+        // A value runs up to the next space
+        if i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+            return None;
+        }
+        pairs.push(LogfmtPair {
+            key,
+            value: value_start..i,
+        });
+    }
+
+    (pairs.len() >= 2).then_some(pairs)
+}
+
+/// Remove the quotes and escapes from a logfmt value
+fn unquote_logfmt(value: &str) -> String {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parse a logfmt line into fields
+pub fn parse_logfmt_fields(line: &str) -> Option<Fields> {
+    let pairs = scan_logfmt(line)?;
+    Some(
+        pairs
+            .into_iter()
+            .map(|pair| {
+                (
+                    line[pair.key].to_string(),
+                    unquote_logfmt(&line[pair.value]),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Level from a well-known level field
+pub fn level_from_fields(fields: &[(String, String)]) -> Option<LogLevel> {
+    let value = LEVEL_KEYS.iter().find_map(|key| field(fields, key))?;
+    // Numeric levels as used by pino/bunyan
+    if let Ok(n) = value.parse::<u32>() {
+        return Some(match n {
+            0..=10 => LogLevel::Trace,
+            11..=20 => LogLevel::Debug,
+            21..=30 => LogLevel::Info,
+            31..=40 => LogLevel::Warn,
+            _ => LogLevel::Error,
+        });
+    }
+    LogLevel::from_name(value)
+}
+
+/// Timestamp from a well-known time field (RFC 3339 text or a Unix epoch).
+/// A bare `t` counts only as RFC 3339: it is as often a duration.
+pub fn timestamp_from_fields(fields: &[(String, String)]) -> Option<DateTime<Local>> {
+    TIMESTAMP_KEYS.iter().find_map(|key| {
+        let value = field(fields, key)?;
+        if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+            return Some(dt.with_timezone(&Local));
+        }
+        if *key == "t" {
+            return None;
+        }
+        if let Ok(epoch) = value.parse::<f64>() {
+            return epoch_to_local(epoch);
+        }
+        crate::app::parse_timestamp(value)
+    })
+}
+
+/// Interpret a Unix epoch in seconds, milliseconds, microseconds or
+/// nanoseconds. Anything before 2001 (1e9 seconds) is more likely a
+/// duration or a count (`time=12.5`) than a timestamp.
+fn epoch_to_local(epoch: f64) -> Option<DateTime<Local>> {
+    if epoch < 1e9 {
+        return None;
+    }
+    let nanos = if epoch > 1e17 {
+        epoch
+    } else if epoch > 1e14 {
+        epoch * 1e3
+    } else if epoch > 1e11 {
+        epoch * 1e6
+    } else {
+        epoch * 1e9
+    };
+    Some(Local.timestamp_nanos(nanos as i64))
+}
+
+/// The message field, if the line has one
+pub fn message_from_fields(fields: &[(String, String)]) -> Option<&str> {
+    MESSAGE_KEYS.iter().find_map(|key| field(fields, key))
+}
+
+/// A user-defined regex parser (`[[parsers]]` in the config file)
+pub struct LineParser {
+    regex: Regex,
+    timestamp_format: Option<String>,
+    sources: glob::Pattern,
+}
+
+impl LineParser {
+    pub fn new(config: &ParserConfig) -> Result<Self, String> {
+        let name = if config.name.is_empty() {
+            &config.pattern
+        } else {
+            &config.name
+        };
+        let regex = Regex::new(&config.pattern)
+            .map_err(|e| format!("Parser '{}': invalid pattern: {}", name, e))?;
+        if regex.capture_names().flatten().next().is_none() {
+            return Err(format!("Parser '{}': pattern has no named groups", name));
+        }
+        let sources = glob::Pattern::new(&config.sources)
+            .map_err(|e| format!("Parser '{}': invalid sources glob: {}", name, e))?;
+        Ok(Self {
+            regex,
+            timestamp_format: config.timestamp_format.clone(),
+            sources,
+        })
+    }
+
+    /// Whether the parser is meant for a source (by name, or path for files)
+    pub fn applies_to(&self, source: &LogSourceType) -> bool {
+        source.matches_glob(&self.sources)
+    }
+
+    /// Take fields, level and timestamp from a matching line.
+    /// Returns false (leaving the line alone) if the pattern doesn't match.
+    pub fn apply(&self, line: &mut LogLine) -> bool {
+        let Some(caps) = self.regex.captures(&line.raw) else {
+            return false;
+        };
+        let fields: Fields = self
+            .regex
+            .capture_names()
+            .flatten()
+            .filter_map(|name| {
+                caps.name(name)
+                    .map(|m| (name.to_string(), m.as_str().to_string()))
+            })
+            .collect();
+
+        if let Some(level) = level_from_fields(&fields) {
+            line.level = level;
+        } else if let Some(msg) = message_from_fields(&fields) {
+            line.level = LogLevel::detect(msg);
+        }
+
+        let timestamp = match (&self.timestamp_format, field(&fields, "ts")) {
+            (Some(format), Some(ts)) => parse_with_format(ts, format),
+            _ => timestamp_from_fields(&fields),
+        };
+        if timestamp.is_some() {
+            line.timestamp = timestamp;
+        }
+
+        line.fields = fields;
+        true
+    }
+}
+
+/// Parse a timestamp with a chrono format. Formats without a year (syslog)
+/// are taken as this year, formats without a date as today.
+pub fn parse_with_format(value: &str, format: &str) -> Option<DateTime<Local>> {
+    if let Ok(dt) = DateTime::parse_from_str(value, format) {
+        return Some(dt.with_timezone(&Local));
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, format) {
+        return Local.from_local_datetime(&dt).single();
+    }
+    let today = Local::now().date_naive();
+    let with_year = format!("{} {}", value, today.year());
+    if let Ok(dt) = NaiveDateTime::parse_from_str(&with_year, &format!("{} %Y", format)) {
+        return Local.from_local_datetime(&dt).single();
+    }
+    let time = NaiveTime::parse_from_str(value, format).ok()?;
+    Local.from_local_datetime(&today.and_time(time)).single()
+}
+
+/// The configured parsers and which of them apply to each source
+#[derive(Default)]
+pub struct ParserSet {
+    parsers: Vec<LineParser>,
+    /// Indices into `parsers` for each source, in config order
+    by_source: Vec<Vec<usize>>,
+}
+
+impl ParserSet {
+    /// Compile the configured parsers; invalid ones are skipped and reported
+    pub fn new(configs: &[ParserConfig]) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let parsers = configs
+            .iter()
+            .filter_map(|config| LineParser::new(config).map_err(|e| errors.push(e)).ok())
+            .collect();
+        (
+            Self {
+                parsers,
+                by_source: Vec::new(),
+            },
+            errors,
+        )
+    }
+
+    /// Register the next source (sources are numbered in the order added)
+    pub fn add_source(&mut self, source: &LogSourceType) {
+        let ids = (0..self.parsers.len())
+            .filter(|&i| self.parsers[i].applies_to(source))
+            .collect();
+        self.by_source.push(ids);
+    }
+
+    /// Apply the first parser of the line's source whose pattern matches
+    pub fn apply(&self, line: &mut LogLine) {
+        let Some(ids) = self.by_source.get(line.source_id) else {
+            return;
+        };
+        for &i in ids {
+            if self.parsers[i].apply(line) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Timelike, Utc};
+
+    const SERVICE_LINE: &str = r#"{"ts":"2024-03-01T12:00:00.250Z","level":"warn","svc":"api","msg":"slow query","trace_id":"abc123","db":{"ms":812,"table":"users"},"tags":["a","b"],"ok":false}"#;
+
+    #[test]
+    fn test_parse_and_flatten() {
+        let fields = parse_json_fields(SERVICE_LINE).unwrap();
+        assert_eq!(field(&fields, "svc"), Some("api"));
+        assert_eq!(field(&fields, "db.ms"), Some("812"));
+        assert_eq!(field(&fields, "db.table"), Some("users"));
+        assert_eq!(field(&fields, "tags"), Some(r#"["a","b"]"#));
+        assert_eq!(field(&fields, "ok"), Some("false"));
+        assert_eq!(fields[0].0, "ts");
+
+        assert!(parse_json_fields("plain text").is_none());
+        assert!(parse_json_fields("[1, 2]").is_none());
+        assert!(parse_json_fields("{not json}").is_none());
+    }
+
+    #[test]
+    fn test_level_timestamp_and_message() {
+        let fields = parse_json_fields(SERVICE_LINE).unwrap();
+        assert_eq!(level_from_fields(&fields), Some(LogLevel::Warn));
+        assert_eq!(
+            timestamp_from_fields(&fields).unwrap().with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+                + chrono::Duration::milliseconds(250)
+        );
+        assert_eq!(message_from_fields(&fields), Some("slow query"));
+    }
+
+    #[test]
+    fn test_default_columns_use_first_structured_line() {
+        let plain = Fields::new();
+        let zap = parse_json_fields(r#"{"time":"x","severity":"info","message":"m"}"#).unwrap();
+        let rows = [plain, zap];
+        assert_eq!(default_columns(&rows), vec!["time", "severity", "message"]);
+        assert!(default_columns(&[Fields::new()]).is_empty());
+    }
+
+    /// Recorded from a Go service using go-kit/log
+    const LOGFMT_LINE: &str = r#"ts=2024-03-01T12:00:00Z level=warn caller=db.go:88 msg="slow query \"users\"" dur=812ms retry="""#;
+
+    #[test]
+    fn test_parse_logfmt() {
+        let fields = parse_logfmt_fields(LOGFMT_LINE).unwrap();
+        assert_eq!(field(&fields, "caller"), Some("db.go:88"));
+        assert_eq!(field(&fields, "msg"), Some(r#"slow query "users""#));
+        assert_eq!(field(&fields, "dur"), Some("812ms"));
+        assert_eq!(field(&fields, "retry"), Some(""));
+        assert_eq!(level_from_fields(&fields), Some(LogLevel::Warn));
+        assert!(timestamp_from_fields(&fields).is_some());
+
+        let pairs = scan_logfmt(r#"a=1  b="x y""#).unwrap();
+        assert_eq!(pairs[1].key, 5..6);
+        assert_eq!(pairs[1].value, 7..12);
+    }
+
+    #[test]
+    fn test_prose_is_not_logfmt() {
+        assert!(scan_logfmt("GET /health status=200 dur=3ms").is_none());
+        assert!(scan_logfmt("only=one").is_none());
+        assert!(scan_logfmt(r#"a=1 b="unterminated"#).is_none());
+        assert!(scan_logfmt(r#"a=1 b="x"y"#).is_none());
+        assert!(scan_logfmt("a=1 =2").is_none());
+        assert!(scan_logfmt("").is_none());
+    }
+
+    fn parser(pattern: &str, timestamp_format: Option<&str>, sources: &str) -> LineParser {
+        LineParser::new(&ParserConfig {
+            name: "test".to_string(),
+            pattern: pattern.to_string(),
+            timestamp_format: timestamp_format.map(str::to_string),
+            sources: sources.to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_line_parser_extracts_level_timestamp_and_fields() {
+        let parser = parser(
+            r"^(?P<ts>\d\d\.\d\d\.\d{4} [\d:]+) <(?P<level>\w)> \[(?P<thread>[^\]]+)\] (?P<msg>.*)$",
+            Some("%d.%m.%Y %H:%M:%S"),
+            "*",
+        );
+        // The level group wins over words in the message
+        let mut line =
+            LogLine::new("01.03.2024 12:00:05 <W> [pool-1] ERROR budget at 90%".to_string());
+        assert!(parser.apply(&mut line));
+        assert_eq!(line.level, LogLevel::Warn);
+
+        // "X" is not a level, so the message decides
+        let mut line = LogLine::new("01.03.2024 12:00:05 <X> [pool-1] retrying".to_string());
+        assert!(parser.apply(&mut line));
+        assert_eq!(line.level, LogLevel::None);
+        let ts = line.timestamp.unwrap();
+        assert_eq!(
+            (ts.year(), ts.month(), ts.day(), ts.hour(), ts.second()),
+            (2024, 3, 1, 12, 5)
+        );
+        assert_eq!(field(&line.fields, "thread"), Some("pool-1"));
+        assert_eq!(field(&line.fields, "msg"), Some("retrying"));
+
+        let mut other = LogLine::new("WARN something else".to_string());
+        assert!(!parser.apply(&mut other));
+        assert_eq!(other.level, LogLevel::Warn);
+        assert!(other.fields.is_empty());
+    }
+
+    #[test]
+    fn test_line_parser_level_names_and_yearless_timestamps() {
+        let parser = parser(
+            r"^(?P<ts>\w{3} +\d+ [\d:]+) (?P<host>\S+) (?P<level>\w+): (?P<msg>.*)$",
+            Some("%b %d %H:%M:%S"),
+            "*",
+        );
+        let mut line = LogLine::new("Mar  1 12:00:00 web-1 crit: disk full".to_string());
+        assert!(parser.apply(&mut line));
+        assert_eq!(line.level, LogLevel::Error);
+        let ts = line.timestamp.unwrap();
+        assert_eq!((ts.year(), ts.month()), (Local::now().year(), 3));
+    }
+
+    #[test]
+    fn test_parser_set_applies_by_source() {
+        let configs = vec![
+            ParserConfig {
+                name: String::new(),
+                pattern: r"^(?P<level>[A-Z]+)\|(?P<msg>.*)$".to_string(),
+                timestamp_format: None,
+                sources: "docker:legacy-*".to_string(),
+            },
+            ParserConfig {
+                name: "broken".to_string(),
+                pattern: "(".to_string(),
+                timestamp_format: None,
+                sources: "*".to_string(),
+            },
+        ];
+        let (mut set, errors) = ParserSet::new(&configs);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Parser 'broken'"));
+
+        set.add_source(&LogSourceType::Docker {
+            container: "legacy-billing".to_string(),
+        });
+        set.add_source(&LogSourceType::Docker {
+            container: "web".to_string(),
+        });
+
+        let mut legacy = LogLine::new("FATAL|out of memory".to_string()).with_source_id(0);
+        set.apply(&mut legacy);
+        assert_eq!(legacy.level, LogLevel::Error);
+        assert_eq!(field(&legacy.fields, "msg"), Some("out of memory"));
+
+        let mut web = LogLine::new("FATAL|out of memory".to_string()).with_source_id(1);
+        set.apply(&mut web);
+        assert!(web.fields.is_empty());
+    }
+
+    #[test]
+    fn test_line_parser_rejects_patterns_without_named_groups() {
+        let config = ParserConfig {
+            name: String::new(),
+            pattern: r"^\d+ .*$".to_string(),
+            timestamp_format: None,
+            sources: "*".to_string(),
+        };
+        assert!(LineParser::new(&config).is_err());
+    }
+
+    #[test]
+    fn test_numeric_levels_and_epochs() {
+        let pino = parse_json_fields(r#"{"level":50,"time":1700000000123,"msg":"x"}"#).unwrap();
+        assert_eq!(level_from_fields(&pino), Some(LogLevel::Error));
+        assert_eq!(
+            timestamp_from_fields(&pino).unwrap().timestamp_millis(),
+            1_700_000_000_123
+        );
+
+        let secs = parse_json_fields(r#"{"ts":1700000000.5,"severity":"DEBUG"}"#).unwrap();
+        assert_eq!(level_from_fields(&secs), Some(LogLevel::Debug));
+        assert_eq!(
+            timestamp_from_fields(&secs).unwrap().timestamp_millis(),
+            1_700_000_000_500
+        );
+    }
+
+    #[test]
+    fn test_durations_in_time_fields_are_not_timestamps() {
+        for line in [
+            "t=5 msg=done",
+            "time=12.5 msg=done",
+            r#"{"t":5,"time":0.25}"#,
+        ] {
+            let fields = parse_json_fields(line)
+                .or_else(|| parse_logfmt_fields(line))
+                .unwrap();
+            assert_eq!(timestamp_from_fields(&fields), None, "{}", line);
+        }
+
+        // Another key still counts when `t` doesn't
+        let fields = parse_logfmt_fields("t=5 ts=1700000000 msg=done").unwrap();
+        assert_eq!(
+            timestamp_from_fields(&fields).unwrap().timestamp(),
+            1_700_000_000
+        );
+
+        let fields = parse_logfmt_fields("t=2024-03-01T12:00:00Z msg=done").unwrap();
+        assert!(timestamp_from_fields(&fields).is_some());
+    }
+}
@@ -0,0 +1,672 @@
+//! Field-aware filter queries.
+//!
+//! A filter that uses a comparison operator, or one of the keywords `AND`,
+//! `OR`, `NOT` along with parentheses or a quoted phrase, is parsed into a
+//! small boolean expression instead of being matched as plain text:
+//!
+//! ```text
+//! level>=warn AND svc=api AND NOT msg~"healthcheck" AND dur>500ms
+//! ```
+//!
+//! Comparisons read a line's structured fields, plus `level`, `source` and
+//! `ts`, which every line has. On a line without the field, a comparison
+//! matches its own text instead, so `user_id=42` still finds
+//! `login user_id=42`. Bare words and quoted phrases match the raw line like
+//! the substring filter does; terms side by side are ANDed.
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use regex::{Regex, RegexBuilder};
+
+use crate::app::{self, LogLevel, LogLine};
+use crate::filter::{MatchRange, find_substring_matches};
+use crate::parse;
+use crate::sources::LogSourceType;
+
+/// Comparison operators, as written in a query
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Match => "~",
+            CompareOp::NotMatch => "!~",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+        }
+    }
+
+    fn is_ordering(self) -> bool {
+        matches!(
+            self,
+            CompareOp::Gt | CompareOp::Ge | CompareOp::Lt | CompareOp::Le
+        )
+    }
+
+    /// Whether `ordering` (left compared to right) satisfies this operator
+    fn accepts(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            CompareOp::Eq => ordering == Equal,
+            CompareOp::Ne => ordering != Equal,
+            CompareOp::Gt => ordering == Greater,
+            CompareOp::Ge => ordering != Less,
+            CompareOp::Lt => ordering == Less,
+            CompareOp::Le => ordering != Greater,
+            CompareOp::Match | CompareOp::NotMatch => false,
+        }
+    }
+}
+
+/// The right-hand side of a comparison, pre-parsed for each way it can be compared
+#[derive(Clone, Debug)]
+pub struct Value {
+    lower: String,
+    number: Option<f64>,
+    /// Seconds, when written with a unit (`500ms`, `1m30s`)
+    duration: Option<f64>,
+    level: Option<LogLevel>,
+    time: Option<DateTime<Local>>,
+    regex: Option<Regex>,
+}
+
+/// A parsed query
+#[derive(Clone, Debug)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    /// Case-insensitive substring of the raw line (stored lowercased)
+    Text(String),
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: Value,
+        /// The comparison as plain text (`user_id=42`, lowercased, with `!=`
+        /// and `!~` as `=` and `~`), matched when the line lacks the field
+        text: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) => format!("'{}'", w),
+            Token::Quoted(q) => format!("\"{}\"", q),
+            Token::Op(op) => format!("'{}'", op.symbol()),
+            Token::And => "AND".to_string(),
+            Token::Or => "OR".to_string(),
+            Token::Not => "NOT".to_string(),
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+}
+
+/// Split a query into tokens. Stops at an unterminated quote, which is
+/// returned as the error alongside the tokens read so far.
+fn tokenize(input: &str) -> (Vec<Token>, Option<String>) {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if let Some((op, len)) = operator_at(rest) {
+            tokens.push(Token::Op(op));
+            rest = &rest[len..];
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push(Token::Open);
+                rest = &rest[1..];
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                rest = &rest[1..];
+            }
+            '"' => {
+                let mut text = String::new();
+                let mut chars = rest[1..].char_indices();
+                let mut end = None;
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '"' => {
+                            end = Some(1 + i + 1);
+                            break;
+                        }
+                        '\\' => text.extend(chars.next().map(|(_, c)| c)),
+                        c => text.push(c),
+                    }
+                }
+                let Some(end) = end else {
+                    return (tokens, Some("unterminated quote".to_string()));
+                };
+                tokens.push(Token::Quoted(text));
+                rest = &rest[end..];
+            }
+            _ => {
+                let end = rest
+                    .char_indices()
+                    .find(|&(i, c)| {
+                        c.is_whitespace()
+                            || matches!(c, '(' | ')' | '"')
+                            || operator_at(&rest[i..]).is_some()
+                    })
+                    .map_or(rest.len(), |(i, _)| i);
+                tokens.push(match &rest[..end] {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    word => Token::Word(word.to_string()),
+                });
+                rest = &rest[end..];
+            }
+        }
+    }
+
+    (tokens, None)
+}
+
+/// The comparison operator at the start of `text`, with its length
+fn operator_at(text: &str) -> Option<(CompareOp, usize)> {
+    let two = match text.get(..2) {
+        Some(">=") => Some(CompareOp::Ge),
+        Some("<=") => Some(CompareOp::Le),
+        Some("!=") => Some(CompareOp::Ne),
+        Some("!~") => Some(CompareOp::NotMatch),
+        _ => None,
+    };
+    if let Some(op) = two {
+        return Some((op, 2));
+    }
+    let one = match text.as_bytes().first()? {
+        b'=' => CompareOp::Eq,
+        b'~' => CompareOp::Match,
+        b'>' => CompareOp::Gt,
+        b'<' => CompareOp::Lt,
+        _ => return None,
+    };
+    Some((one, 1))
+}
+
+/// Whether a filter should be read as a query rather than plain text: it
+/// has a comparison, or uses `AND`/`OR`/`NOT` together with parentheses or
+/// a quoted phrase. Keywords alone are text (`404 NOT FOUND`).
+pub fn looks_like_query(input: &str) -> bool {
+    let (tokens, _) = tokenize(input);
+    let has = |f: fn(&Token) -> bool| tokens.iter().any(f);
+    has(|t| matches!(t, Token::Op(_)))
+        || (has(|t| matches!(t, Token::And | Token::Or | Token::Not))
+            && has(|t| matches!(t, Token::Open | Token::Close | Token::Quoted(_))))
+}
+
+/// Whether a filter uses `AND`, `OR` or `NOT`, so it was surely meant as a
+/// query. Without them, text that doesn't parse (`~/.config`, `a->b`) is
+/// just text.
+pub fn has_keywords(input: &str) -> bool {
+    let (tokens, _) = tokenize(input);
+    tokens
+        .iter()
+        .any(|t| matches!(t, Token::And | Token::Or | Token::Not))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.and()?;
+            left = Query::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut left = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                // Terms side by side are ANDed
+                Some(Token::Word(_) | Token::Quoted(_) | Token::Not | Token::Open) => {}
+                _ => return Ok(left),
+            }
+            let right = self.unary()?;
+            left = Query::And(Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Query, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query, String> {
+        match self.next() {
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    Some(other) => Err(format!("expected ')' but found {}", other.describe())),
+                    None => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Word(word)) => {
+                if let Some(Token::Op(op)) = self.peek() {
+                    let op = *op;
+                    self.pos += 1;
+                    let value = match self.next() {
+                        Some(Token::Word(v) | Token::Quoted(v)) => v,
+                        _ => {
+                            return Err(format!(
+                                "expected a value after '{}{}'",
+                                word,
+                                op.symbol()
+                            ));
+                        }
+                    };
+                    return compare(word, op, &value);
+                }
+                Ok(Query::Text(word.to_lowercase()))
+            }
+            Some(Token::Quoted(phrase)) => Ok(Query::Text(phrase.to_lowercase())),
+            Some(Token::Op(op)) => Err(format!("expected a field name before '{}'", op.symbol())),
+            Some(other) => Err(format!("unexpected {}", other.describe())),
+            None => Err("unexpected end of query".to_string()),
+        }
+    }
+}
+
+/// Build a comparison, checking that the value makes sense for the field
+fn compare(field: String, op: CompareOp, raw: &str) -> Result<Query, String> {
+    let mut value = Value {
+        lower: raw.to_lowercase(),
+        number: raw.parse().ok(),
+        duration: parse_duration(raw),
+        level: None,
+        time: None,
+        regex: None,
+    };
+
+    if matches!(op, CompareOp::Match | CompareOp::NotMatch) {
+        let regex = RegexBuilder::new(raw)
+            .case_insensitive(true)
+            .build()
+            .map_err(|_| format!("invalid regex in '{}{}{}'", field, op.symbol(), raw))?;
+        value.regex = Some(regex);
+    } else if field == "level" {
+        value.level =
+            Some(LogLevel::from_name(raw).ok_or_else(|| format!("unknown level '{}'", raw))?);
+    } else if field == "ts" {
+        value.time =
+            Some(parse_time(raw).ok_or_else(|| format!("can't read '{}' as a time", raw))?);
+    } else if op.is_ordering() && value.number.is_none() && value.duration.is_none() {
+        return Err(format!("'{}' needs a number or duration", op.symbol()));
+    }
+
+    let as_text = match op {
+        CompareOp::Ne => CompareOp::Eq,
+        CompareOp::NotMatch => CompareOp::Match,
+        op => op,
+    };
+    let text = format!("{}{}{}", field, as_text.symbol(), raw).to_lowercase();
+    Ok(Query::Compare {
+        field,
+        op,
+        value,
+        text,
+    })
+}
+
+/// Parse a duration like `500ms`, `1.5s` or `1h30m` into seconds
+pub fn parse_duration(text: &str) -> Option<f64> {
+    let mut total = 0.0;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+/// Read the value of a `ts` comparison: a duration counts back from now
+/// (`ts>15m` is the last fifteen minutes), otherwise a date or time of day
+fn parse_time(text: &str) -> Option<DateTime<Local>> {
+    if let Some(seconds) = parse_duration(text) {
+        return Some(Local::now() - chrono::Duration::milliseconds((seconds * 1000.0) as i64));
+    }
+    app::parse_timestamp(text)
+        .or_else(|| {
+            let midnight = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)?;
+            Local.from_local_datetime(&midnight).earliest()
+        })
+        .or_else(|| parse::parse_with_format(text, "%H:%M:%S"))
+        .or_else(|| parse::parse_with_format(text, "%H:%M"))
+}
+
+impl Query {
+    /// Parse a query, describing the first syntax error
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (tokens, error) = tokenize(input);
+        if let Some(error) = error {
+            return Err(error);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.next() {
+            None => Ok(query),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+        }
+    }
+
+    /// Check a line against the query; `sources` resolves `source` comparisons
+    pub fn matches(&self, line: &LogLine, sources: &[LogSourceType]) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(line, sources) && b.matches(line, sources),
+            Query::Or(a, b) => a.matches(line, sources) || b.matches(line, sources),
+            Query::Not(inner) => !inner.matches(line, sources),
+            Query::Text(text) => line.raw.to_lowercase().contains(text.as_str()),
+            Query::Compare {
+                field,
+                op,
+                value,
+                text,
+            } => compare_field(line, sources, field, *op, value, text),
+        }
+    }
+
+    /// Ranges of the raw line to highlight: text terms and `~` patterns
+    /// that aren't negated
+    pub fn find_matches(&self, line: &str, matches: &mut Vec<MatchRange>) {
+        match self {
+            Query::And(a, b) | Query::Or(a, b) => {
+                a.find_matches(line, matches);
+                b.find_matches(line, matches);
+            }
+            Query::Not(_) => {}
+            Query::Text(text) => find_substring_matches(line, text, matches),
+            Query::Compare {
+                op: CompareOp::Match,
+                value,
+                ..
+            } => {
+                if let Some(regex) = &value.regex {
+                    matches.extend(regex.find_iter(line).map(|m| MatchRange {
+                        start: m.start(),
+                        end: m.end(),
+                    }));
+                }
+            }
+            Query::Compare { .. } => {}
+        }
+    }
+}
+
+fn compare_field(
+    line: &LogLine,
+    sources: &[LogSourceType],
+    field: &str,
+    op: CompareOp,
+    value: &Value,
+    text: &str,
+) -> bool {
+    // A missing field is unequal to everything and matches no pattern
+    let negated = matches!(op, CompareOp::Ne | CompareOp::NotMatch);
+    let absent = negated;
+
+    match field {
+        "level" if value.regex.is_none() => match (line.level, value.level) {
+            (LogLevel::None, _) | (_, None) => absent,
+            (level, Some(wanted)) => op.accepts(level.severity().cmp(&wanted.severity())),
+        },
+        "ts" if value.regex.is_none() => match (line.timestamp, value.time) {
+            (Some(ts), Some(wanted)) => op.accepts(ts.cmp(&wanted)),
+            _ => absent,
+        },
+        "source" => match sources.get(line.source_id) {
+            Some(source) => compare_text(&source.name(), op, value),
+            None => absent,
+        },
+        "level" => match line.level {
+            LogLevel::None => absent,
+            level => compare_text(level.name(), op, value),
+        },
+        _ => match lookup(line, field) {
+            Some(found) => compare_text(found, op, value),
+            // Unstructured (or differently structured) lines: match the
+            // comparison as written
+            None => line.raw.to_lowercase().contains(text) != negated,
+        },
+    }
+}
+
+/// A field's value; `msg` falls back to the well-known message keys and
+/// then to the whole line, so it works on unstructured logs too
+fn lookup<'a>(line: &'a LogLine, field: &str) -> Option<&'a str> {
+    let fields = &line.fields;
+    parse::field(fields, field).or_else(|| match field {
+        "msg" | "message" => Some(parse::message_from_fields(fields).unwrap_or(&line.raw)),
+        _ => None,
+    })
+}
+
+fn compare_text(text: &str, op: CompareOp, value: &Value) -> bool {
+    match op {
+        CompareOp::Match => value.regex.as_ref().is_some_and(|r| r.is_match(text)),
+        CompareOp::NotMatch => !value.regex.as_ref().is_some_and(|r| r.is_match(text)),
+        CompareOp::Eq | CompareOp::Ne => {
+            let equal = match (text.trim().parse::<f64>(), value.number) {
+                (Ok(a), Some(b)) => a == b,
+                _ => text.to_lowercase() == value.lower,
+            };
+            equal == (op == CompareOp::Eq)
+        }
+        _ => {
+            let ordering = match (value.duration, value.number) {
+                (Some(wanted), _) => parse_duration(text).and_then(|d| d.partial_cmp(&wanted)),
+                (None, Some(wanted)) => text
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|n| n.partial_cmp(&wanted)),
+                (None, None) => None,
+            };
+            ordering.is_some_and(|o| op.accepts(o))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(raw: &str) -> LogLine {
+        LogLine::new(raw.to_string())
+    }
+
+    fn check(query: &str, raw: &str) -> bool {
+        let sources = vec![LogSourceType::Docker {
+            container: "api".to_string(),
+        }];
+        Query::parse(query).unwrap().matches(&line(raw), &sources)
+    }
+
+    #[test]
+    fn test_looks_like_query() {
+        assert!(looks_like_query("level>=warn"));
+        assert!(looks_like_query("(timeout OR refused)"));
+        assert!(looks_like_query("NOT \"healthcheck\""));
+        assert!(looks_like_query("svc=api NOT healthcheck"));
+        // Keywords on their own are just words in the text
+        assert!(!looks_like_query("timeout OR refused"));
+        assert!(!looks_like_query("404 NOT FOUND"));
+        assert!(!looks_like_query("DROP TABLE users OR 1"));
+        assert!(!looks_like_query("connection refused"));
+        assert!(!looks_like_query("not found"));
+        assert!(!looks_like_query("\"a=b\""));
+        assert!(!looks_like_query("done!"));
+    }
+
+    #[test]
+    fn test_query_fields_and_booleans() {
+        let query = r#"level>=warn AND svc=api AND NOT msg~"healthcheck" AND dur>500ms"#;
+        assert!(check(query, "level=warn svc=api msg=slow dur=1.2s"));
+        assert!(check(query, "level=error svc=API msg=slow dur=750ms"));
+        assert!(!check(query, "level=info svc=api msg=slow dur=1.2s"));
+        assert!(!check(query, "level=warn svc=web msg=slow dur=1.2s"));
+        assert!(!check(query, "level=warn svc=api msg=healthcheck dur=1.2s"));
+        assert!(!check(query, "level=warn svc=api msg=slow dur=20ms"));
+        assert!(!check(query, "level=warn svc=api msg=slow"));
+
+        assert!(check("svc=api OR svc=web", r#"{"svc":"web"}"#));
+        assert!(check(
+            "(svc=api OR svc=web) status>=500",
+            r#"{"svc":"web","status":503}"#
+        ));
+        assert!(!check(
+            "(svc=api OR svc=web) status>=500",
+            r#"{"svc":"web","status":404}"#
+        ));
+        assert!(check("http.status=200", r#"{"http":{"status":200}}"#));
+        assert!(check("svc!=api", "plain text line"));
+        // Lines without the field match the comparison's text
+        assert!(check("user_id=42", "login user_id=42"));
+        assert!(!check("svc!=api", "proxy svc=API down"));
+        assert!(!check("user_id=42", r#"{"user_id":7,"note":"user_id=42"}"#));
+    }
+
+    #[test]
+    fn test_query_pseudo_fields_and_text() {
+        assert!(check("source=docker:api", "anything"));
+        assert!(!check("source~web", "anything"));
+        assert!(check("level=warning", "WARN disk almost full"));
+        assert!(!check("level>=warn", "no level here"));
+        assert!(check(
+            "msg~timeout AND retry",
+            "request timeout, will retry"
+        ));
+        assert!(check(
+            r#""connection refused" OR oom"#,
+            "dial: Connection refused"
+        ));
+        assert!(!check("timeout NOT retry", "request timeout, will retry"));
+        assert!(check("ts>=2024-01-01", "2024-03-01T10:00:00Z started"));
+        assert!(!check("ts<2024-01-01", "2024-03-01T10:00:00Z started"));
+    }
+
+    #[test]
+    fn test_query_syntax_errors() {
+        assert_eq!(
+            Query::parse("level>=").unwrap_err(),
+            "expected a value after 'level>='"
+        );
+        assert_eq!(Query::parse("(a OR b").unwrap_err(), "missing ')'");
+        assert_eq!(
+            Query::parse("msg~\"oops").unwrap_err(),
+            "unterminated quote"
+        );
+        assert_eq!(
+            Query::parse("level=loud").unwrap_err(),
+            "unknown level 'loud'"
+        );
+        assert_eq!(
+            Query::parse("=x").unwrap_err(),
+            "expected a field name before '='"
+        );
+        assert_eq!(
+            Query::parse("a AND").unwrap_err(),
+            "unexpected end of query"
+        );
+        assert_eq!(
+            Query::parse("dur>slow").unwrap_err(),
+            "'>' needs a number or duration"
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Some(0.5));
+        assert_eq!(parse_duration("1m30s"), Some(90.0));
+        assert_eq!(parse_duration("250µs"), Some(0.00025));
+        assert_eq!(parse_duration("500"), None);
+        assert_eq!(parse_duration("fast"), None);
+    }
+
+    #[test]
+    fn test_query_highlights_positive_terms() {
+        let query = Query::parse("timeout AND NOT retry OR msg~err.r").unwrap();
+        let mut matches = Vec::new();
+        query.find_matches("Timeout error, retry", &mut matches);
+        let spans: Vec<_> = matches.iter().map(|m| (m.start, m.end)).collect();
+        assert_eq!(spans, vec![(0, 7), (8, 13)]);
+    }
+}
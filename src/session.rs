@@ -0,0 +1,71 @@
+//! State kept between runs: saved filters and filter history.
+//!
+//! Stored as JSON in `state.json` next to `config.toml`. Unlike the config
+//! file, it's written by bark whenever a filter is saved, deleted or used.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::filter::SavedFilter;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Filters saved with `s` (not the ones from the config file)
+    pub saved_filters: Vec<SavedFilter>,
+    /// Most recent first
+    pub filter_history: Vec<String>,
+}
+
+impl Session {
+    /// Read the state file (empty if it doesn't exist yet)
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    /// Write the state file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip() {
+        let path = std::env::temp_dir().join(format!("bark-session-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(Session::load(&path).unwrap().filter_history.is_empty());
+
+        let session = Session {
+            saved_filters: vec![SavedFilter {
+                name: "errors".to_string(),
+                pattern: "ERROR".to_string(),
+                is_regex: false,
+                stack: Vec::new(),
+                predefined: false,
+            }],
+            filter_history: vec!["ERROR".to_string(), "timeout".to_string()],
+        };
+        session.save(&path).unwrap();
+
+        let loaded = Session::load(&path).unwrap();
+        assert_eq!(loaded.saved_filters[0].name, "errors");
+        assert_eq!(loaded.filter_history, vec!["ERROR", "timeout"]);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(Session::load(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
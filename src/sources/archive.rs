@@ -0,0 +1,290 @@
+//! Compressed log files and rotation sets.
+//!
+//! Archives are recognised by their magic bytes (not their extension) and
+//! decompressed on the fly. A rotation set is a live log plus
+//! its rotated siblings (`app.log.1`, `app.log.2.gz`, `app.log-20240101.zst`),
+//! ordered oldest first so they can be replayed as one stream.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use super::tail::decode_line;
+
+/// Supported compression formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if bytes.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extensions stripped when matching rotated siblings
+const ARCHIVE_EXTENSIONS: &[&str] = &[".gz", ".zst", ".zstd", ".bz2"];
+
+/// Detect whether a file is compressed, and with what
+pub fn detect_compression(path: &Path) -> io::Result<Option<Compression>> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path)?;
+    let mut read = 0;
+    while read < magic.len() {
+        match file.read(&mut magic[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(Compression::from_magic(&magic[..read]))
+}
+
+/// Open a file for reading, decompressing it if needed
+pub fn open_reader(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let compression = detect_compression(path)?;
+    let file = File::open(path)?;
+    Ok(match compression {
+        None => Box::new(BufReader::new(file)),
+        Some(Compression::Gzip) => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(
+            BufReader::new(file),
+        ))),
+        Some(Compression::Zstd) => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+        Some(Compression::Bzip2) => Box::new(BufReader::new(bzip2::read::MultiBzDecoder::new(
+            BufReader::new(file),
+        ))),
+    })
+}
+
+/// Read every line of a (possibly compressed) file, stopping early if
+/// `on_line` returns false
+pub fn read_lines(path: &Path, mut on_line: impl FnMut(String) -> bool) -> io::Result<()> {
+    let mut reader = open_reader(path)?;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        if !on_line(decode_line(line)) {
+            return Ok(());
+        }
+    }
+}
+
+/// Position of a rotated file within its set
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum RotationKey {
+    /// Date-style suffix (`app.log-20240101`); sorts ascending, oldest first
+    Dated(String),
+    /// Numbered suffix (`app.log.3`); higher numbers are older
+    Numbered(std::cmp::Reverse<u64>),
+}
+
+/// Classify a sibling file name relative to the live log's file name
+fn rotation_key(base: &str, name: &str) -> Option<RotationKey> {
+    let rest = name.strip_prefix(base)?;
+    let rest = ARCHIVE_EXTENSIONS
+        .iter()
+        .find_map(|ext| rest.strip_suffix(ext))
+        .unwrap_or(rest);
+
+    let mut chars = rest.chars();
+    let sep = chars.next()?;
+    let suffix = chars.as_str();
+    if suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return None;
+    }
+
+    // Eight digits or more is a date (`app.log.20240101`, logrotate's
+    // `dateext` with a dot), not a rotation index
+    match (sep, suffix.parse::<u64>()) {
+        ('.', Ok(n)) if suffix.len() < 8 => Some(RotationKey::Numbered(std::cmp::Reverse(n))),
+        ('.' | '-' | '_', _) => Some(RotationKey::Dated(suffix.to_string())),
+        _ => None,
+    }
+}
+
+/// List the rotated siblings of a live log file, oldest first.
+/// The live file itself is not included.
+pub fn rotation_set(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let Some(base) = path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(Vec::new());
+    };
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+
+    let mut rotated: Vec<(RotationKey, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let key = rotation_key(base, name.to_str()?)?;
+            Some((key, entry.path()))
+        })
+        .collect();
+    rotated.sort();
+
+    Ok(rotated.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bark_archive_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn collect(path: &Path) -> Vec<String> {
+        let mut lines = Vec::new();
+        read_lines(path, |l| {
+            lines.push(l);
+            true
+        })
+        .unwrap();
+        lines
+    }
+
+    #[test]
+    fn test_reads_gzip_zstd_and_bzip2() {
+        let dir = temp_dir("formats");
+        let text = b"first\nsecond\r\n";
+
+        let gz = dir.join("app.log.1.gz");
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(text).unwrap();
+        fs::write(&gz, enc.finish().unwrap()).unwrap();
+
+        let zst = dir.join("app.log.2.zst");
+        fs::write(&zst, zstd::encode_all(&text[..], 0).unwrap()).unwrap();
+
+        let bz = dir.join("app.log.3.bz2");
+        let mut enc = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        enc.write_all(text).unwrap();
+        fs::write(&bz, enc.finish().unwrap()).unwrap();
+
+        assert_eq!(detect_compression(&gz).unwrap(), Some(Compression::Gzip));
+        assert_eq!(detect_compression(&zst).unwrap(), Some(Compression::Zstd));
+        assert_eq!(detect_compression(&bz).unwrap(), Some(Compression::Bzip2));
+        for path in [&gz, &zst, &bz] {
+            assert_eq!(collect(path), vec!["first", "second"]);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_plain_file_is_not_compressed() {
+        let dir = temp_dir("plain");
+        let path = dir.join("app.log.gz");
+        // Misleading extension: content wins over the name
+        fs::write(&path, "plain text\n").unwrap();
+        assert_eq!(detect_compression(&path).unwrap(), None);
+        assert_eq!(collect(&path), vec!["plain text"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rotation_key() {
+        assert_eq!(
+            rotation_key("app.log", "app.log.2.gz"),
+            Some(RotationKey::Numbered(std::cmp::Reverse(2)))
+        );
+        assert_eq!(
+            rotation_key("app.log", "app.log-20240101.zst"),
+            Some(RotationKey::Dated("20240101".to_string()))
+        );
+        assert_eq!(
+            rotation_key("app.log", "app.log.20240101.gz"),
+            Some(RotationKey::Dated("20240101".to_string()))
+        );
+        assert_eq!(rotation_key("app.log", "app.log"), None);
+        assert_eq!(rotation_key("app.log", "app.log.bak"), None);
+        assert_eq!(rotation_key("app.log", "app.logger"), None);
+        assert_eq!(rotation_key("app.log", "other.log.1"), None);
+    }
+
+    #[test]
+    fn test_rotation_set_orders_oldest_first() {
+        let dir = temp_dir("set");
+        for name in [
+            "app.log",
+            "app.log.1",
+            "app.log.2.gz",
+            "app.log.10.zst",
+            "app.log-20240102",
+            "app.log-20240101.gz",
+            "app.log.old",
+            "other.log.1",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = rotation_set(&dir.join("app.log"))
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "app.log-20240101.gz",
+                "app.log-20240102",
+                "app.log.10.zst",
+                "app.log.2.gz",
+                "app.log.1",
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_dot_and_dash_dated_suffixes_sort_by_date() {
+        let dir = temp_dir("dated");
+        for name in [
+            "app.log",
+            "app.log.20240103.gz",
+            "app.log-20240102.gz",
+            "app.log.20240101",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = rotation_set(&dir.join("app.log"))
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "app.log.20240101",
+                "app.log-20240102.gz",
+                "app.log.20240103.gz",
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,152 @@
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use super::process::{StderrMode, report_exit, run_process};
+use super::{LogEvent, LogSource};
+use crate::app::LogLine;
+use crate::config::DEFAULT_CHANNEL_BUFFER;
+
+/// Split a command line into arguments, honouring single quotes, double
+/// quotes and backslash escapes. No shell is involved, so globs, pipes and
+/// variables are passed through literally.
+pub fn split_command(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| "Trailing backslash in command".to_string())?;
+                current.push(escaped);
+                in_arg = true;
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated ' in command".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("Unterminated \" in command".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated \" in command".to_string()),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    if args.is_empty() {
+        return Err("Command cannot be empty".to_string());
+    }
+    Ok(args)
+}
+
+/// A log source that runs an arbitrary command and reads its output
+pub struct CommandSource {
+    argv: Vec<String>,
+}
+
+impl CommandSource {
+    pub fn new(argv: Vec<String>) -> Self {
+        Self { argv }
+    }
+}
+
+#[async_trait::async_trait]
+impl LogSource for CommandSource {
+    async fn stream(&self) -> mpsc::Receiver<LogEvent> {
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_BUFFER);
+        let argv = self.argv.clone();
+
+        tokio::spawn(async move {
+            let Some((program, args)) = argv.split_first() else {
+                let _ = tx
+                    .send(LogEvent::Error("Command cannot be empty".to_string()))
+                    .await;
+                let _ = tx.send(LogEvent::EndOfStream).await;
+                return;
+            };
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+
+            let exit =
+                run_process(cmd, StderrMode::Lines, |line| Some(LogLine::new(line)), &tx).await;
+            report_exit(&tx, program, "Is it installed and on PATH?", exit).await;
+        });
+
+        rx
+    }
+
+    fn name(&self) -> String {
+        format!("cmd:{}", self.argv.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_plain_words() {
+        assert_eq!(
+            split_command("aws logs tail --follow grp").unwrap(),
+            vec!["aws", "logs", "tail", "--follow", "grp"]
+        );
+        assert_eq!(split_command("  a   b ").unwrap(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_split_quotes_and_escapes() {
+        assert_eq!(
+            split_command(r#"grep -E 'a|b' "two words" say\ hi "q\"x" ''"#).unwrap(),
+            vec!["grep", "-E", "a|b", "two words", "say hi", "q\"x", ""]
+        );
+        // Quoted parts join the surrounding word
+        assert_eq!(
+            split_command(r#"--name="my app""#).unwrap(),
+            vec!["--name=my app"]
+        );
+    }
+
+    #[test]
+    fn test_split_errors() {
+        assert!(split_command("").is_err());
+        assert!(split_command("   ").is_err());
+        assert!(split_command("echo 'open").is_err());
+        assert!(split_command("echo \"open").is_err());
+        assert!(split_command("echo \\").is_err());
+    }
+}
@@ -1,11 +1,21 @@
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
+use std::time::Duration;
+
+use super::process::{Backoff, ReconnectPolicy, ResumePoint, StderrMode, resume_time, run_process};
 use super::{LogEvent, LogSource};
-use crate::app::LogLine;
 use crate::config::{DEFAULT_CHANNEL_BUFFER, DEFAULT_TAIL_LINES};
 
+/// Containers restart and daemons get bounced, so keep trying
+pub(super) const RECONNECT: ReconnectPolicy = ReconnectPolicy {
+    max_attempts: None,
+    initial_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(30),
+};
+
+pub(super) const HINT: &str = "Is Docker installed and running?";
+
 /// Validate Docker container name to prevent option injection.
 pub fn validate_container_name(name: &str) -> Result<(), String> {
     if name.is_empty() {
@@ -20,7 +30,8 @@ pub fn validate_container_name(name: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// A log source that reads from a Docker container using docker logs -f
+/// A log source that reads from a Docker container using docker logs -f,
+/// for daemons the Engine API client can't reach (see `docker_api.rs`)
 pub struct DockerSource {
     container: String,
 }
@@ -38,110 +49,38 @@ impl LogSource for DockerSource {
         let container = self.container.clone();
 
         tokio::spawn(async move {
-            let result = Command::new("docker")
-                .arg("logs")
-                .arg("-f")
-                .arg("--tail")
-                .arg(DEFAULT_TAIL_LINES)
-                .arg("--") // Prevent option injection from container name
-                .arg(&container)
-                .stdout(std::process::Stdio::piped())
-                .stderr(std::process::Stdio::piped())
-                .spawn();
-
-            match result {
-                Ok(mut child) => {
-                    let tx_stderr = tx.clone();
-
-                    // Spawn task to read stderr
-                    let stderr_handle = child.stderr.take().map(|stderr| {
-                        tokio::spawn(async move {
-                            let reader = BufReader::new(stderr);
-                            let mut lines = reader.lines();
-                            loop {
-                                match lines.next_line().await {
-                                    Ok(Some(line)) => {
-                                        if tx_stderr
-                                            .send(LogEvent::Line(LogLine::new(line)))
-                                            .await
-                                            .is_err()
-                                        {
-                                            break;
-                                        }
-                                    }
-                                    Ok(None) => break,
-                                    Err(e) => {
-                                        let _ = tx_stderr
-                                            .send(LogEvent::Error(format!(
-                                                "stderr read error: {}",
-                                                e
-                                            )))
-                                            .await;
-                                        // Continue reading
-                                    }
-                                }
-                            }
-                        })
-                    });
-
-                    // Read stdout in main task
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        let mut lines = reader.lines();
-
-                        loop {
-                            match lines.next_line().await {
-                                Ok(Some(line)) => {
-                                    if tx.send(LogEvent::Line(LogLine::new(line))).await.is_err() {
-                                        break;
-                                    }
-                                }
-                                Ok(None) => break,
-                                Err(e) => {
-                                    let _ = tx
-                                        .send(LogEvent::Error(format!("stdout read error: {}", e)))
-                                        .await;
-                                    // Continue reading - don't abort on single bad line
-                                }
-                            }
-                        }
-                    }
-
-                    // Wait for stderr task
-                    if let Some(handle) = stderr_handle {
-                        let _ = handle.await;
-                    }
-
-                    match child.wait().await {
-                        Ok(status) if !status.success() => {
-                            let _ = tx
-                                .send(LogEvent::Error(format!(
-                                    "docker logs exited with status: {}",
-                                    status
-                                )))
-                                .await;
-                        }
-                        Err(e) => {
-                            let _ = tx
-                                .send(LogEvent::Error(format!(
-                                    "Error waiting for docker logs: {}",
-                                    e
-                                )))
-                                .await;
-                        }
-                        _ => {}
-                    }
-
-                    let _ = tx.send(LogEvent::EndOfStream).await;
+            let label = format!("docker logs for '{}'", container);
+            let mut backoff = Backoff::new(RECONNECT);
+            let mut resume = ResumePoint::default();
+
+            loop {
+                let mut cmd = Command::new("docker");
+                cmd.arg("logs").arg("-f").arg("--timestamps");
+                match resume.start() {
+                    Some(at) => cmd.arg("--since").arg(resume_time(at)),
+                    None => cmd.arg("--tail").arg(DEFAULT_TAIL_LINES),
+                };
+                cmd.arg("--") // Prevent option injection from container name
+                    .arg(&container);
+
+                let mut received = false;
+                // Container stderr is log output, not a docker diagnostic
+                let exit = run_process(
+                    cmd,
+                    StderrMode::Lines,
+                    |line| {
+                        received = true;
+                        resume.line(line)
+                    },
+                    &tx,
+                )
+                .await;
+
+                if received {
+                    backoff.reset();
                 }
-                Err(e) => {
-                    let _ = tx
-                        .send(LogEvent::Error(format!(
-                            "Failed to start docker logs for '{}': {}. Is Docker installed and running?",
-                            container, e
-                        )))
-                        .await;
-                    let _ = tx.send(LogEvent::EndOfStream).await;
+                if !backoff.retry(&tx, &label, HINT, exit).await {
+                    break;
                 }
             }
         });
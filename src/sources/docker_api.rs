@@ -0,0 +1,1168 @@
+//! Docker Engine API client, spoken directly over the daemon socket.
+//!
+//! Just enough HTTP/1.1 for `GET` requests to `/var/run/docker.sock` (or
+//! `DOCKER_HOST`): every request gets its own connection, chunked bodies are
+//! decoded as they arrive, and log streams are split from Docker's
+//! multiplexed stdout/stderr framing. Hosts only the CLI can reach (`ssh://`,
+//! TLS, no socket) are left to `docker.rs`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use super::docker::{HINT, RECONNECT};
+use super::process::{Backoff, split_timestamp};
+use super::tail::decode_line;
+use super::{LogEvent, LogSource};
+use crate::app::LogLine;
+use crate::config::{DEFAULT_CHANNEL_BUFFER, DEFAULT_TAIL_LINES};
+
+/// Where the daemon listens when `DOCKER_HOST` is unset
+pub const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+/// How long discovery waits on the daemon before giving up
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the Docker daemon listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DockerHost {
+    Unix(PathBuf),
+    /// `host:port`, plain HTTP
+    Tcp(String),
+}
+
+impl DockerHost {
+    /// The daemon named by `DOCKER_HOST`, else the default socket. None when
+    /// it can't be reached natively: the socket doesn't exist, or the host
+    /// needs the CLI (`ssh://`, `DOCKER_TLS_VERIFY`).
+    pub fn from_env() -> Option<Self> {
+        if std::env::var_os("DOCKER_TLS_VERIFY").is_some_and(|v| !v.is_empty()) {
+            return None;
+        }
+        let host = match std::env::var("DOCKER_HOST") {
+            Ok(value) if !value.is_empty() => Self::parse(&value)?,
+            _ => DockerHost::Unix(PathBuf::from(DEFAULT_SOCKET)),
+        };
+        match &host {
+            DockerHost::Unix(path) if !cfg!(unix) || !path.exists() => None,
+            _ => Some(host),
+        }
+    }
+
+    /// Parse a `DOCKER_HOST` value (None for schemes other than unix/tcp)
+    fn parse(value: &str) -> Option<Self> {
+        if let Some(path) = value.strip_prefix("unix://") {
+            Some(DockerHost::Unix(PathBuf::from(path)))
+        } else {
+            let addr = value.strip_prefix("tcp://")?.trim_end_matches('/');
+            Some(DockerHost::Tcp(addr.to_string()))
+        }
+    }
+
+    async fn connect(&self) -> Result<Box<dyn Connection>, String> {
+        let connection: Box<dyn Connection> = match self {
+            #[cfg(unix)]
+            DockerHost::Unix(path) => Box::new(
+                tokio::net::UnixStream::connect(path)
+                    .await
+                    .map_err(|e| self.connect_error(e))?,
+            ),
+            #[cfg(not(unix))]
+            DockerHost::Unix(_) => return Err(self.connect_error(unsupported())),
+            DockerHost::Tcp(addr) => Box::new(
+                tokio::net::TcpStream::connect(addr)
+                    .await
+                    .map_err(|e| self.connect_error(e))?,
+            ),
+        };
+        Ok(connection)
+    }
+
+    fn connect_blocking(&self) -> Result<Box<dyn BlockingConnection>, String> {
+        let timeout = Some(DISCOVERY_TIMEOUT);
+        let connection: Box<dyn BlockingConnection> = match self {
+            #[cfg(unix)]
+            DockerHost::Unix(path) => {
+                let stream = std::os::unix::net::UnixStream::connect(path)
+                    .map_err(|e| self.connect_error(e))?;
+                stream
+                    .set_read_timeout(timeout)
+                    .map_err(|e| self.connect_error(e))?;
+                Box::new(stream)
+            }
+            #[cfg(not(unix))]
+            DockerHost::Unix(_) => return Err(self.connect_error(unsupported())),
+            DockerHost::Tcp(addr) => {
+                let stream =
+                    std::net::TcpStream::connect(addr).map_err(|e| self.connect_error(e))?;
+                stream
+                    .set_read_timeout(timeout)
+                    .map_err(|e| self.connect_error(e))?;
+                Box::new(stream)
+            }
+        };
+        Ok(connection)
+    }
+
+    fn connect_error(&self, e: std::io::Error) -> String {
+        format!("Cannot connect to Docker at {}: {}. {}", self, e, HINT)
+    }
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Unix sockets are not supported on this platform",
+    )
+}
+
+impl fmt::Display for DockerHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DockerHost::Unix(path) => write!(f, "unix://{}", path.display()),
+            DockerHost::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+trait BlockingConnection: Read + Write {}
+impl<T: Read + Write> BlockingConnection for T {}
+
+/// A `GET` request; the daemon closes the connection after responding
+fn request(path: &str) -> String {
+    format!(
+        "GET {} HTTP/1.1\r\nHost: docker\r\nUser-Agent: barklog\r\nConnection: close\r\n\r\n",
+        path
+    )
+}
+
+/// Percent-encode a path segment or query value
+fn percent_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Status and body framing of a response
+#[derive(Debug, PartialEq, Eq)]
+struct ResponseHead {
+    status: u16,
+    chunked: bool,
+}
+
+/// Parse a response head once it has fully arrived, returning it along with
+/// its length in bytes
+fn parse_head(buf: &[u8]) -> Result<Option<(ResponseHead, usize)>, String> {
+    let Some(end) = find(buf, b"\r\n\r\n") else {
+        return Ok(None);
+    };
+    let head = String::from_utf8_lossy(&buf[..end]);
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| format!("Malformed response from Docker: {}", status_line))?;
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding")
+                && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+    Ok(Some((ResponseHead { status, chunked }, end + 4)))
+}
+
+/// Undoes `Transfer-Encoding: chunked` as data arrives
+#[derive(Debug, Default)]
+struct ChunkedDecoder {
+    buf: Vec<u8>,
+    /// Bytes of the current chunk still to come
+    remaining: usize,
+    /// The current chunk is complete but its trailing CRLF hasn't been skipped
+    in_chunk_end: bool,
+    /// The last (empty) chunk arrived
+    done: bool,
+}
+
+impl ChunkedDecoder {
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        self.buf.extend_from_slice(data);
+        let mut out = Vec::new();
+        while !self.done {
+            if self.remaining > 0 {
+                if self.buf.is_empty() {
+                    break;
+                }
+                let n = self.remaining.min(self.buf.len());
+                out.extend(self.buf.drain(..n));
+                self.remaining -= n;
+                self.in_chunk_end = self.remaining == 0;
+                continue;
+            }
+            if self.in_chunk_end {
+                if self.buf.len() < 2 {
+                    break;
+                }
+                self.buf.drain(..2);
+                self.in_chunk_end = false;
+            }
+            let Some(end) = find(&self.buf, b"\r\n") else {
+                break;
+            };
+            let line = String::from_utf8_lossy(&self.buf[..end]);
+            // Chunk extensions (`;name=value`) carry nothing we need
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| format!("Malformed chunk from Docker: {:?}", line))?;
+            self.buf.drain(..end + 2);
+            if size == 0 {
+                self.done = true;
+            }
+            self.remaining = size;
+        }
+        Ok(out)
+    }
+}
+
+/// A response body, decoded as it arrives
+enum Body {
+    /// Runs until the connection closes
+    Plain,
+    Chunked(ChunkedDecoder),
+}
+
+impl Body {
+    fn new(head: &ResponseHead) -> Self {
+        if head.chunked {
+            Body::Chunked(ChunkedDecoder::default())
+        } else {
+            Body::Plain
+        }
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Body::Plain => Ok(data.to_vec()),
+            Body::Chunked(decoder) => decoder.decode(data),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(self, Body::Chunked(decoder) if decoder.done)
+    }
+}
+
+/// Split a complete response into its status and decoded body
+fn parse_response(response: &[u8]) -> Result<(u16, Vec<u8>), String> {
+    let (head, len) = parse_head(response)?
+        .ok_or_else(|| "Docker closed the connection without responding".to_string())?;
+    let body = Body::new(&head).decode(&response[len..])?;
+    Ok((head.status, body))
+}
+
+/// Docker reports failures as `{"message": "..."}`
+fn error_message(status: u16, body: &[u8]) -> String {
+    serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("message")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("Docker API returned status {}", status))
+}
+
+fn json_response(response: &[u8]) -> Result<Value, String> {
+    let (status, body) = parse_response(response)?;
+    if status != 200 {
+        return Err(error_message(status, &body));
+    }
+    serde_json::from_slice(&body).map_err(|e| format!("Invalid response from Docker: {}", e))
+}
+
+async fn get_json(host: &DockerHost, path: &str) -> Result<Value, String> {
+    let mut connection = host.connect().await?;
+    let mut response = Vec::new();
+    connection
+        .write_all(request(path).as_bytes())
+        .await
+        .map_err(|e| host.connect_error(e))?;
+    connection
+        .read_to_end(&mut response)
+        .await
+        .map_err(|e| host.connect_error(e))?;
+    json_response(&response)
+}
+
+fn get_json_blocking(host: &DockerHost, path: &str) -> Result<Value, String> {
+    let mut connection = host.connect_blocking()?;
+    let mut response = Vec::new();
+    connection
+        .write_all(request(path).as_bytes())
+        .map_err(|e| host.connect_error(e))?;
+    connection
+        .read_to_end(&mut response)
+        .map_err(|e| host.connect_error(e))?;
+    json_response(&response)
+}
+
+/// The body of a streaming response (logs, events), read as it arrives
+struct BodyStream {
+    connection: Box<dyn Connection>,
+    body: Body,
+    /// Body bytes that arrived along with the head
+    pending: Option<Vec<u8>>,
+    chunk: Vec<u8>,
+}
+
+impl BodyStream {
+    /// Send a request and wait for the response head. Anything but 200 is
+    /// an error carrying Docker's message.
+    async fn open(host: &DockerHost, path: &str) -> Result<Self, String> {
+        let mut connection = host.connect().await?;
+        connection
+            .write_all(request(path).as_bytes())
+            .await
+            .map_err(|e| host.connect_error(e))?;
+
+        let mut buf = Vec::new();
+        let mut chunk = vec![0u8; 8192];
+        loop {
+            let n = connection.read(&mut chunk).await.map_err(lost_connection)?;
+            if n == 0 {
+                return Err("Docker closed the connection without responding".to_string());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            let Some((head, len)) = parse_head(&buf)? else {
+                continue;
+            };
+            if head.status != 200 {
+                let _ = connection.read_to_end(&mut buf).await;
+                let (status, body) = parse_response(&buf)?;
+                return Err(error_message(status, &body));
+            }
+            let mut body = Body::new(&head);
+            let pending = Some(body.decode(&buf[len..])?);
+            return Ok(Self {
+                connection,
+                body,
+                pending,
+                chunk,
+            });
+        }
+    }
+
+    /// The next piece of the body; None once it has ended
+    async fn next(&mut self) -> Result<Option<Vec<u8>>, String> {
+        if let Some(data) = self.pending.take().filter(|data| !data.is_empty()) {
+            return Ok(Some(data));
+        }
+        while !self.body.is_done() {
+            let n = self
+                .connection
+                .read(&mut self.chunk)
+                .await
+                .map_err(lost_connection)?;
+            if n == 0 {
+                break;
+            }
+            let data = self.body.decode(&self.chunk[..n])?;
+            if !data.is_empty() {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn lost_connection(e: std::io::Error) -> String {
+    format!("Lost connection to Docker: {}", e)
+}
+
+/// A running container, as `GET /containers/json` lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerSummary {
+    pub name: String,
+    pub status: String,
+    pub image: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+/// List running containers (what `docker ps` shows)
+pub fn list_containers(host: &DockerHost) -> Result<Vec<ContainerSummary>, String> {
+    get_json_blocking(host, "/containers/json").map(|list| parse_container_list(&list))
+}
+
+fn parse_container_list(list: &Value) -> Vec<ContainerSummary> {
+    let text = |container: &Value, key: &str| {
+        container
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|container| {
+            // Names carry a leading '/' (linked containers have several)
+            let name = container.get("Names")?.get(0)?.as_str()?;
+            Some(ContainerSummary {
+                name: name.trim_start_matches('/').to_string(),
+                status: text(container, "Status"),
+                image: text(container, "Image"),
+                labels: string_map(container.get("Labels")),
+            })
+        })
+        .collect()
+}
+
+/// The string entries of a JSON object (labels, event attributes)
+fn string_map(value: Option<&Value>) -> BTreeMap<String, String> {
+    value
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+        .collect()
+}
+
+/// Which containers a Docker watch follows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContainerPattern {
+    All,
+    /// Containers whose name matches a glob (`api-*`)
+    Name(glob::Pattern),
+    /// Containers with a label, optionally with a value matching a glob
+    /// (`label=com.docker.compose.service=api`)
+    Label {
+        key: String,
+        value: Option<glob::Pattern>,
+    },
+    /// Containers whose image matches a glob, with or without its tag
+    /// (`image=nginx`, `image=myorg/*:1.*`)
+    Image(glob::Pattern),
+}
+
+impl ContainerPattern {
+    /// Parse `<name glob>`, `label=<key>[=<value glob>]` or `image=<glob>`
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        let glob = |pattern: &str| {
+            glob::Pattern::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))
+        };
+        if let Some(label) = arg.strip_prefix("label=") {
+            let (key, value) = match label.split_once('=') {
+                Some((key, value)) => (key, Some(glob(value)?)),
+                None => (label, None),
+            };
+            if key.is_empty() {
+                return Err(format!("Invalid label pattern '{}': no label name", arg));
+            }
+            Ok(ContainerPattern::Label {
+                key: key.to_string(),
+                value,
+            })
+        } else if let Some(image) = arg.strip_prefix("image=") {
+            Ok(ContainerPattern::Image(glob(image)?))
+        } else {
+            Ok(ContainerPattern::Name(glob(arg)?))
+        }
+    }
+
+    pub fn matches(&self, container: &ContainerSummary) -> bool {
+        match self {
+            ContainerPattern::All => true,
+            ContainerPattern::Name(pattern) => pattern.matches(&container.name),
+            ContainerPattern::Label { key, value } => container
+                .labels
+                .get(key)
+                .is_some_and(|label| value.as_ref().is_none_or(|pattern| pattern.matches(label))),
+            ContainerPattern::Image(pattern) => {
+                pattern.matches(&container.image)
+                    || pattern.matches(image_repository(&container.image))
+            }
+        }
+    }
+}
+
+/// `nginx:1.27` -> `nginx` (the port of `registry:5000/app` isn't a tag)
+fn image_repository(image: &str) -> &str {
+    match image.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => image,
+    }
+}
+
+/// A container starting or stopping, from `GET /events`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerEvent {
+    /// `start` rather than `die`
+    pub started: bool,
+    /// Event attributes hold the container's labels alongside `name` and `image`
+    pub container: ContainerSummary,
+}
+
+/// Parse one entry of the events stream (None for anything but container
+/// starts and stops)
+fn parse_event(line: &[u8]) -> Option<ContainerEvent> {
+    let event: Value = serde_json::from_slice(line).ok()?;
+    if event.get("Type")?.as_str()? != "container" {
+        return None;
+    }
+    let action = event.get("Action")?.as_str()?;
+    let started = match action {
+        "start" => true,
+        "die" => false,
+        _ => return None,
+    };
+    let attributes = string_map(event.pointer("/Actor/Attributes"));
+    Some(ContainerEvent {
+        started,
+        container: ContainerSummary {
+            name: attributes.get("name")?.clone(),
+            status: action.to_string(),
+            image: attributes.get("image").cloned().unwrap_or_default(),
+            labels: attributes,
+        },
+    })
+}
+
+/// A subscription to container start and stop events
+pub struct ContainerEvents {
+    stream: BodyStream,
+    /// Events arrive as newline-terminated JSON objects
+    buf: Vec<u8>,
+}
+
+impl ContainerEvents {
+    pub async fn subscribe(host: &DockerHost) -> Result<Self, String> {
+        let filters = r#"{"type":["container"],"event":["start","die"]}"#;
+        let path = format!("/events?filters={}", percent_encode(filters));
+        Ok(Self {
+            stream: BodyStream::open(host, &path).await?,
+            buf: Vec::new(),
+        })
+    }
+
+    /// The next start or stop; None once the daemon ends the stream
+    pub async fn next(&mut self) -> Result<Option<ContainerEvent>, String> {
+        loop {
+            while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=pos).collect();
+                if let Some(event) = parse_event(&line) {
+                    return Ok(Some(event));
+                }
+            }
+            match self.stream.next().await? {
+                Some(data) => self.buf.extend_from_slice(&data),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Output stream a log frame was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stream {
+    Stdout = 0,
+    Stderr = 1,
+}
+
+/// Splits Docker's multiplexed log stream. Each frame is an 8-byte header
+/// (stream type, three zero bytes, big-endian payload length) and payload.
+#[derive(Debug, Default)]
+struct FrameDecoder {
+    buf: Vec<u8>,
+}
+
+impl FrameDecoder {
+    fn decode(&mut self, data: &[u8]) -> Vec<(Stream, Vec<u8>)> {
+        self.buf.extend_from_slice(data);
+        let mut frames = Vec::new();
+        while self.buf.len() >= 8 {
+            let len = u32::from_be_bytes([self.buf[4], self.buf[5], self.buf[6], self.buf[7]]);
+            let end = 8 + len as usize;
+            if self.buf.len() < end {
+                break;
+            }
+            let stream = if self.buf[0] == 2 {
+                Stream::Stderr
+            } else {
+                Stream::Stdout
+            };
+            frames.push((stream, self.buf[8..end].to_vec()));
+            self.buf.drain(..end);
+        }
+        frames
+    }
+}
+
+/// Reassembles lines per stream, since frames needn't end at a newline
+#[derive(Debug, Default)]
+struct LineSplitter {
+    partial: [Vec<u8>; 2],
+}
+
+impl LineSplitter {
+    fn push(&mut self, stream: Stream, data: &[u8]) -> Vec<String> {
+        let partial = &mut self.partial[stream as usize];
+        partial.extend_from_slice(data);
+        let mut lines = Vec::new();
+        while let Some(pos) = partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = partial.drain(..=pos).collect();
+            lines.push(decode_line(&line[..pos]));
+        }
+        lines
+    }
+
+    /// Unterminated lines left when the stream ends
+    fn finish(&mut self) -> Vec<String> {
+        self.partial
+            .iter_mut()
+            .filter(|partial| !partial.is_empty())
+            .map(|partial| decode_line(&std::mem::take(partial)))
+            .collect()
+    }
+}
+
+/// Format a resume point for `since`, which is inclusive: start just after
+/// the last line seen
+fn since_param(at: DateTime<Utc>) -> String {
+    let at = at + chrono::Duration::nanoseconds(1);
+    format!("{}.{:09}", at.timestamp(), at.timestamp_subsec_nanos())
+}
+
+/// One container's log stream, resumable across connections
+struct LogFollower {
+    host: DockerHost,
+    container: String,
+    /// Docker's timestamp of the last line; a reconnect resumes after it
+    last: Option<DateTime<Utc>>,
+    /// Whether the current run delivered any lines
+    received: bool,
+}
+
+impl LogFollower {
+    /// Follow the logs until the stream ends. Ok when it ended cleanly (the
+    /// container stopped) or the receiver went away.
+    async fn run(&mut self, tx: &mpsc::Sender<LogEvent>) -> Result<(), String> {
+        let name = percent_encode(&self.container);
+        let info = get_json(&self.host, &format!("/containers/{}/json", name)).await?;
+        // A TTY container's output is a single raw stream, not multiplexed
+        let tty = info
+            .pointer("/Config/Tty")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let start = match self.last {
+            Some(at) => format!("since={}", since_param(at)),
+            None => format!("tail={}", DEFAULT_TAIL_LINES),
+        };
+        let path = format!(
+            "/containers/{}/logs?follow=1&stdout=1&stderr=1&timestamps=1&{}",
+            name, start
+        );
+
+        let mut stream = tokio::select! {
+            stream = BodyStream::open(&self.host, &path) => stream?,
+            _ = tx.closed() => return Ok(()),
+        };
+        let mut frames = FrameDecoder::default();
+        let mut lines = LineSplitter::default();
+        loop {
+            let data = tokio::select! {
+                data = stream.next() => data?,
+                _ = tx.closed() => return Ok(()),
+            };
+            let Some(data) = data else {
+                break;
+            };
+            let pieces = if tty {
+                vec![(Stream::Stdout, data)]
+            } else {
+                frames.decode(&data)
+            };
+            for (stream, bytes) in pieces {
+                for line in lines.push(stream, &bytes) {
+                    if !self.send(tx, &line).await {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        for line in lines.finish() {
+            if !self.send(tx, &line).await {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a line, timed by Docker rather than by its text.
+    /// Returns false once the receiver is gone.
+    async fn send(&mut self, tx: &mpsc::Sender<LogEvent>, raw: &str) -> bool {
+        let (at, text) = split_timestamp(raw);
+        let mut line = LogLine::new(text.to_string());
+        if let Some(at) = at {
+            line = line.with_timestamp(at.with_timezone(&Local));
+            self.last = Some(at);
+        }
+        self.received = true;
+        tx.send(LogEvent::Line(line)).await.is_ok()
+    }
+}
+
+/// A log source that follows a Docker container through the Engine API
+pub struct DockerApiSource {
+    container: String,
+    host: DockerHost,
+    /// Start after this line instead of at the tail
+    resume: Option<DateTime<Utc>>,
+}
+
+impl DockerApiSource {
+    pub fn new(container: String, host: DockerHost) -> Self {
+        Self {
+            container,
+            host,
+            resume: None,
+        }
+    }
+
+    /// Pick up after a line already shown, as when a restarted container
+    /// comes back: its tail would replay the previous run
+    pub fn resume_after(mut self, at: Option<DateTime<Utc>>) -> Self {
+        self.resume = at;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl LogSource for DockerApiSource {
+    async fn stream(&self) -> mpsc::Receiver<LogEvent> {
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_BUFFER);
+        let mut follower = LogFollower {
+            host: self.host.clone(),
+            container: self.container.clone(),
+            last: self.resume,
+            received: false,
+        };
+
+        tokio::spawn(async move {
+            let mut backoff = Backoff::new(RECONNECT);
+            loop {
+                follower.received = false;
+                let result = follower.run(&tx).await;
+                if tx.is_closed() {
+                    break;
+                }
+                if follower.received {
+                    backoff.reset();
+                }
+                if !backoff.retry_after(&tx, result.err()).await {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    fn name(&self) -> String {
+        self.container.clone()
+    }
+}
+
+/// A stand-in Docker daemon for tests
+#[cfg(test)]
+pub(crate) mod fake {
+    use super::find;
+
+    /// Chunked encoding of `body`, in chunks of `chunk_size` bytes
+    pub fn chunked(body: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for chunk in body.chunks(chunk_size) {
+            out.extend(format!("{:x}\r\n", chunk.len()).as_bytes());
+            out.extend(chunk);
+            out.extend(b"\r\n");
+        }
+        out.extend(b"0\r\n\r\n");
+        out
+    }
+
+    /// A 200 response with a JSON body
+    pub fn json(body: &str) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .into_bytes()
+    }
+
+    /// A 200 response streaming `body` in chunks
+    pub fn streaming(body: &[u8]) -> Vec<u8> {
+        let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        response.extend(chunked(body, 5));
+        response
+    }
+
+    /// Serve canned responses, one per connection, on a fake daemon socket.
+    /// Resolves to the request lines received.
+    #[cfg(unix)]
+    pub fn daemon(
+        socket: &std::path::Path,
+        responses: Vec<Vec<u8>>,
+    ) -> tokio::task::JoinHandle<Vec<String>> {
+        let connections = responses.len();
+        let mut responses = responses.into_iter();
+        serve(socket, connections, move |_| responses.next().unwrap())
+    }
+
+    /// Answer `connections` connections with `respond(request line)`.
+    /// Resolves to the request lines received.
+    #[cfg(unix)]
+    pub fn serve(
+        socket: &std::path::Path,
+        connections: usize,
+        mut respond: impl FnMut(&str) -> Vec<u8> + Send + 'static,
+    ) -> tokio::task::JoinHandle<Vec<String>> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let _ = std::fs::remove_file(socket);
+        let listener = tokio::net::UnixListener::bind(socket).unwrap();
+        tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for _ in 0..connections {
+                let (mut conn, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while find(&buf, b"\r\n\r\n").is_none() {
+                    let n = conn.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let head = String::from_utf8_lossy(&buf).into_owned();
+                let request = head.lines().next().unwrap_or_default().to_string();
+                conn.write_all(&respond(&request)).await.unwrap();
+                requests.push(request);
+            }
+            requests
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::chunked;
+    use super::*;
+
+    fn frame(stream: u8, payload: &str) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend((payload.len() as u32).to_be_bytes());
+        frame.extend(payload.as_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_parse_docker_host() {
+        assert_eq!(
+            DockerHost::parse("unix:///run/user/1000/docker.sock"),
+            Some(DockerHost::Unix(PathBuf::from(
+                "/run/user/1000/docker.sock"
+            )))
+        );
+        assert_eq!(
+            DockerHost::parse("tcp://10.0.0.5:2375/"),
+            Some(DockerHost::Tcp("10.0.0.5:2375".to_string()))
+        );
+        // Left to the CLI
+        assert_eq!(DockerHost::parse("ssh://me@build-box"), None);
+        assert_eq!(DockerHost::parse("npipe:////./pipe/docker_engine"), None);
+    }
+
+    #[test]
+    fn test_parse_head() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Type: application/vnd.docker.multiplexed-stream\r\ntransfer-encoding: Chunked\r\n\r\nbody";
+        assert_eq!(
+            parse_head(response).unwrap(),
+            Some((
+                ResponseHead {
+                    status: 200,
+                    chunked: true
+                },
+                response.len() - 4
+            ))
+        );
+        // Not all there yet
+        assert_eq!(parse_head(b"HTTP/1.1 404 Not Found\r\n").unwrap(), None);
+        assert!(parse_head(b"garbage\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_chunked_decoder_handles_any_split() {
+        let body = b"hello, chunked world";
+        let encoded = chunked(body, 7);
+        // Feed the encoding in every possible pair of pieces
+        for split in 0..encoded.len() {
+            let mut decoder = ChunkedDecoder::default();
+            let mut out = decoder.decode(&encoded[..split]).unwrap();
+            out.extend(decoder.decode(&encoded[split..]).unwrap());
+            assert_eq!(out, body);
+            assert!(decoder.done);
+        }
+        assert!(ChunkedDecoder::default().decode(b"zz\r\n").is_err());
+    }
+
+    #[test]
+    fn test_frames_and_lines_are_reassembled_per_stream() {
+        let mut stream = frame(1, "out one\nout ");
+        stream.extend(frame(2, "err one\n"));
+        stream.extend(frame(1, "two\r\n"));
+        stream.extend(frame(2, "err tail"));
+
+        let mut frames = FrameDecoder::default();
+        let mut lines = LineSplitter::default();
+        let mut got = Vec::new();
+        // Byte by byte: headers and payloads arrive split
+        for byte in &stream {
+            for (stream, payload) in frames.decode(std::slice::from_ref(byte)) {
+                got.extend(lines.push(stream, &payload));
+            }
+        }
+        got.extend(lines.finish());
+        assert_eq!(got, vec!["out one", "err one", "out two", "err tail"]);
+    }
+
+    #[test]
+    fn test_split_timestamp_and_resume_point() {
+        let (at, text) = split_timestamp("2024-05-01T10:00:00.123456789Z GET /health 200");
+        assert_eq!(text, "GET /health 200");
+        let at = at.unwrap();
+        assert_eq!(at.timestamp_subsec_nanos(), 123_456_789);
+        assert_eq!(since_param(at), format!("{}.123456790", at.timestamp()));
+
+        assert_eq!(split_timestamp("2024-05-01T10:00:00Z").1, "");
+        assert_eq!(
+            split_timestamp("no timestamp here"),
+            (None, "no timestamp here")
+        );
+    }
+
+    #[test]
+    fn test_parse_container_list_and_errors() {
+        let list = serde_json::json!([
+            {"Names": ["/web"], "Status": "Up 2 hours", "Image": "nginx:1.27", "Labels": {"tier": "frontend"}},
+            {"Names": [], "Status": "Up", "Image": "broken"},
+        ]);
+        assert_eq!(
+            parse_container_list(&list),
+            vec![ContainerSummary {
+                name: "web".to_string(),
+                status: "Up 2 hours".to_string(),
+                image: "nginx:1.27".to_string(),
+                labels: BTreeMap::from([("tier".to_string(), "frontend".to_string())]),
+            }]
+        );
+        assert_eq!(
+            error_message(404, br#"{"message":"No such container: nope"}"#),
+            "No such container: nope"
+        );
+        assert_eq!(error_message(500, b""), "Docker API returned status 500");
+        assert_eq!(percent_encode("web.1/../x"), "web.1%2F..%2Fx");
+    }
+
+    #[test]
+    fn test_container_pattern_matches_name_label_and_image() {
+        let container = ContainerSummary {
+            name: "shop-api-2".to_string(),
+            status: "Up 1 second".to_string(),
+            image: "registry:5000/shop/api:1.4".to_string(),
+            labels: BTreeMap::from([("com.docker.compose.service".to_string(), "api".to_string())]),
+        };
+        let matches = |arg: &str| ContainerPattern::parse(arg).unwrap().matches(&container);
+
+        assert!(ContainerPattern::All.matches(&container));
+        assert!(matches("shop-*"));
+        assert!(!matches("shop"));
+        assert!(matches("label=com.docker.compose.service"));
+        assert!(matches("label=com.docker.compose.service=a*"));
+        assert!(!matches("label=com.docker.compose.service=web"));
+        assert!(!matches("label=com.docker.compose.project"));
+        // With or without the tag; a registry port isn't one
+        assert!(matches("image=registry:5000/shop/api"));
+        assert!(matches("image=*/shop/api:1.*"));
+        assert!(!matches("image=registry"));
+
+        assert!(ContainerPattern::parse("label=").is_err());
+        assert!(ContainerPattern::parse("image=[").is_err());
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let event = br#"{"Type":"container","Action":"start","Actor":{"ID":"4f1c","Attributes":{"image":"nginx","name":"web-2","tier":"frontend"}},"time":1714557600}"#;
+        let event = parse_event(event).unwrap();
+        assert!(event.started);
+        assert_eq!(event.container.name, "web-2");
+        assert_eq!(event.container.image, "nginx");
+        assert_eq!(event.container.labels["tier"], "frontend");
+
+        let died = br#"{"Type":"container","Action":"die","Actor":{"Attributes":{"name":"web-2","exitCode":"137"}}}"#;
+        assert!(!parse_event(died).unwrap().started);
+        // Other actions and object types are skipped
+        let exec = br#"{"Type":"container","Action":"exec_start: sh","Actor":{"Attributes":{"name":"web-2"}}}"#;
+        assert_eq!(parse_event(exec), None);
+        assert_eq!(
+            parse_event(br#"{"Type":"network","Action":"connect"}"#),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_source_follows_logs_from_a_fake_daemon() {
+        let socket = std::env::temp_dir().join(format!("bark_docker_{}.sock", std::process::id()));
+        let mut logs = frame(1, "2024-05-01T10:00:00.000000001Z starting\n");
+        logs.extend(frame(2, "2024-05-01T10:00:00.000000002Z ERROR boom\n"));
+        logs.extend(frame(1, "2024-05-01T10:00:00.000000003Z ready\n"));
+        let daemon = fake::daemon(
+            &socket,
+            vec![
+                fake::json(r#"{"Config":{"Tty":false}}"#),
+                fake::streaming(&logs),
+            ],
+        );
+        let source = DockerApiSource::new("web".to_string(), DockerHost::Unix(socket.clone()));
+        let mut rx = source.stream().await;
+
+        let mut lines = Vec::new();
+        while lines.len() < 3 {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("timed out waiting for lines")
+                .expect("stream ended early");
+            if let LogEvent::Line(line) = event {
+                lines.push(line);
+            }
+        }
+        // The stream ended cleanly, so the source reconnects
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert!(matches!(
+            event,
+            Ok(Some(LogEvent::Reconnecting { attempt: 1 }))
+        ));
+        drop(rx);
+
+        let raw: Vec<&str> = lines.iter().map(|l| l.raw.as_str()).collect();
+        assert_eq!(raw, vec!["starting", "ERROR boom", "ready"]);
+        let nanos: Vec<u32> = lines
+            .iter()
+            .map(|l| l.timestamp.unwrap().timestamp_subsec_nanos())
+            .collect();
+        assert_eq!(nanos, vec![1, 2, 3]);
+
+        let requests = daemon.await.unwrap();
+        assert_eq!(requests[0], "GET /containers/web/json HTTP/1.1");
+        assert_eq!(
+            requests[1],
+            "GET /containers/web/logs?follow=1&stdout=1&stderr=1&timestamps=1&tail=1000 HTTP/1.1"
+        );
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    /// Lines of one run of a source, up to the reconnect after it ends
+    #[cfg(unix)]
+    async fn read_run(source: DockerApiSource) -> Vec<LogLine> {
+        let mut rx = source.stream().await;
+        let mut lines = Vec::new();
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("timed out waiting for lines")
+                .expect("stream ended early");
+            match event {
+                LogEvent::Line(line) => lines.push(line),
+                LogEvent::Reconnecting { .. } => return lines,
+                _ => {}
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restarted_container_resumes_after_lines_already_shown() {
+        let socket =
+            std::env::temp_dir().join(format!("bark_docker_restart_{}.sock", std::process::id()));
+        // Docker keeps a container's logs across a stop and start
+        let history = [
+            "2024-05-01T10:00:00.000000001Z starting",
+            "2024-05-01T10:00:00.000000002Z ready",
+            "2024-05-01T10:00:00.000000003Z stopping",
+            "2024-05-01T10:05:00.000000001Z starting",
+            "2024-05-01T10:05:00.000000002Z ready",
+        ];
+        let mut log_requests = 0;
+        let daemon = fake::serve(&socket, 4, move |request| {
+            if request.contains("/json") {
+                return fake::json(r#"{"Config":{"Tty":false}}"#);
+            }
+            // The first run stopped after three lines
+            log_requests += 1;
+            let written = if log_requests == 1 {
+                &history[..3]
+            } else {
+                &history[..]
+            };
+            let since = request.split("since=").nth(1).map(|rest| {
+                let stamp = rest.split_whitespace().next().unwrap();
+                let (secs, nanos) = stamp.split_once('.').unwrap();
+                DateTime::from_timestamp(secs.parse().unwrap(), nanos.parse().unwrap()).unwrap()
+            });
+            let mut body = Vec::new();
+            for line in written {
+                if since.is_none_or(|since| split_timestamp(line).0.unwrap() >= since) {
+                    body.extend(frame(1, &format!("{}\n", line)));
+                }
+            }
+            fake::streaming(&body)
+        });
+        let host = DockerHost::Unix(socket.clone());
+
+        let first = read_run(DockerApiSource::new("web".to_string(), host.clone())).await;
+        let last = first.last().and_then(|l| l.timestamp);
+        let second = read_run(
+            DockerApiSource::new("web".to_string(), host)
+                .resume_after(last.map(|at| at.with_timezone(&Utc))),
+        )
+        .await;
+
+        let raw: Vec<&str> = first
+            .iter()
+            .chain(&second)
+            .map(|l| l.raw.as_str())
+            .collect();
+        assert_eq!(
+            raw,
+            vec!["starting", "ready", "stopping", "starting", "ready"]
+        );
+
+        let requests = daemon.await.unwrap();
+        assert!(requests[1].contains("&tail=1000 "));
+        assert!(requests[3].contains("&since=1714557600.000000004 "));
+        let _ = std::fs::remove_file(&socket);
+    }
+}
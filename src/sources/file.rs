@@ -1,21 +1,65 @@
-use std::path::PathBuf;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::sync::mpsc;
 
+use super::archive::{detect_compression, read_lines, rotation_set};
+use super::tail::{FileTailer, TailEvent};
 use super::{LogEvent, LogSource};
 use crate::app::LogLine;
-use crate::config::DEFAULT_CHANNEL_BUFFER;
+use crate::config::{DEFAULT_CHANNEL_BUFFER, DEFAULT_TAIL_LINES, FILE_POLL_INTERVAL_MS};
 
-/// A log source that reads from a file using tail -F
+/// A log source that follows a local file natively (like `tail -F`).
+///
+/// Compressed files (gzip, zstd, bzip2) are decompressed and read once.
+/// In rotation-set mode, rotated siblings are replayed oldest first before
+/// the live file is read from its beginning and followed.
 pub struct FileSource {
     path: PathBuf,
+    rotated: bool,
 }
 
 impl FileSource {
     pub fn new(path: PathBuf) -> Self {
-        Self { path }
+        Self {
+            path,
+            rotated: false,
+        }
     }
+
+    /// Stitch the file's rotation set (`app.log.2.gz`, `app.log.1`, `app.log`)
+    /// into one stream
+    pub fn with_rotation_set(path: PathBuf) -> Self {
+        Self {
+            path,
+            rotated: true,
+        }
+    }
+}
+
+/// Convert a tailer event into a log event
+fn to_log_event(event: TailEvent) -> LogEvent {
+    match event {
+        TailEvent::Line(line) => LogEvent::Line(LogLine::new(line)),
+        TailEvent::Truncated => LogEvent::Notice("File truncated, reading from start".to_string()),
+        TailEvent::Rotated => LogEvent::Notice("File rotated, following new file".to_string()),
+        TailEvent::Reopened => LogEvent::Notice("File reappeared, following".to_string()),
+        TailEvent::Missing(msg) => LogEvent::Error(format!("Waiting for file: {}", msg)),
+    }
+}
+
+/// Send every line of an archive. Returns false once the receiver is gone.
+fn send_archive(path: &Path, tx: &mpsc::Sender<LogEvent>) -> bool {
+    let mut open = true;
+    let result = read_lines(path, |line| {
+        open = tx.blocking_send(LogEvent::Line(LogLine::new(line))).is_ok();
+        open
+    });
+    if let Err(e) = result {
+        open = tx
+            .blocking_send(LogEvent::Error(format!("{}: {}", path.display(), e)))
+            .is_ok();
+    }
+    open
 }
 
 #[async_trait::async_trait]
@@ -23,68 +67,60 @@ impl LogSource for FileSource {
     async fn stream(&self) -> mpsc::Receiver<LogEvent> {
         let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_BUFFER);
         let path = self.path.clone();
+        let rotated = self.rotated;
 
-        tokio::spawn(async move {
-            let result = Command::new("tail")
-                .arg("-F")
-                .arg(&path)
-                .stdout(std::process::Stdio::piped())
-                .stderr(std::process::Stdio::piped())
-                .spawn();
-
-            match result {
-                Ok(mut child) => {
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        let mut lines = reader.lines();
-
-                        loop {
-                            match lines.next_line().await {
-                                Ok(Some(line)) => {
-                                    if tx.send(LogEvent::Line(LogLine::new(line))).await.is_err() {
-                                        break;
-                                    }
-                                }
-                                Ok(None) => {
-                                    // EOF - process exited
-                                    break;
-                                }
-                                Err(e) => {
-                                    // Report read error (e.g., invalid UTF-8) and continue
-                                    let _ = tx
-                                        .send(LogEvent::Error(format!("Read error: {}", e)))
-                                        .await;
-                                    // Continue reading - don't abort on single bad line
-                                }
+        // File I/O is blocking, so the tailer runs on the blocking pool.
+        // It exits on its own once the receiver is dropped.
+        tokio::task::spawn_blocking(move || {
+            if rotated {
+                match rotation_set(&path) {
+                    Ok(archives) => {
+                        for archive in archives {
+                            if !send_archive(&archive, &tx) {
+                                return;
                             }
                         }
                     }
+                    Err(e) => {
+                        let _ = tx.blocking_send(LogEvent::Error(format!(
+                            "Cannot list rotated files: {}",
+                            e
+                        )));
+                    }
+                }
+            }
 
-                    // Wait for process to exit
-                    match child.wait().await {
-                        Ok(status) if !status.success() => {
-                            let _ = tx
-                                .send(LogEvent::Error(format!(
-                                    "tail exited with status: {}",
-                                    status
-                                )))
-                                .await;
-                        }
-                        Err(e) => {
-                            let _ = tx
-                                .send(LogEvent::Error(format!("Error waiting for tail: {}", e)))
-                                .await;
-                        }
-                        _ => {}
+            // Compressed files never grow, so read them once and stop
+            if let Ok(Some(_)) = detect_compression(&path) {
+                if send_archive(&path, &tx) {
+                    let _ = tx.blocking_send(LogEvent::EndOfStream);
+                }
+                return;
+            }
+
+            let mut tailer = FileTailer::new(path);
+            let mut events = if rotated {
+                // Continue right where the newest archive left off
+                tailer.open_from_start()
+            } else {
+                tailer.open_at_tail(DEFAULT_TAIL_LINES.parse().unwrap_or(0))
+            };
+
+            loop {
+                events.extend(tailer.poll());
+                for event in events.drain(..) {
+                    if tx.blocking_send(to_log_event(event)).is_err() {
+                        return;
                     }
+                }
 
-                    let _ = tx.send(LogEvent::EndOfStream).await;
+                if tx.is_closed() {
+                    return;
                 }
-                Err(e) => {
-                    let _ = tx
-                        .send(LogEvent::Error(format!("Failed to spawn tail: {}", e)))
-                        .await;
-                    let _ = tx.send(LogEvent::EndOfStream).await;
+
+                // Keep reading without pausing while a large backlog remains
+                if !tailer.has_pending_data() {
+                    std::thread::sleep(Duration::from_millis(FILE_POLL_INTERVAL_MS));
                 }
             }
         });
@@ -93,9 +129,50 @@ impl LogSource for FileSource {
     }
 
     fn name(&self) -> String {
-        self.path
+        let name = self
+            .path
             .file_name()
             .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| self.path.display().to_string())
+            .unwrap_or_else(|| self.path.display().to_string());
+        if self.rotated {
+            format!("{}*", name)
+        } else {
+            name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_rotation_set_streams_oldest_first_then_live() {
+        let dir = std::env::temp_dir().join(format!("bark_file_set_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(b"oldest\n").unwrap();
+        fs::write(dir.join("app.log.2.gz"), enc.finish().unwrap()).unwrap();
+        fs::write(dir.join("app.log.1"), "older\n").unwrap();
+        fs::write(dir.join("app.log"), "live\n").unwrap();
+
+        let source = FileSource::with_rotation_set(dir.join("app.log"));
+        let mut rx = source.stream().await;
+        let mut lines = Vec::new();
+        while lines.len() < 3 {
+            match rx.recv().await {
+                Some(LogEvent::Line(line)) => lines.push(line.raw),
+                Some(_) => {}
+                None => break,
+            }
+        }
+        assert_eq!(lines, vec!["oldest", "older", "live"]);
+
+        drop(rx);
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,303 @@
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use chrono::{DateTime, Local};
+use serde_json::Value;
+
+use std::time::Duration;
+
+use super::process::{Backoff, ReconnectPolicy, StderrMode, run_process};
+use super::{LogEvent, LogSource};
+use crate::app::{LogLevel, LogLine};
+use crate::config::{DEFAULT_CHANNEL_BUFFER, DEFAULT_TAIL_LINES};
+use async_trait::async_trait;
+
+/// `journalctl -f` only exits if something is wrong locally; retry a few times
+const RECONNECT: ReconnectPolicy = ReconnectPolicy {
+    max_attempts: Some(5),
+    initial_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(10),
+};
+
+/// Validate a journalctl filter value (unit, priority, since).
+///
+/// Values are passed as `--opt=value`, so they can't inject options. A
+/// leading '-' is still refused for units and priorities, where it means the
+/// value was left out (`-u -p err`), but `since` takes relative times like
+/// `-1h`.
+pub fn validate_filter_value(kind: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err(format!("Journal {} cannot be empty", kind));
+    }
+
+    if kind != "since" && value.starts_with('-') {
+        return Err(format!("Invalid journal {}: cannot start with '-'", kind));
+    }
+
+    Ok(())
+}
+
+/// systemd journal log source using `journalctl -o json -f`
+pub struct JournaldSource {
+    /// Systemd unit to follow (all units if None)
+    unit: Option<String>,
+    /// Priority filter, e.g. "err", "4" or "warning..err"
+    priority: Option<String>,
+    /// Only show entries newer than this (e.g. "1 hour ago", "2024-01-01 10:00")
+    since: Option<String>,
+}
+
+impl JournaldSource {
+    pub fn new(unit: Option<String>, priority: Option<String>, since: Option<String>) -> Self {
+        Self {
+            unit,
+            priority,
+            since,
+        }
+    }
+}
+
+/// Map a syslog priority (0 = emerg .. 7 = debug) onto a log level
+fn priority_level(priority: u8) -> LogLevel {
+    match priority {
+        0..=3 => LogLevel::Error,
+        4 => LogLevel::Warn,
+        5 | 6 => LogLevel::Info,
+        _ => LogLevel::Debug,
+    }
+}
+
+/// Read a journal field as text. Binary fields are exported as byte arrays.
+fn field_str(entry: &Value, key: &str) -> Option<String> {
+    match entry.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64().map(|b| b as u8))
+                .collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        other => Some(other.to_string()),
+    }
+}
+
+/// Render one entry of `journalctl -o json` output.
+/// Level and timestamp come from the entry itself rather than the text heuristics.
+fn parse_journal_entry(entry: &Value) -> Option<LogLine> {
+    let message = field_str(entry, "MESSAGE")?;
+
+    let timestamp = field_str(entry, "__REALTIME_TIMESTAMP")
+        .and_then(|us| us.parse::<i64>().ok())
+        .and_then(DateTime::from_timestamp_micros)
+        .map(|ts| ts.with_timezone(&Local));
+
+    let identifier = field_str(entry, "SYSLOG_IDENTIFIER")
+        .or_else(|| field_str(entry, "_SYSTEMD_UNIT"))
+        .or_else(|| field_str(entry, "_COMM"));
+
+    // Render like `journalctl -o short-iso`: time host ident[pid]: message
+    let mut raw = String::new();
+    if let Some(ts) = timestamp {
+        raw.push_str(&ts.format("%Y-%m-%dT%H:%M:%S%.3f%:z ").to_string());
+    }
+    if let Some(host) = field_str(entry, "_HOSTNAME") {
+        raw.push_str(&host);
+        raw.push(' ');
+    }
+    if let Some(ident) = identifier {
+        raw.push_str(&ident);
+        if let Some(pid) = field_str(entry, "_PID") {
+            raw.push_str(&format!("[{}]", pid));
+        }
+        raw.push_str(": ");
+    }
+    raw.push_str(&message);
+
+    let mut line = LogLine::new(raw);
+    if let Some(priority) = field_str(entry, "PRIORITY").and_then(|p| p.parse::<u8>().ok()) {
+        line = line.with_level(priority_level(priority));
+    }
+    if let Some(ts) = timestamp {
+        line = line.with_timestamp(ts);
+    }
+    Some(line)
+}
+
+/// Parse a journal line, keeping anything that isn't a journal entry as-is.
+/// Remembers the entry's cursor so a restarted journalctl can resume after it.
+fn parse_journal_line(json: String, cursor: &mut Option<String>) -> LogLine {
+    let Ok(entry) = serde_json::from_str::<Value>(&json) else {
+        return LogLine::new(json);
+    };
+    if let Some(c) = field_str(&entry, "__CURSOR") {
+        *cursor = Some(c);
+    }
+    parse_journal_entry(&entry).unwrap_or_else(|| LogLine::new(json))
+}
+
+#[async_trait]
+impl LogSource for JournaldSource {
+    async fn stream(&self) -> mpsc::Receiver<LogEvent> {
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_BUFFER);
+
+        let unit = self.unit.clone();
+        let priority = self.priority.clone();
+        let since = self.since.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Backoff::new(RECONNECT);
+            // Cursor of the last entry; a restart picks up right after it
+            let mut cursor: Option<String> = None;
+
+            loop {
+                let mut cmd = Command::new("journalctl");
+                cmd.arg("-o").arg("json").arg("-f").arg("--no-pager");
+
+                // Use --opt=value so values can never be read as options
+                match &cursor {
+                    Some(cursor) => cmd
+                        .arg(format!("--after-cursor={}", cursor))
+                        .arg("--lines=all"),
+                    None => cmd.arg(format!("--lines={}", DEFAULT_TAIL_LINES)),
+                };
+                if let Some(unit) = &unit {
+                    cmd.arg(format!("--unit={}", unit));
+                }
+                if let Some(priority) = &priority {
+                    cmd.arg(format!("--priority={}", priority));
+                }
+                if cursor.is_none() {
+                    if let Some(since) = &since {
+                        cmd.arg(format!("--since={}", since));
+                    }
+                }
+
+                let mut received = false;
+                let exit = run_process(
+                    cmd,
+                    StderrMode::Errors,
+                    |json| {
+                        received = true;
+                        Some(parse_journal_line(json, &mut cursor))
+                    },
+                    &tx,
+                )
+                .await;
+
+                if received {
+                    backoff.reset();
+                }
+                if !backoff
+                    .retry(&tx, "journalctl", "Is systemd-journald available?", exit)
+                    .await
+                {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    fn name(&self) -> String {
+        match &self.unit {
+            Some(unit) => format!("journal:{}", unit),
+            None => "journal".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn parse(json: &str) -> Option<LogLine> {
+        parse_journal_entry(&serde_json::from_str(json).ok()?)
+    }
+
+    /// Recorded from `journalctl -o json -u sshd`
+    const SSHD_ENTRY: &str = r#"{"__CURSOR":"s=8c1f;i=4a2;b=2f1e;m=1a2b3c;t=5f9a8b7c6d5e4;x=9a8b","__REALTIME_TIMESTAMP":"1700000000123456","__MONOTONIC_TIMESTAMP":"439260061","_BOOT_ID":"2f1e0d9c8b7a","PRIORITY":"6","SYSLOG_FACILITY":"4","SYSLOG_IDENTIFIER":"sshd","_PID":"1234","_UID":"0","_COMM":"sshd","_SYSTEMD_UNIT":"sshd.service","_HOSTNAME":"web-1","MESSAGE":"Accepted publickey for deploy from 10.0.0.5 port 52144 ssh2"}"#;
+
+    /// Recorded kernel entry with an error priority and no PID
+    const KERNEL_ENTRY: &str = r#"{"__REALTIME_TIMESTAMP":"1700000005000000","PRIORITY":"3","SYSLOG_IDENTIFIER":"kernel","_TRANSPORT":"kernel","_HOSTNAME":"web-1","MESSAGE":"EXT4-fs (sda1): warning: mounting unchecked fs"}"#;
+
+    /// Binary message exported as a byte array ("hi\n" with an invalid byte)
+    const BINARY_ENTRY: &str =
+        r#"{"__REALTIME_TIMESTAMP":"1700000010000000","PRIORITY":"7","MESSAGE":[104,105,255]}"#;
+
+    #[test]
+    fn test_parse_line_remembers_cursor() {
+        let mut cursor = None;
+        let line = parse_journal_line(SSHD_ENTRY.to_string(), &mut cursor);
+        assert!(line.raw.contains("Accepted publickey"));
+        assert_eq!(
+            cursor.as_deref(),
+            Some("s=8c1f;i=4a2;b=2f1e;m=1a2b3c;t=5f9a8b7c6d5e4;x=9a8b")
+        );
+
+        // Entries without a cursor and non-JSON lines keep the last one
+        parse_journal_line(KERNEL_ENTRY.to_string(), &mut cursor);
+        let line = parse_journal_line("-- No entries --".to_string(), &mut cursor);
+        assert_eq!(line.raw, "-- No entries --");
+        assert!(cursor.is_some());
+    }
+
+    #[test]
+    fn test_parse_entry_uses_priority_and_realtime_timestamp() {
+        let line = parse(SSHD_ENTRY).unwrap();
+        assert_eq!(line.level, LogLevel::Info);
+        assert_eq!(
+            line.timestamp.unwrap().with_timezone(&Utc),
+            Utc.timestamp_micros(1_700_000_000_123_456).unwrap()
+        );
+        assert!(line.raw.ends_with(
+            "web-1 sshd[1234]: Accepted publickey for deploy from 10.0.0.5 port 52144 ssh2"
+        ));
+        assert!(!line.is_json);
+    }
+
+    #[test]
+    fn test_priority_overrides_text_heuristics() {
+        // The message says "warning" but the journal says err
+        let line = parse(KERNEL_ENTRY).unwrap();
+        assert_eq!(line.level, LogLevel::Error);
+        assert!(line.raw.contains("kernel: EXT4-fs"));
+    }
+
+    #[test]
+    fn test_binary_message_is_decoded_lossily() {
+        let line = parse(BINARY_ENTRY).unwrap();
+        assert_eq!(line.level, LogLevel::Debug);
+        assert!(line.raw.ends_with("hi\u{fffd}"));
+    }
+
+    #[test]
+    fn test_priority_level_mapping() {
+        assert_eq!(priority_level(0), LogLevel::Error);
+        assert_eq!(priority_level(3), LogLevel::Error);
+        assert_eq!(priority_level(4), LogLevel::Warn);
+        assert_eq!(priority_level(5), LogLevel::Info);
+        assert_eq!(priority_level(6), LogLevel::Info);
+        assert_eq!(priority_level(7), LogLevel::Debug);
+    }
+
+    #[test]
+    fn test_non_journal_json_is_rejected() {
+        assert!(parse("not json").is_none());
+        assert!(parse(r#"{"msg":"no MESSAGE field"}"#).is_none());
+    }
+
+    #[test]
+    fn test_validate_filter_value() {
+        assert!(validate_filter_value("unit", "nginx.service").is_ok());
+        assert!(validate_filter_value("since", "1 hour ago").is_ok());
+        assert!(validate_filter_value("unit", "--help").is_err());
+        assert!(validate_filter_value("unit", "").is_err());
+        // Relative times count back from now
+        assert!(validate_filter_value("since", "-1h").is_ok());
+        assert!(validate_filter_value("since", "-5min").is_ok());
+        assert!(validate_filter_value("since", "").is_err());
+    }
+}
@@ -1,13 +1,27 @@
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
-use super::{LogEvent, LogSource};
-use crate::app::LogLine;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::process::{
+    Backoff, ProcessExit, ReconnectPolicy, ResumePoint, StderrMode, report_exit, resume_time,
+    run_process,
+};
+use super::{LogEvent, LogSource, LogSourceType};
 use crate::config::{DEFAULT_CHANNEL_BUFFER, DEFAULT_TAIL_LINES};
 use async_trait::async_trait;
 
+/// Pods restart (and crash-loop backoff can take minutes), so keep trying
+const RECONNECT: ReconnectPolicy = ReconnectPolicy {
+    max_attempts: None,
+    initial_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(60),
+};
+
+/// Shown when kubectl can't be run
+const HINT: &str = "Is kubectl installed and configured?";
+
 /// Validate Kubernetes pod name to prevent option injection.
 pub fn validate_pod_name(name: &str) -> Result<(), String> {
     if name.is_empty() {
@@ -22,24 +36,311 @@ pub fn validate_pod_name(name: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Which cluster and namespace kubectl talks to; unset fields fall back to
+/// the kubeconfig's current context and its namespace
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scope {
+    /// kubeconfig context (`--context`)
+    pub context: Option<String>,
+    /// Namespace (`-n`)
+    pub namespace: Option<String>,
+}
+
+impl Scope {
+    /// kubectl arguments selecting this scope
+    pub fn args(&self) -> Vec<&str> {
+        let mut args = Vec::new();
+        if let Some(context) = &self.context {
+            args.extend(["--context", context.as_str()]);
+        }
+        if let Some(ns) = &self.namespace {
+            args.extend(["-n", ns.as_str()]);
+        }
+        args
+    }
+}
+
+/// Workload kinds accepted as `--k8s <kind>/<name>`
+const WORKLOAD_KINDS: &[&str] = &[
+    "deploy",
+    "deployment",
+    "sts",
+    "statefulset",
+    "ds",
+    "daemonset",
+    "rs",
+    "replicaset",
+    "job",
+];
+
+/// Split a `deploy/api` style argument into kind and name, if it names a workload
+pub fn parse_workload(arg: &str) -> Option<(&str, &str)> {
+    let (kind, name) = arg.split_once('/')?;
+    let kind_known = WORKLOAD_KINDS.contains(&kind.to_ascii_lowercase().as_str());
+    (kind_known && !name.is_empty()).then_some((kind, name))
+}
+
+/// Validate a label selector to prevent option injection.
+pub fn validate_selector(selector: &str) -> Result<(), String> {
+    if selector.trim().is_empty() {
+        return Err("Label selector cannot be empty".to_string());
+    }
+    if selector.starts_with('-') {
+        return Err("Invalid label selector: cannot start with '-'".to_string());
+    }
+    Ok(())
+}
+
+/// Label selector from a workload's `.spec.selector.matchLabels` (JSON)
+fn match_labels_selector(json: &str) -> Result<String, String> {
+    let labels: BTreeMap<String, String> = serde_json::from_str(json.trim())
+        .map_err(|_| "workload has no matchLabels selector".to_string())?;
+    if labels.is_empty() {
+        return Err("workload has no matchLabels selector".to_string());
+    }
+    Ok(labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+/// Look up the label selector of a workload (`deploy/api`) so its pods can
+/// be followed across rollouts. Blocks on `kubectl`.
+pub fn resolve_workload_selector(kind: &str, name: &str, scope: &Scope) -> Result<String, String> {
+    validate_pod_name(name)?;
+    let mut cmd = std::process::Command::new("kubectl");
+    cmd.arg("get")
+        .arg(format!("{}/{}", kind, name))
+        .arg("-o")
+        .arg("jsonpath={.spec.selector.matchLabels}")
+        .args(scope.args());
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Cannot run kubectl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "kubectl get {}/{} failed: {}",
+            kind,
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    match_labels_selector(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|e| format!("{}/{}: {}", kind, name, e))
+}
+
+/// Which containers of a pod to follow
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContainerSelection {
+    /// Only this container (`-c`)
+    pub container: Option<String>,
+    /// Init containers too (`--all-containers`)
+    pub all_containers: bool,
+    /// Also the previous instance of containers that restarted (`--previous`)
+    pub previous: bool,
+}
+
+impl ContainerSelection {
+    /// Sources for one container: its live stream and, if asked for and the
+    /// container has restarted, its previous instance
+    fn sources(
+        &self,
+        pod: &str,
+        scope: &Scope,
+        container: Option<&str>,
+        init: bool,
+        restarted: bool,
+    ) -> Vec<LogSourceType> {
+        let source = |previous| LogSourceType::K8s {
+            pod: pod.to_string(),
+            context: scope.context.clone(),
+            namespace: scope.namespace.clone(),
+            container: container.map(str::to_string),
+            init,
+            previous,
+        };
+        let mut sources = vec![source(false)];
+        if self.previous && restarted {
+            sources.push(source(true));
+        }
+        sources
+    }
+}
+
+/// `kubectl get pods` output: one line per pod with its name, phase, and
+/// `name=restarts,` lists of init and regular containers, tab-separated
+const POD_LISTING: &str = concat!(
+    r#"jsonpath={range .items[*]}{.metadata.name}{"\t"}{.status.phase}{"\t"}"#,
+    r#"{range .status.initContainerStatuses[*]}{.name}={.restartCount},{end}{"\t"}"#,
+    r#"{range .status.containerStatuses[*]}{.name}={.restartCount},{end}{"\n"}{end}"#,
+);
+
+/// Sources for the containers listed in `POD_LISTING` output. Init
+/// containers come first, as they run first.
+fn parse_pod_listing(
+    output: &str,
+    scope: &Scope,
+    selection: &ContainerSelection,
+    running_only: bool,
+) -> Vec<LogSourceType> {
+    let mut sources = Vec::new();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        let [pod, phase, init_containers, containers] = parts.as_slice() else {
+            continue;
+        };
+        if running_only && *phase != "Running" {
+            continue;
+        }
+        for (statuses, init) in [(init_containers, true), (containers, false)] {
+            if init && !selection.all_containers {
+                continue;
+            }
+            for (name, restarted) in restart_statuses(statuses) {
+                if selection.container.as_deref().is_some_and(|c| c != name) {
+                    continue;
+                }
+                sources.extend(selection.sources(pod, scope, Some(name), init, restarted));
+            }
+        }
+    }
+    sources
+}
+
+/// Container names in a `name=restarts,` list, with whether each restarted
+fn restart_statuses(statuses: &str) -> impl Iterator<Item = (&str, bool)> {
+    statuses.split(',').filter(|s| !s.is_empty()).map(|status| {
+        let (name, restarts) = status.split_once('=').unwrap_or((status, "0"));
+        (name, restarts.parse::<u32>().is_ok_and(|n| n > 0))
+    })
+}
+
+/// Whether a pod's container restarted, from `POD_LISTING` output for that
+/// pod. Without a name it is the first container, the one kubectl follows
+/// by default. A container not listed yet has not restarted.
+fn container_restarted(output: &str, container: Option<&str>) -> bool {
+    let Some(line) = output.lines().next() else {
+        return false;
+    };
+    let parts: Vec<&str> = line.split('\t').collect();
+    let [_, _, init_containers, containers] = parts.as_slice() else {
+        return false;
+    };
+    restart_statuses(containers)
+        .chain(restart_statuses(init_containers))
+        .find(|(name, _)| container.is_none_or(|c| c == *name))
+        .is_some_and(|(_, restarted)| restarted)
+}
+
+/// Run `kubectl get pods` with `POD_LISTING` output and extra `args`
+fn get_pods(args: &[&str], scope: &Scope) -> Result<String, String> {
+    let mut cmd = std::process::Command::new("kubectl");
+    cmd.args(["get", "pods", "-o", POD_LISTING])
+        .args(args)
+        .args(scope.args());
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Cannot run kubectl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "kubectl get pods {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// List pods and the sources for their selected containers
+fn list_pods(
+    args: &[&str],
+    scope: &Scope,
+    selection: &ContainerSelection,
+    running_only: bool,
+) -> Result<Vec<LogSourceType>, String> {
+    let output = get_pods(args, scope)?;
+    Ok(parse_pod_listing(&output, scope, selection, running_only))
+}
+
+/// List the running pods matching a label selector, one source per selected
+/// container. Blocks on `kubectl`.
+pub fn list_pod_set(
+    selector: &str,
+    scope: &Scope,
+    selection: &ContainerSelection,
+) -> Result<Vec<LogSourceType>, String> {
+    list_pods(&["-l", selector], scope, selection, true)
+}
+
+/// Sources for one named pod. `--all-containers` asks the cluster which
+/// containers there are and `--previous` which of them restarted; otherwise
+/// kubectl picks the default container unless `-c` names one. Blocks on
+/// `kubectl`.
+pub fn pod_sources(
+    pod: &str,
+    scope: &Scope,
+    selection: &ContainerSelection,
+) -> Result<Vec<LogSourceType>, String> {
+    let field = format!("metadata.name={}", pod);
+    if selection.all_containers {
+        let sources = list_pods(&["--field-selector", &field], scope, selection, false)?;
+        if sources.is_empty() {
+            return Err(format!("Pod '{}' has no containers to follow", pod));
+        }
+        return Ok(sources);
+    }
+    let container = selection.container.as_deref();
+    let restarted = selection.previous
+        && container_restarted(&get_pods(&["--field-selector", &field], scope)?, container);
+    Ok(selection.sources(pod, scope, container, false, restarted))
+}
+
 /// Kubernetes pod log source using kubectl
 pub struct K8sSource {
     /// Pod name (or name pattern)
     pod: String,
+    /// kubeconfig context (optional, defaults to the current one)
+    context: Option<String>,
     /// Namespace (optional, defaults to current context)
     namespace: Option<String>,
     /// Container name (optional, required for multi-container pods)
     container: Option<String>,
+    /// Init container: its stream ends once it has run to completion
+    init: bool,
+    /// Read the previous (crashed) instance's logs once instead of following
+    previous: bool,
 }
 
 impl K8sSource {
     pub fn new(pod: String, namespace: Option<String>, container: Option<String>) -> Self {
         Self {
             pod,
+            context: None,
             namespace,
             container,
+            init: false,
+            previous: false,
         }
     }
+
+    /// Talk to the cluster of a kubeconfig context other than the current one
+    pub fn in_context(mut self, context: Option<String>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Mark the container as an init container
+    pub fn init_container(mut self, init: bool) -> Self {
+        self.init = init;
+        self
+    }
+
+    /// Read the container's previous instance instead of the running one
+    pub fn previous_instance(mut self, previous: bool) -> Self {
+        self.previous = previous;
+        self
+    }
 }
 
 #[async_trait]
@@ -48,85 +349,86 @@ impl LogSource for K8sSource {
         let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_BUFFER);
 
         let pod = self.pod.clone();
+        let context = self.context.clone();
         let namespace = self.namespace.clone();
         let container = self.container.clone();
+        let (init, previous) = (self.init, self.previous);
 
         tokio::spawn(async move {
-            let mut cmd = Command::new("kubectl");
-            cmd.arg("logs")
-                .arg("-f")
-                .arg(format!("--tail={}", DEFAULT_TAIL_LINES));
+            let label = format!("kubectl logs for pod '{}'", pod);
+            let mut backoff = Backoff::new(RECONNECT);
+            let mut resume = ResumePoint::default();
 
-            if let Some(ns) = namespace {
-                cmd.arg("-n").arg(ns);
-            }
+            loop {
+                let mut cmd = Command::new("kubectl");
+                cmd.arg("logs").arg("--timestamps");
+                if previous {
+                    cmd.arg("--previous");
+                } else {
+                    cmd.arg("-f");
+                }
+                match resume.start() {
+                    Some(at) => cmd.arg(format!("--since-time={}", resume_time(at))),
+                    None => cmd.arg(format!("--tail={}", DEFAULT_TAIL_LINES)),
+                };
 
-            if let Some(c) = &container {
-                cmd.arg("-c").arg(c);
-            }
+                if let Some(context) = &context {
+                    cmd.arg("--context").arg(context);
+                }
 
-            // Add -- before pod name to prevent option injection
-            cmd.arg("--").arg(&pod);
-
-            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
-
-            let mut child = match cmd.spawn() {
-                Ok(child) => child,
-                Err(e) => {
-                    let _ = tx
-                        .send(LogEvent::Error(format!(
-                            "Failed to run kubectl for pod '{}': {}. Is kubectl installed and configured?",
-                            pod, e
-                        )))
-                        .await;
-                    return;
+                if let Some(ns) = &namespace {
+                    cmd.arg("-n").arg(ns);
                 }
-            };
-
-            let stdout = match child.stdout.take() {
-                Some(stdout) => stdout,
-                None => {
-                    let _ = tx
-                        .send(LogEvent::Error("Failed to get stdout".to_string()))
-                        .await;
-                    return;
+
+                if let Some(c) = &container {
+                    cmd.arg("-c").arg(c);
                 }
-            };
 
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
+                // Add -- before pod name to prevent option injection
+                cmd.arg("--").arg(&pod);
 
-            loop {
-                match lines.next_line().await {
-                    Ok(Some(line)) => {
-                        if tx.send(LogEvent::Line(LogLine::new(line))).await.is_err() {
-                            break;
-                        }
-                    }
-                    Ok(None) => {
-                        let _ = tx.send(LogEvent::EndOfStream).await;
-                        break;
-                    }
-                    Err(e) => {
-                        let _ = tx.send(LogEvent::Error(e.to_string())).await;
-                        break;
-                    }
+                let mut received = false;
+                let exit = run_process(
+                    cmd,
+                    StderrMode::Errors,
+                    |line| {
+                        received = true;
+                        resume.line(line)
+                    },
+                    &tx,
+                )
+                .await;
+
+                // A previous instance's logs are complete, and an init
+                // container that ran to completion won't write more
+                let completed = matches!(&exit, ProcessExit::Exited(status) if status.success());
+                if previous || (init && completed) {
+                    report_exit(&tx, &label, HINT, exit).await;
+                    break;
                 }
-            }
 
-            let _ = child.kill().await;
+                if received {
+                    backoff.reset();
+                }
+                if !backoff.retry(&tx, &label, HINT, exit).await {
+                    break;
+                }
+            }
         });
 
         rx
     }
 
     fn name(&self) -> String {
-        match (&self.namespace, &self.container) {
-            (Some(ns), Some(c)) => format!("k8s:{}/{}/{}", ns, self.pod, c),
-            (Some(ns), None) => format!("k8s:{}/{}", ns, self.pod),
-            (None, Some(c)) => format!("k8s:{}/{}", self.pod, c),
-            (None, None) => format!("k8s:{}", self.pod),
+        LogSourceType::K8s {
+            pod: self.pod.clone(),
+            context: self.context.clone(),
+            namespace: self.namespace.clone(),
+            container: self.container.clone(),
+            init: self.init,
+            previous: self.previous,
         }
+        .name()
     }
 }
 
@@ -170,4 +472,149 @@ mod tests {
         );
         assert_eq!(source.name(), "k8s:prod/my-pod/nginx");
     }
+
+    #[test]
+    fn test_parse_workload() {
+        assert_eq!(parse_workload("deploy/api"), Some(("deploy", "api")));
+        assert_eq!(
+            parse_workload("StatefulSet/db"),
+            Some(("StatefulSet", "db"))
+        );
+        assert_eq!(parse_workload("pod/api"), None);
+        assert_eq!(parse_workload("deploy/"), None);
+        assert_eq!(parse_workload("api"), None);
+    }
+
+    #[test]
+    fn test_scope_args() {
+        assert!(Scope::default().args().is_empty());
+        let scope = Scope {
+            context: Some("staging".to_string()),
+            namespace: Some("prod".to_string()),
+        };
+        assert_eq!(scope.args(), vec!["--context", "staging", "-n", "prod"]);
+    }
+
+    #[test]
+    fn test_validate_selector() {
+        assert!(validate_selector("app=api,tier!=cache").is_ok());
+        assert!(validate_selector("--all").is_err());
+        assert!(validate_selector(" ").is_err());
+    }
+
+    #[test]
+    fn test_match_labels_selector() {
+        assert_eq!(
+            match_labels_selector(r#"{"tier":"web","app":"api"}"#).unwrap(),
+            "app=api,tier=web"
+        );
+        assert!(match_labels_selector("").is_err());
+        assert!(match_labels_selector("{}").is_err());
+    }
+
+    fn prod() -> Scope {
+        Scope {
+            context: None,
+            namespace: Some("prod".to_string()),
+        }
+    }
+
+    fn container(pod: &str, container: &str, init: bool, previous: bool) -> LogSourceType {
+        LogSourceType::K8s {
+            pod: pod.to_string(),
+            context: None,
+            namespace: Some("prod".to_string()),
+            container: Some(container.to_string()),
+            init,
+            previous,
+        }
+    }
+
+    #[test]
+    fn test_parse_pod_listing_lists_running_containers() {
+        let output = "api-7d9f-x2k4q\tRunning\tmigrate=0,\tapi=0,envoy=0,\n\
+                      api-7d9f-zz81m\tPending\t\t\n\
+                      api-5c4b-qq1aa\tRunning\t\tapi=0,\n";
+        let selection = ContainerSelection::default();
+
+        assert_eq!(
+            parse_pod_listing(output, &prod(), &selection, true),
+            vec![
+                container("api-7d9f-x2k4q", "api", false, false),
+                container("api-7d9f-x2k4q", "envoy", false, false),
+                container("api-5c4b-qq1aa", "api", false, false),
+            ]
+        );
+
+        let only_envoy = ContainerSelection {
+            container: Some("envoy".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_pod_listing(output, &prod(), &only_envoy, true),
+            vec![container("api-7d9f-x2k4q", "envoy", false, false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_pod_listing_with_init_and_previous_containers() {
+        let output = "api-x2k4q\tRunning\tmigrate=0,\tapi=3,envoy=0,\n";
+        let selection = ContainerSelection {
+            container: None,
+            all_containers: true,
+            previous: true,
+        };
+
+        // Only the container that restarted has a previous instance
+        assert_eq!(
+            parse_pod_listing(output, &prod(), &selection, false),
+            vec![
+                container("api-x2k4q", "migrate", true, false),
+                container("api-x2k4q", "api", false, false),
+                container("api-x2k4q", "api", false, true),
+                container("api-x2k4q", "envoy", false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pod_sources_without_listing() {
+        let selection = ContainerSelection {
+            container: Some("api".to_string()),
+            all_containers: false,
+            previous: false,
+        };
+        assert_eq!(
+            pod_sources("api-x2k4q", &prod(), &selection).unwrap(),
+            vec![container("api-x2k4q", "api", false, false)]
+        );
+    }
+
+    #[test]
+    fn test_container_restarted() {
+        let output = "api-x2k4q\tRunning\tmigrate=1,\tapi=0,envoy=3,\n";
+        // kubectl's default is the first container
+        assert!(!container_restarted(output, None));
+        assert!(container_restarted(output, Some("envoy")));
+        assert!(container_restarted(output, Some("migrate")));
+        assert!(!container_restarted(output, Some("api")));
+        // Not scheduled yet, or gone
+        assert!(!container_restarted("api-x2k4q\tPending\t\t\n", None));
+        assert!(!container_restarted("", Some("api")));
+    }
+
+    #[test]
+    fn test_k8s_source_name_marks_init_and_previous() {
+        let source = K8sSource::new("my-pod".to_string(), None, Some("migrate".to_string()))
+            .init_container(true);
+        assert_eq!(source.name(), "k8s:my-pod/migrate (init)");
+
+        let source = K8sSource::new("my-pod".to_string(), None, Some("api".to_string()))
+            .previous_instance(true);
+        assert_eq!(source.name(), "k8s:my-pod/api (previous)");
+
+        let source = K8sSource::new("my-pod".to_string(), Some("prod".to_string()), None)
+            .in_context(Some("staging".to_string()));
+        assert_eq!(source.name(), "k8s@staging:prod/my-pod");
+    }
 }
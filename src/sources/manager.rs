@@ -7,7 +7,8 @@ use tokio::task::JoinHandle;
 /// Manages multiple log sources and merges their streams
 pub struct SourceManager {
     tx: mpsc::Sender<SourcedLogEvent>,
-    handles: Vec<JoinHandle<()>>,
+    /// Forwarding task for each source, tagged with its source id
+    handles: Vec<(usize, JoinHandle<()>)>,
 }
 
 impl SourceManager {
@@ -35,7 +36,19 @@ impl SourceManager {
             }
         });
 
-        self.handles.push(handle);
+        self.handles.push((source_id, handle));
+    }
+
+    /// Stop streaming from a source. Dropping its stream ends the source task.
+    pub fn remove_source(&mut self, source_id: usize) {
+        self.handles.retain(|(id, handle)| {
+            if *id == source_id {
+                handle.abort();
+                false
+            } else {
+                true
+            }
+        });
     }
 
     /// Get the number of active sources
@@ -47,7 +60,7 @@ impl SourceManager {
     /// Shutdown all source streams
     #[allow(dead_code)]
     pub fn shutdown(&self) {
-        for handle in &self.handles {
+        for (_, handle) in &self.handles {
             handle.abort();
         }
     }
@@ -56,7 +69,7 @@ impl SourceManager {
 impl Drop for SourceManager {
     fn drop(&mut self) {
         // Abort all spawned tasks to ensure child processes are cleaned up
-        for handle in &self.handles {
+        for (_, handle) in &self.handles {
             handle.abort();
         }
     }
@@ -1,68 +1,198 @@
 //! Log source abstraction and implementations.
 //!
 //! Provides a unified `LogSource` trait with implementations for:
-//! - Local files (via `tail -F`)
-//! - Docker containers (via `docker logs -f`)
+//! - Local files (native tailing with rotation detection, compressed archives)
+//! - Docker containers (via the Engine API socket, or `docker logs -f`)
 //! - Kubernetes pods (via `kubectl logs -f`)
 //! - Remote files via SSH (via `ssh ... tail -F`)
+//! - Standard input (piped data)
+//! - systemd journal (via `journalctl -o json -f`)
+//! - Arbitrary commands (`--cmd`)
 
+pub mod archive;
+pub mod command;
 pub mod docker;
+pub mod docker_api;
 pub mod file;
+pub mod journald;
 pub mod k8s;
 pub mod manager;
+pub mod process;
 pub mod ssh;
+pub mod stdin;
+pub mod tail;
+pub mod watch;
 
 use crate::app::LogLine;
+use crate::config::Config;
+use chrono::{DateTime, Local, Utc};
 use std::path::PathBuf;
 use tokio::sync::mpsc;
 
 /// Describes how a log source is configured
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LogSourceType {
     File {
         path: PathBuf,
+        /// Replay rotated siblings (`app.log.1`, `app.log.2.gz`, ...) before the live file
+        rotated: bool,
     },
     Docker {
         container: String,
     },
     K8s {
         pod: String,
+        /// kubeconfig context (None = the current one)
+        context: Option<String>,
         namespace: Option<String>,
         container: Option<String>,
+        /// An init container (ends once it completes)
+        init: bool,
+        /// The previous instance of a restarted container (read once)
+        previous: bool,
     },
     Ssh {
         host: String,
         path: String,
     },
+    Stdin,
+    Journald {
+        unit: Option<String>,
+        priority: Option<String>,
+        since: Option<String>,
+    },
+    Command {
+        argv: Vec<String>,
+    },
 }
 
 impl LogSourceType {
+    /// Whether a config glob (`docker:api-*`, `/var/log/*.log`) selects
+    /// this source, by name or, for files, by path
+    pub fn matches_glob(&self, pattern: &glob::Pattern) -> bool {
+        pattern.matches(&self.name())
+            || matches!(self, LogSourceType::File { path, .. } if pattern.matches_path(path))
+    }
+
     pub fn name(&self) -> String {
         match self {
-            LogSourceType::File { path } => path
-                .file_name()
-                .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| path.display().to_string()),
+            LogSourceType::File { path, rotated } => {
+                let name = path
+                    .file_name()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.display().to_string());
+                if *rotated { format!("{}*", name) } else { name }
+            }
             LogSourceType::Docker { container } => format!("docker:{}", container),
             LogSourceType::K8s {
                 pod,
+                context,
                 namespace,
                 container,
-            } => match (namespace, container) {
-                (Some(ns), Some(c)) => format!("k8s:{}/{}/{}", ns, pod, c),
-                (Some(ns), None) => format!("k8s:{}/{}", ns, pod),
-                (None, Some(c)) => format!("k8s:{}/{}", pod, c),
-                (None, None) => format!("k8s:{}", pod),
-            },
+                init,
+                previous,
+            } => {
+                let path = match (namespace, container) {
+                    (Some(ns), Some(c)) => format!("{}/{}/{}", ns, pod, c),
+                    (Some(ns), None) => format!("{}/{}", ns, pod),
+                    (None, Some(c)) => format!("{}/{}", pod, c),
+                    (None, None) => pod.clone(),
+                };
+                let name = match context {
+                    Some(context) => format!("k8s@{}:{}", context, path),
+                    None => format!("k8s:{}", path),
+                };
+                match (init, previous) {
+                    (false, false) => name,
+                    (true, false) => format!("{} (init)", name),
+                    (false, true) => format!("{} (previous)", name),
+                    (true, true) => format!("{} (init, previous)", name),
+                }
+            }
             LogSourceType::Ssh { host, path } => format!("ssh:{}:{}", host, path),
+            LogSourceType::Stdin => "stdin".to_string(),
+            LogSourceType::Journald { unit, .. } => match unit {
+                Some(unit) => format!("journal:{}", unit),
+                None => "journal".to_string(),
+            },
+            LogSourceType::Command { argv } => format!("cmd:{}", argv.join(" ")),
+        }
+    }
+}
+
+/// Build a source to restart in a slot whose newest line was written at
+/// `after`. A Docker container resumes after that line; anything else
+/// starts over as usual.
+pub fn create_resumed_source(
+    source_type: &LogSourceType,
+    config: &Config,
+    after: Option<DateTime<Local>>,
+) -> Box<dyn LogSource> {
+    if let LogSourceType::Docker { container } = source_type {
+        if let Some(host) = docker_api::DockerHost::from_env() {
+            return Box::new(
+                docker_api::DockerApiSource::new(container.clone(), host)
+                    .resume_after(after.map(|at| at.with_timezone(&Utc))),
+            );
         }
     }
+    create_source(source_type, config)
+}
+
+/// Build the streaming implementation for a source description
+pub fn create_source(source_type: &LogSourceType, config: &Config) -> Box<dyn LogSource> {
+    match source_type.clone() {
+        LogSourceType::File {
+            path,
+            rotated: false,
+        } => Box::new(file::FileSource::new(path)),
+        LogSourceType::File {
+            path,
+            rotated: true,
+        } => Box::new(file::FileSource::with_rotation_set(path)),
+        LogSourceType::Docker { container } => match docker_api::DockerHost::from_env() {
+            Some(host) => Box::new(docker_api::DockerApiSource::new(container, host)),
+            // No socket to talk to (or an ssh:// host): go through the CLI
+            None => Box::new(docker::DockerSource::new(container)),
+        },
+        LogSourceType::K8s {
+            pod,
+            context,
+            namespace,
+            container,
+            init,
+            previous,
+        } => Box::new(
+            k8s::K8sSource::new(pod, namespace, container)
+                .in_context(context)
+                .init_container(init)
+                .previous_instance(previous),
+        ),
+        LogSourceType::Ssh { host, path } => Box::new(ssh::SshSource::with_host_key_checking(
+            host,
+            path,
+            config.ssh_host_key_checking.clone(),
+        )),
+        LogSourceType::Stdin => Box::new(stdin::StdinSource),
+        LogSourceType::Journald {
+            unit,
+            priority,
+            since,
+        } => Box::new(journald::JournaldSource::new(unit, priority, since)),
+        LogSourceType::Command { argv } => Box::new(command::CommandSource::new(argv)),
+    }
 }
 
 /// Events emitted by log sources
 pub enum LogEvent {
     Line(LogLine),
     Error(String),
+    /// Informational status change (e.g. file rotated or truncated)
+    Notice(String),
+    /// The connection dropped; the source is retrying (attempts count from 1)
+    Reconnecting {
+        attempt: u32,
+    },
     EndOfStream,
 }
 
@@ -0,0 +1,486 @@
+//! Shared reader for sources backed by a child process.
+//!
+//! Docker, Kubernetes, SSH, journald and arbitrary commands all come down to
+//! "spawn a process, turn its output into log lines, report how it ended".
+//! `run_process` does the reading; `report_exit` turns the outcome into
+//! events so each source only has to build its `Command`. Sources that should
+//! survive a dropped connection loop around `run_process` with a `Backoff`.
+
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use super::LogEvent;
+use super::tail::decode_line;
+use crate::app::LogLine;
+
+/// What to do with the process's stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StderrMode {
+    /// Stderr carries log output too (e.g. a container's stderr)
+    Lines,
+    /// Stderr carries tool diagnostics, reported as errors
+    Errors,
+}
+
+/// How a process run ended
+#[derive(Debug)]
+pub enum ProcessExit {
+    /// The process exited on its own
+    Exited(ExitStatus),
+    /// The process could not be started
+    SpawnFailed(std::io::Error),
+    /// The receiver went away; the process was killed
+    Cancelled,
+}
+
+/// Read the next line of a stream (invalid UTF-8 is replaced, not fatal);
+/// None at its end. A stream that is already closed never yields. Safe to
+/// cancel: a partial line stays in `buf` for the next call.
+async fn next_line<R: AsyncRead + Unpin>(
+    reader: Option<&mut BufReader<R>>,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<Option<String>> {
+    let Some(reader) = reader else {
+        return std::future::pending().await;
+    };
+    reader.read_until(b'\n', buf).await?;
+    if buf.is_empty() {
+        return Ok(None);
+    }
+    let line = decode_line(buf.strip_suffix(b"\n").unwrap_or(buf));
+    buf.clear();
+    Ok(Some(line))
+}
+
+/// Spawn `cmd` and forward its output until it exits.
+/// `parse` turns each output line into a `LogLine`, or drops it.
+pub async fn run_process(
+    mut cmd: Command,
+    stderr_mode: StderrMode,
+    mut parse: impl FnMut(String) -> Option<LogLine> + Send,
+    tx: &mpsc::Sender<LogEvent>,
+) -> ProcessExit {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return ProcessExit::SpawnFailed(e),
+    };
+
+    let mut stdout = child.stdout.take().map(BufReader::new);
+    let mut stderr = child.stderr.take().map(BufReader::new);
+    let (mut stdout_buf, mut stderr_buf) = (Vec::new(), Vec::new());
+    let mut open = true;
+    while open && (stdout.is_some() || stderr.is_some()) {
+        // Stop early if the receiver goes away while the process is quiet
+        let (read, from_stderr) = tokio::select! {
+            read = next_line(stdout.as_mut(), &mut stdout_buf) => (read, false),
+            read = next_line(stderr.as_mut(), &mut stderr_buf) => (read, true),
+            _ = tx.closed() => {
+                open = false;
+                break;
+            }
+        };
+        let event = match read {
+            Ok(Some(line)) if from_stderr && stderr_mode == StderrMode::Errors => {
+                LogEvent::Error(line)
+            }
+            Ok(Some(line)) => match parse(line) {
+                Some(line) => LogEvent::Line(line),
+                None => continue,
+            },
+            Ok(None) | Err(_) => {
+                if from_stderr {
+                    stderr = None;
+                } else {
+                    stdout = None;
+                }
+                match read {
+                    Err(e) => LogEvent::Error(format!("read error: {}", e)),
+                    _ => continue,
+                }
+            }
+        };
+        open = tx.send(event).await.is_ok();
+    }
+
+    if !open {
+        let _ = child.kill().await;
+        return ProcessExit::Cancelled;
+    }
+
+    match child.wait().await {
+        Ok(status) => ProcessExit::Exited(status),
+        Err(e) => ProcessExit::SpawnFailed(e),
+    }
+}
+
+/// Describe an unsuccessful exit ("docker logs exited with status 1")
+fn describe_exit(label: &str, status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("{} exited with status {}", label, code),
+        // Killed by a signal (unix)
+        None => format!("{} was terminated ({})", label, status),
+    }
+}
+
+/// Report how a process ended, followed by `EndOfStream`.
+/// `label` names the command; `hint` is shown when it could not be started.
+pub async fn report_exit(tx: &mpsc::Sender<LogEvent>, label: &str, hint: &str, exit: ProcessExit) {
+    let message = match exit {
+        ProcessExit::Cancelled => return,
+        ProcessExit::Exited(status) if status.success() => None,
+        ProcessExit::Exited(status) => Some(describe_exit(label, status)),
+        ProcessExit::SpawnFailed(e) => Some(format!("Failed to run {}: {}. {}", label, e, hint)),
+    };
+    if let Some(message) = message {
+        let _ = tx.send(LogEvent::Error(message)).await;
+    }
+    let _ = tx.send(LogEvent::EndOfStream).await;
+}
+
+/// Format a resume point for `docker logs --since` / `kubectl logs --since-time`
+pub fn resume_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// Split off the RFC 3339 timestamp Docker and Kubernetes put before each
+/// line when asked for timestamps
+pub fn split_timestamp(line: &str) -> (Option<DateTime<Utc>>, &str) {
+    let (stamp, text) = line.split_once(' ').unwrap_or((line, ""));
+    match DateTime::parse_from_rfc3339(stamp) {
+        Ok(at) => (Some(at.with_timezone(&Utc)), text),
+        Err(_) => (None, line),
+    }
+}
+
+/// Where a `--timestamps` log stream picks up after a reconnect: the
+/// daemon's timestamp of the last line delivered, not the local clock, so
+/// lines still in flight when the connection dropped and clock skew against
+/// a remote daemon neither lose nor repeat lines
+#[derive(Debug, Default)]
+pub struct ResumePoint {
+    /// Timestamp of the newest line delivered
+    last: Option<DateTime<Utc>>,
+    /// Lines up to here were delivered before the current run
+    seen: Option<DateTime<Utc>>,
+}
+
+impl ResumePoint {
+    /// Start a run: the time to resume from, if any line was delivered
+    pub fn start(&mut self) -> Option<DateTime<Utc>> {
+        self.seen = self.last;
+        self.last
+    }
+
+    /// Turn a timestamped line into a `LogLine` timed by the daemon. Lines
+    /// delivered by an earlier run are dropped: resume points are inclusive,
+    /// and `kubectl --since-time` only keeps whole seconds.
+    pub fn line(&mut self, raw: String) -> Option<LogLine> {
+        let (at, text) = split_timestamp(&raw);
+        let Some(at) = at else {
+            return Some(LogLine::new(raw));
+        };
+        if self.seen.is_some_and(|seen| at <= seen) {
+            return None;
+        }
+        self.last = Some(self.last.map_or(at, |last| last.max(at)));
+        Some(LogLine::new(text.to_string()).with_timestamp(at.with_timezone(&Local)))
+    }
+}
+
+/// How a source is restarted after its process exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Give up after this many attempts in a row (None = keep trying)
+    pub max_attempts: Option<u32>,
+    /// Delay before the first attempt; doubled on each further attempt
+    pub initial_delay: Duration,
+    /// Upper bound for the delay
+    pub max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Delay before the given attempt (1-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Reconnect attempts for one source
+pub struct Backoff {
+    policy: ReconnectPolicy,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        Self { policy, attempt: 0 }
+    }
+
+    /// Forget earlier failures (call once a run delivered data)
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// Decide what happens after a process run. Returns true once it is time
+    /// to start the process again; otherwise the exit has been reported
+    /// (followed by `EndOfStream`) or the receiver is gone.
+    pub async fn retry(
+        &mut self,
+        tx: &mpsc::Sender<LogEvent>,
+        label: &str,
+        hint: &str,
+        exit: ProcessExit,
+    ) -> bool {
+        match exit {
+            ProcessExit::Exited(status) => {
+                let error = (!status.success()).then(|| describe_exit(label, status));
+                self.retry_after(tx, error).await
+            }
+            // Nothing to reconnect to
+            other => {
+                report_exit(tx, label, hint, other).await;
+                false
+            }
+        }
+    }
+
+    /// `retry` for sources that don't run a process: `error` says why the
+    /// stream ended (None when it ended cleanly). Once out of attempts the
+    /// error is reported, followed by `EndOfStream`.
+    pub async fn retry_after(
+        &mut self,
+        tx: &mpsc::Sender<LogEvent>,
+        error: Option<String>,
+    ) -> bool {
+        if self
+            .policy
+            .max_attempts
+            .is_some_and(|max| self.attempt >= max)
+        {
+            if let Some(message) = error {
+                let _ = tx.send(LogEvent::Error(message)).await;
+            }
+            let _ = tx.send(LogEvent::EndOfStream).await;
+            return false;
+        }
+
+        self.attempt += 1;
+        if let Some(message) = error {
+            if tx.send(LogEvent::Error(message)).await.is_err() {
+                return false;
+            }
+        }
+        let event = LogEvent::Reconnecting {
+            attempt: self.attempt,
+        };
+        if tx.send(event).await.is_err() {
+            return false;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(self.policy.delay(self.attempt)) => true,
+            _ = tx.closed() => false,
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    async fn collect(cmd: Command, mode: StderrMode) -> (Vec<String>, Vec<String>, ProcessExit) {
+        let (tx, mut rx) = mpsc::channel(100);
+        let exit = run_process(cmd, mode, |line| Some(LogLine::new(line)), &tx).await;
+        drop(tx);
+
+        let (mut lines, mut errors) = (Vec::new(), Vec::new());
+        while let Some(event) = rx.recv().await {
+            match event {
+                LogEvent::Line(line) => lines.push(line.raw),
+                LogEvent::Error(e) => errors.push(e),
+                _ => {}
+            }
+        }
+        (lines, errors, exit)
+    }
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    #[tokio::test]
+    async fn test_reads_stdout_and_stderr_as_lines() {
+        let (mut lines, errors, exit) = collect(
+            sh("echo out; echo err >&2; printf 'no newline'"),
+            StderrMode::Lines,
+        )
+        .await;
+        lines.sort();
+        assert_eq!(lines, vec!["err", "no newline", "out"]);
+        assert!(errors.is_empty());
+        assert!(matches!(exit, ProcessExit::Exited(s) if s.success()));
+    }
+
+    #[tokio::test]
+    async fn test_stderr_as_errors_and_exit_status() {
+        let (lines, errors, exit) =
+            collect(sh("echo out; echo oops >&2; exit 3"), StderrMode::Errors).await;
+        assert_eq!(lines, vec!["out"]);
+        assert_eq!(errors, vec!["oops"]);
+        assert!(matches!(exit, ProcessExit::Exited(s) if s.code() == Some(3)));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_utf8_does_not_end_stream() {
+        let (lines, _, _) = collect(sh("printf 'a\\377b\\nnext\\n'"), StderrMode::Lines).await;
+        assert_eq!(lines, vec!["a\u{fffd}b", "next"]);
+    }
+
+    #[tokio::test]
+    async fn test_stderr_lines_are_parsed_too() {
+        let (tx, mut rx) = mpsc::channel(100);
+        let mut resume = ResumePoint::default();
+        run_process(
+            sh("echo '2024-05-01T10:00:00.5Z boom' >&2"),
+            StderrMode::Lines,
+            |line| resume.line(line),
+            &tx,
+        )
+        .await;
+        drop(tx);
+        match rx.recv().await {
+            Some(LogEvent::Line(line)) => {
+                assert_eq!(line.raw, "boom");
+                assert!(line.timestamp.is_some());
+            }
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn test_resume_point_follows_daemon_timestamps() {
+        let mut resume = ResumePoint::default();
+        assert_eq!(resume.start(), None);
+        let raws = |resume: &mut ResumePoint, lines: &[&str]| -> Vec<String> {
+            lines
+                .iter()
+                .filter_map(|line| resume.line(line.to_string()))
+                .map(|line| line.raw)
+                .collect()
+        };
+        assert_eq!(
+            raws(
+                &mut resume,
+                &[
+                    "2024-05-01T10:00:01.2Z first",
+                    "2024-05-01T10:00:01.7Z second",
+                ]
+            ),
+            vec!["first", "second"]
+        );
+
+        // kubectl resumes from the whole second, so the second is replayed
+        let at = resume.start().unwrap();
+        assert_eq!(resume_time(at), "2024-05-01T10:00:01.700000000Z");
+        assert_eq!(
+            raws(
+                &mut resume,
+                &[
+                    "2024-05-01T10:00:01.2Z first",
+                    "2024-05-01T10:00:01.7Z second",
+                    "2024-05-01T10:00:02Z third",
+                    "not timestamped",
+                ]
+            ),
+            vec!["third", "not timestamped"]
+        );
+        assert_eq!(
+            resume_time(resume.start().unwrap()),
+            "2024-05-01T10:00:02.000000000Z"
+        );
+    }
+
+    #[test]
+    fn test_reconnect_delay_doubles_up_to_max() {
+        let policy = ReconnectPolicy {
+            max_attempts: None,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+        let delays: Vec<u64> = (1..=6).map(|a| policy.delay(a).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+        assert_eq!(policy.delay(100), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_backoff_retries_then_gives_up() {
+        let mut backoff = Backoff::new(ReconnectPolicy {
+            max_attempts: Some(2),
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        });
+        let (tx, mut rx) = mpsc::channel(100);
+
+        let mut runs = 0;
+        loop {
+            runs += 1;
+            let exit = run_process(
+                sh("exit 1"),
+                StderrMode::Lines,
+                |l| Some(LogLine::new(l)),
+                &tx,
+            )
+            .await;
+            if !backoff.retry(&tx, "sh", "", exit).await {
+                break;
+            }
+        }
+        drop(tx);
+        assert_eq!(runs, 3);
+
+        let mut attempts = Vec::new();
+        let mut ended = false;
+        while let Some(event) = rx.recv().await {
+            match event {
+                LogEvent::Reconnecting { attempt } => attempts.push(attempt),
+                LogEvent::EndOfStream => ended = true,
+                _ => {}
+            }
+        }
+        assert_eq!(attempts, vec![1, 2]);
+        assert!(ended);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_failure_is_reported() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let exit = run_process(
+            Command::new("bark-no-such-binary"),
+            StderrMode::Lines,
+            |line| Some(LogLine::new(line)),
+            &tx,
+        )
+        .await;
+        report_exit(&tx, "bark-no-such-binary", "Is it installed?", exit).await;
+
+        match rx.recv().await {
+            Some(LogEvent::Error(msg)) => assert!(msg.contains("Is it installed?")),
+            _ => panic!("expected an error event"),
+        }
+        assert!(matches!(rx.recv().await, Some(LogEvent::EndOfStream)));
+    }
+}
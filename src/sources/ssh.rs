@@ -1,13 +1,22 @@
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
+use std::time::Duration;
+
+use super::process::{Backoff, ReconnectPolicy, StderrMode, run_process};
 use super::{LogEvent, LogSource};
 use crate::app::LogLine;
 use crate::config::{DEFAULT_CHANNEL_BUFFER, DEFAULT_TAIL_LINES};
 use async_trait::async_trait;
 
+/// Ride out network blips, but stop eventually: a host that never comes back
+/// (or a rejected key) would otherwise retry forever
+const RECONNECT: ReconnectPolicy = ReconnectPolicy {
+    max_attempts: Some(10),
+    initial_delay: Duration::from_secs(2),
+    max_delay: Duration::from_secs(60),
+};
+
 /// Validate SSH hostname to prevent command injection.
 /// Rejects hostnames starting with '-' (option injection) and
 /// hostnames with shell metacharacters.
@@ -87,68 +96,52 @@ impl LogSource for SshSource {
         let host_key_checking = self.host_key_checking.clone();
 
         tokio::spawn(async move {
-            // Use ssh to run tail -F on the remote host
-            let mut cmd = Command::new("ssh");
-            cmd.arg("-o")
-                .arg("BatchMode=yes") // Disable password prompts
-                .arg("-o")
-                .arg(format!("StrictHostKeyChecking={}", host_key_checking))
-                .arg("--") // Prevent option injection from hostname
-                .arg(&host)
-                .arg("tail")
-                .arg("-F")
-                .arg("-n")
-                .arg(DEFAULT_TAIL_LINES)
-                .arg("--") // Prevent option injection from path
-                .arg(&path)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-
-            let mut child = match cmd.spawn() {
-                Ok(child) => child,
-                Err(e) => {
-                    let _ = tx
-                        .send(LogEvent::Error(format!(
-                            "Failed to connect to '{}' for '{}': {}. Check SSH key authentication.",
-                            host, path, e
-                        )))
-                        .await;
-                    return;
-                }
-            };
-
-            let stdout = match child.stdout.take() {
-                Some(stdout) => stdout,
-                None => {
-                    let _ = tx
-                        .send(LogEvent::Error("Failed to get stdout".to_string()))
-                        .await;
-                    return;
-                }
-            };
-
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
+            let label = format!("ssh to '{}' for '{}'", host, path);
+            let mut backoff = Backoff::new(RECONNECT);
+            // tail has no resume point; once lines were shown, a reconnect
+            // only follows new data so nothing is repeated. Lines written
+            // while the connection was down are skipped.
+            let mut resumed = false;
 
             loop {
-                match lines.next_line().await {
-                    Ok(Some(line)) => {
-                        if tx.send(LogEvent::Line(LogLine::new(line))).await.is_err() {
-                            break;
-                        }
-                    }
-                    Ok(None) => {
-                        let _ = tx.send(LogEvent::EndOfStream).await;
-                        break;
-                    }
-                    Err(e) => {
-                        let _ = tx.send(LogEvent::Error(e.to_string())).await;
-                        break;
-                    }
+                // Use ssh to run tail -F on the remote host
+                let mut cmd = Command::new("ssh");
+                cmd.arg("-o")
+                    .arg("BatchMode=yes") // Disable password prompts
+                    .arg("-o")
+                    .arg(format!("StrictHostKeyChecking={}", host_key_checking))
+                    .arg("--") // Prevent option injection from hostname
+                    .arg(&host)
+                    .arg("tail")
+                    .arg("-F")
+                    .arg("-n")
+                    .arg(if resumed { "0" } else { DEFAULT_TAIL_LINES })
+                    .arg("--") // Prevent option injection from path
+                    .arg(&path);
+
+                let mut received = false;
+                let exit = run_process(
+                    cmd,
+                    StderrMode::Errors,
+                    |line| {
+                        received = true;
+                        Some(LogLine::new(line))
+                    },
+                    &tx,
+                )
+                .await;
+
+                if received {
+                    resumed = true;
+                    backoff.reset();
+                }
+                if !backoff
+                    .retry(&tx, &label, "Check SSH key authentication.", exit)
+                    .await
+                {
+                    break;
                 }
             }
-
-            let _ = child.kill().await;
         });
 
         rx
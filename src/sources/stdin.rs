@@ -0,0 +1,57 @@
+use std::io::{BufRead, IsTerminal};
+use tokio::sync::mpsc;
+
+use super::tail::decode_line;
+use super::{LogEvent, LogSource};
+use crate::app::LogLine;
+use crate::config::DEFAULT_CHANNEL_BUFFER;
+
+/// A log source that reads lines piped into standard input
+/// (`kubectl logs -f ... | bark -`). Keyboard input comes from the
+/// controlling terminal instead, so both work at the same time.
+pub struct StdinSource;
+
+/// Whether standard input is a pipe or file rather than a terminal
+pub fn stdin_is_piped() -> bool {
+    !std::io::stdin().is_terminal()
+}
+
+#[async_trait::async_trait]
+impl LogSource for StdinSource {
+    async fn stream(&self) -> mpsc::Receiver<LogEvent> {
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_BUFFER);
+
+        // A plain thread rather than the blocking pool: a read on stdin
+        // cannot be cancelled and must not hold up runtime shutdown
+        std::thread::spawn(move || {
+            let mut stdin = std::io::stdin().lock();
+            let mut buf = Vec::new();
+            loop {
+                buf.clear();
+                match stdin.read_until(b'\n', &mut buf) {
+                    Ok(0) => {
+                        let _ = tx.blocking_send(LogEvent::EndOfStream);
+                        return;
+                    }
+                    Ok(_) => {
+                        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+                        let event = LogEvent::Line(LogLine::new(decode_line(line)));
+                        if tx.blocking_send(event).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.blocking_send(LogEvent::Error(e.to_string()));
+                        return;
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+
+    fn name(&self) -> String {
+        "stdin".to_string()
+    }
+}
@@ -0,0 +1,412 @@
+//! Native file tailing that follows a path across rotation and truncation.
+//!
+//! `FileTailer` is a small polling state machine: each call to `poll()` reads
+//! whatever was appended since the last call, then checks whether the path
+//! still refers to the same file. Rename-based rotation (the file at the path
+//! was replaced) and copy-truncate rotation (the file shrank) are both detected
+//! and the tailer reopens or rewinds accordingly.
+
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+/// Maximum number of bytes read from the file in a single poll
+const MAX_READ_PER_POLL: u64 = 4 * 1024 * 1024;
+
+/// Block size used when scanning backwards for the initial tail lines
+const TAIL_SCAN_BLOCK: u64 = 64 * 1024;
+
+/// Events produced by the tailer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TailEvent {
+    /// A complete line (without the trailing newline)
+    Line(String),
+    /// The file shrank and reading restarted from the beginning
+    Truncated,
+    /// The path now refers to a different file, which was reopened
+    Rotated,
+    /// The file is missing or unreadable; waiting for it to reappear
+    Missing(String),
+    /// The file appeared again after being missing
+    Reopened,
+}
+
+/// Identity of an open file, used to detect rename-based rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileIdentity {
+    dev: u64,
+    ino: u64,
+}
+
+#[cfg(unix)]
+fn file_identity(meta: &Metadata) -> Option<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+    Some(FileIdentity {
+        dev: meta.dev(),
+        ino: meta.ino(),
+    })
+}
+
+#[cfg(not(unix))]
+fn file_identity(_meta: &Metadata) -> Option<FileIdentity> {
+    // No stable inode on this platform; only truncation is detected
+    None
+}
+
+/// Follows a file by path, similar to `tail -F`
+pub struct FileTailer {
+    path: PathBuf,
+    file: Option<File>,
+    identity: Option<FileIdentity>,
+    /// Byte offset of the next read in the open file
+    position: u64,
+    /// Bytes of an incomplete trailing line, kept until its newline arrives
+    partial: Vec<u8>,
+    /// Whether the missing state has already been reported
+    missing_reported: bool,
+    /// Whether the file has been open at least once
+    opened_once: bool,
+}
+
+impl FileTailer {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: None,
+            identity: None,
+            position: 0,
+            partial: Vec::new(),
+            missing_reported: false,
+            opened_once: false,
+        }
+    }
+
+    /// Open the file positioned so the next poll returns its last `lines` lines.
+    /// A missing file is not an error; it will be picked up once it exists.
+    pub fn open_at_tail(&mut self, lines: usize) -> Vec<TailEvent> {
+        match File::open(&self.path) {
+            Ok(mut file) => {
+                let start = match tail_start_offset(&mut file, lines) {
+                    Ok(offset) => offset,
+                    Err(e) => return vec![self.mark_missing(e)],
+                };
+                self.attach(file, start);
+                Vec::new()
+            }
+            Err(e) => vec![self.mark_missing(e)],
+        }
+    }
+
+    /// Open the file positioned at its first line
+    pub fn open_from_start(&mut self) -> Vec<TailEvent> {
+        match File::open(&self.path) {
+            Ok(file) => {
+                self.attach(file, 0);
+                Vec::new()
+            }
+            Err(e) => vec![self.mark_missing(e)],
+        }
+    }
+
+    /// Read newly appended data and check the path for rotation or truncation
+    pub fn poll(&mut self) -> Vec<TailEvent> {
+        let mut events = Vec::new();
+
+        if self.file.is_none() {
+            match File::open(&self.path) {
+                Ok(file) => {
+                    let was_opened = self.opened_once;
+                    self.attach(file, 0);
+                    if was_opened {
+                        events.push(TailEvent::Reopened);
+                    }
+                }
+                Err(e) => {
+                    if !self.missing_reported {
+                        events.push(self.mark_missing(e));
+                    }
+                    return events;
+                }
+            }
+        }
+
+        // Drain whatever the current handle has before looking at the path,
+        // so lines written just before a rotation are not lost
+        if let Err(e) = self.read_available(&mut events) {
+            events.push(self.mark_missing(e));
+            return events;
+        }
+
+        match fs::metadata(&self.path) {
+            Ok(meta) => {
+                let identity = file_identity(&meta);
+                if identity.is_some() && identity != self.identity {
+                    self.flush_partial(&mut events);
+                    match File::open(&self.path) {
+                        Ok(file) => {
+                            self.attach(file, 0);
+                            events.push(TailEvent::Rotated);
+                            if let Err(e) = self.read_available(&mut events) {
+                                events.push(self.mark_missing(e));
+                            }
+                        }
+                        Err(e) => events.push(self.mark_missing(e)),
+                    }
+                } else if meta.len() < self.position {
+                    self.partial.clear();
+                    self.position = 0;
+                    if let Some(file) = self.file.as_mut() {
+                        if let Err(e) = file.seek(SeekFrom::Start(0)) {
+                            events.push(self.mark_missing(e));
+                            return events;
+                        }
+                    }
+                    events.push(TailEvent::Truncated);
+                    if let Err(e) = self.read_available(&mut events) {
+                        events.push(self.mark_missing(e));
+                    }
+                }
+            }
+            Err(e) => {
+                // The path went away (deleted or renamed without replacement)
+                self.flush_partial(&mut events);
+                events.push(self.mark_missing(e));
+            }
+        }
+
+        events
+    }
+
+    /// Whether the file holds more data than has been read so far
+    pub fn has_pending_data(&self) -> bool {
+        match (&self.file, fs::metadata(&self.path)) {
+            (Some(_), Ok(meta)) => meta.len() > self.position,
+            _ => false,
+        }
+    }
+
+    fn attach(&mut self, mut file: File, start: u64) {
+        let start = file.seek(SeekFrom::Start(start)).unwrap_or(0);
+        self.identity = file.metadata().ok().and_then(|m| file_identity(&m));
+        self.file = Some(file);
+        self.position = start;
+        self.partial.clear();
+        self.missing_reported = false;
+        self.opened_once = true;
+    }
+
+    fn mark_missing(&mut self, err: io::Error) -> TailEvent {
+        self.file = None;
+        self.identity = None;
+        self.partial.clear();
+        self.missing_reported = true;
+        TailEvent::Missing(format!("{}: {}", self.path.display(), err))
+    }
+
+    fn read_available(&mut self, events: &mut Vec<TailEvent>) -> io::Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+
+        let mut buf = Vec::new();
+        let read = file.take(MAX_READ_PER_POLL).read_to_end(&mut buf)?;
+        self.position += read as u64;
+
+        let mut start = 0;
+        for (i, &byte) in buf.iter().enumerate() {
+            if byte == b'\n' {
+                let line = if self.partial.is_empty() {
+                    decode_line(&buf[start..i])
+                } else {
+                    self.partial.extend_from_slice(&buf[start..i]);
+                    let line = decode_line(&self.partial);
+                    self.partial.clear();
+                    line
+                };
+                events.push(TailEvent::Line(line));
+                start = i + 1;
+            }
+        }
+        self.partial.extend_from_slice(&buf[start..]);
+
+        Ok(())
+    }
+
+    /// Emit an incomplete trailing line (used when the file is going away)
+    fn flush_partial(&mut self, events: &mut Vec<TailEvent>) {
+        if !self.partial.is_empty() {
+            events.push(TailEvent::Line(decode_line(&self.partial)));
+            self.partial.clear();
+        }
+    }
+}
+
+/// Decode a line as UTF-8, replacing invalid sequences and stripping a trailing CR
+pub fn decode_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Find the byte offset where the last `lines` lines of a file begin
+fn tail_start_offset(file: &mut File, lines: usize) -> io::Result<u64> {
+    let len = file.metadata()?.len();
+    if lines == 0 {
+        return Ok(len);
+    }
+
+    let mut end = len;
+    let mut newlines = 0;
+    let mut buf = vec![0u8; TAIL_SCAN_BLOCK as usize];
+    let mut skip_trailing = true;
+
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_SCAN_BLOCK);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+
+        for i in (0..chunk.len()).rev() {
+            if chunk[i] != b'\n' {
+                skip_trailing = false;
+                continue;
+            }
+            // The newline terminating the final line does not start a new one
+            if skip_trailing {
+                skip_trailing = false;
+                continue;
+            }
+            newlines += 1;
+            if newlines == lines {
+                return Ok(start + i as u64 + 1);
+            }
+        }
+        end = start;
+    }
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bark_tail_{}_{}", std::process::id(), name))
+    }
+
+    fn append(path: &PathBuf, text: &str) {
+        let mut f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        f.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn lines(events: &[TailEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                TailEvent::Line(l) => Some(l.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_open_at_tail_returns_last_lines() {
+        let path = temp_path("last_lines");
+        let _ = fs::remove_file(&path);
+        append(&path, "one\ntwo\nthree\nfour\n");
+
+        let mut tailer = FileTailer::new(path.clone());
+        assert!(tailer.open_at_tail(2).is_empty());
+        assert_eq!(lines(&tailer.poll()), vec!["three", "four"]);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_follows_appended_lines_and_partials() {
+        let path = temp_path("append");
+        let _ = fs::remove_file(&path);
+        append(&path, "first\n");
+
+        let mut tailer = FileTailer::new(path.clone());
+        tailer.open_at_tail(10);
+        assert_eq!(lines(&tailer.poll()), vec!["first"]);
+
+        append(&path, "sec");
+        assert!(lines(&tailer.poll()).is_empty());
+        append(&path, "ond\r\nthird\n");
+        assert_eq!(lines(&tailer.poll()), vec!["second", "third"]);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_detects_truncation() {
+        let path = temp_path("truncate");
+        let _ = fs::remove_file(&path);
+        append(&path, "a long line before truncation\n");
+
+        let mut tailer = FileTailer::new(path.clone());
+        tailer.open_at_tail(10);
+        tailer.poll();
+
+        fs::write(&path, "new\n").unwrap();
+        let events = tailer.poll();
+        assert!(events.contains(&TailEvent::Truncated));
+        assert_eq!(lines(&events), vec!["new"]);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_detects_rename_rotation() {
+        let path = temp_path("rotate");
+        let rotated = temp_path("rotate.1");
+        let _ = fs::remove_file(&path);
+        append(&path, "old\n");
+
+        let mut tailer = FileTailer::new(path.clone());
+        tailer.open_at_tail(10);
+        tailer.poll();
+
+        // Late write to the old file, then rotate and start a new one
+        append(&path, "late\n");
+        fs::rename(&path, &rotated).unwrap();
+        append(&path, "fresh\n");
+
+        let events = tailer.poll();
+        assert!(events.contains(&TailEvent::Rotated));
+        assert_eq!(lines(&events), vec!["late", "fresh"]);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&rotated);
+    }
+
+    #[test]
+    fn test_missing_file_reported_once_then_reopened() {
+        let path = temp_path("missing");
+        let _ = fs::remove_file(&path);
+
+        let mut tailer = FileTailer::new(path.clone());
+        let events = tailer.open_at_tail(10);
+        assert!(matches!(events.as_slice(), [TailEvent::Missing(_)]));
+        assert!(tailer.poll().is_empty());
+
+        append(&path, "hello\n");
+        let events = tailer.poll();
+        assert_eq!(lines(&events), vec!["hello"]);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_replaced() {
+        assert_eq!(decode_line(b"ok \xff end"), "ok \u{fffd} end");
+    }
+}
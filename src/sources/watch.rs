@@ -0,0 +1,472 @@
+//! Watchers that add and remove sources at runtime: files under a glob or
+//! directory, the pods behind a Kubernetes label selector or workload, and
+//! Docker containers matching a pattern.
+//!
+//! A watcher runs in the background and reports `WatchEvent`s on a channel
+//! that the main loop turns into `AppState::add_source` /
+//! `SourceManager::add_source` calls (or marks sources as ended).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::docker_api::{self, ContainerEvents, ContainerPattern, DockerHost};
+use super::{LogSourceType, k8s};
+use crate::config::{DOCKER_EVENTS_RETRY_MS, POD_WATCH_POLL_INTERVAL_MS, WATCH_POLL_INTERVAL_MS};
+
+/// A set of sources to keep watching after startup
+#[derive(Clone, Debug)]
+pub enum WatchSpec {
+    /// Files matching a glob pattern (`/var/log/myapp/*.log`)
+    Files {
+        pattern: String,
+        /// Files already opened
+        known: Vec<PathBuf>,
+    },
+    /// Containers of the running pods matching a label selector (`app=api`)
+    Pods {
+        selector: String,
+        scope: k8s::Scope,
+        containers: k8s::ContainerSelection,
+        /// Containers already opened, so pods that go away before the first
+        /// poll are still reported
+        known: Vec<LogSourceType>,
+    },
+    /// Docker containers matching a pattern, followed through the daemon's
+    /// event stream
+    Containers {
+        host: DockerHost,
+        pattern: ContainerPattern,
+        /// Containers already opened
+        known: Vec<LogSourceType>,
+    },
+}
+
+/// Source set changes reported by watchers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A new source appeared
+    Added(LogSourceType),
+    /// A source went away
+    Removed(LogSourceType),
+    /// The watch couldn't look for sources this time; it keeps trying
+    Error(String),
+}
+
+/// Whether a command-line argument is a glob pattern rather than a path
+pub fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+/// Glob pattern that matches every file directly inside `dir`
+pub fn dir_pattern(dir: &Path) -> String {
+    let escaped = glob::Pattern::escape(&dir.to_string_lossy());
+    format!("{}/*", escaped.trim_end_matches('/'))
+}
+
+/// List the regular files matching a glob pattern, sorted by path
+pub fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let paths = glob::glob(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+    let mut files: Vec<PathBuf> = paths
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Compare two listings and report files that appeared or disappeared
+fn diff_paths(known: &BTreeSet<PathBuf>, current: &BTreeSet<PathBuf>) -> Vec<WatchEvent> {
+    let file = |path: &PathBuf| LogSourceType::File {
+        path: path.clone(),
+        rotated: false,
+    };
+    let added = current
+        .difference(known)
+        .map(|path| WatchEvent::Added(file(path)));
+    let removed = known
+        .difference(current)
+        .map(|path| WatchEvent::Removed(file(path)));
+    removed.chain(added).collect()
+}
+
+/// Compare two pod listings and report containers that appeared or disappeared
+fn diff_sources(known: &[LogSourceType], current: &[LogSourceType]) -> Vec<WatchEvent> {
+    let added = current
+        .iter()
+        .filter(|source| !known.contains(source))
+        .map(|source| WatchEvent::Added(source.clone()));
+    let removed = known
+        .iter()
+        .filter(|source| !current.contains(source))
+        .map(|source| WatchEvent::Removed(source.clone()));
+    removed.chain(added).collect()
+}
+
+/// Start watching. Members missing from the spec's `known` list are
+/// reported as `Added` on the first poll; the receiver must still ignore
+/// sources it already has.
+/// Stops once the receiver is dropped.
+pub fn spawn(spec: WatchSpec, tx: mpsc::Sender<WatchEvent>) {
+    match spec {
+        WatchSpec::Files { pattern, known } => spawn_file_watch(pattern, known, tx),
+        WatchSpec::Pods {
+            selector,
+            scope,
+            containers,
+            known,
+        } => spawn_pod_watch(selector, scope, containers, known, tx),
+        WatchSpec::Containers {
+            host,
+            pattern,
+            known,
+        } => spawn_container_watch(host, pattern, known, tx),
+    }
+}
+
+fn spawn_file_watch(pattern: String, known: Vec<PathBuf>, tx: mpsc::Sender<WatchEvent>) {
+    tokio::spawn(async move {
+        let mut known: BTreeSet<PathBuf> = known.into_iter().collect();
+        // Whether the last listing failed, so a lasting failure is reported once
+        let mut failing = false;
+        loop {
+            tokio::time::sleep(Duration::from_millis(WATCH_POLL_INTERVAL_MS)).await;
+            if tx.is_closed() {
+                return;
+            }
+
+            // Directory listing is blocking; run it on the blocking pool
+            let glob = pattern.clone();
+            let listing = tokio::task::spawn_blocking(move || expand_glob(&glob))
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+            let files = match listing {
+                Ok(files) => files,
+                Err(e) => {
+                    // Keep the files already known and try again next time
+                    if !failing {
+                        let message = format!("Watching '{}': {}", pattern, e);
+                        if tx.send(WatchEvent::Error(message)).await.is_err() {
+                            return;
+                        }
+                    }
+                    failing = true;
+                    continue;
+                }
+            };
+            failing = false;
+            let current: BTreeSet<PathBuf> = files.into_iter().collect();
+            for event in diff_paths(&known, &current) {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+            known = current;
+        }
+    });
+}
+
+fn spawn_pod_watch(
+    selector: String,
+    scope: k8s::Scope,
+    containers: k8s::ContainerSelection,
+    mut known: Vec<LogSourceType>,
+    tx: mpsc::Sender<WatchEvent>,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_millis(POD_WATCH_POLL_INTERVAL_MS)).await;
+            if tx.is_closed() {
+                return;
+            }
+
+            let (selector, scope, containers) =
+                (selector.clone(), scope.clone(), containers.clone());
+            let listing = tokio::task::spawn_blocking(move || {
+                k8s::list_pod_set(&selector, &scope, &containers)
+            })
+            .await;
+            let current = match listing {
+                Ok(Ok(current)) => current,
+                // The API server may be briefly unreachable; don't end every
+                // pod's stream over one failed listing
+                Ok(Err(_)) => continue,
+                Err(_) => return,
+            };
+            for event in diff_sources(&known, &current) {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+            known = current;
+        }
+    });
+}
+
+/// Running containers that match a pattern, as sources
+pub fn matching_containers(
+    containers: Vec<docker_api::ContainerSummary>,
+    pattern: &ContainerPattern,
+) -> Vec<LogSourceType> {
+    containers
+        .into_iter()
+        .filter(|container| pattern.matches(container))
+        .map(|container| LogSourceType::Docker {
+            container: container.name,
+        })
+        .collect()
+}
+
+fn spawn_container_watch(
+    host: DockerHost,
+    pattern: ContainerPattern,
+    mut known: Vec<LogSourceType>,
+    tx: mpsc::Sender<WatchEvent>,
+) {
+    tokio::spawn(async move {
+        loop {
+            // The daemon may restart; containers aren't ended over it
+            let _ = follow_container_events(&host, &pattern, &mut known, &tx).await;
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(DOCKER_EVENTS_RETRY_MS)) => {}
+                _ = tx.closed() => return,
+            }
+        }
+    });
+}
+
+/// Report containers that start or stop until the event stream ends.
+/// Catches up with a listing after subscribing, so nothing that changed
+/// while unsubscribed is missed.
+async fn follow_container_events(
+    host: &DockerHost,
+    pattern: &ContainerPattern,
+    known: &mut Vec<LogSourceType>,
+    tx: &mpsc::Sender<WatchEvent>,
+) -> Result<(), String> {
+    let mut events = ContainerEvents::subscribe(host).await?;
+
+    let listing_host = host.clone();
+    let containers =
+        tokio::task::spawn_blocking(move || docker_api::list_containers(&listing_host))
+            .await
+            .map_err(|e| e.to_string())??;
+    let current = matching_containers(containers, pattern);
+    for event in diff_sources(known, &current) {
+        if tx.send(event).await.is_err() {
+            return Ok(());
+        }
+    }
+    *known = current;
+
+    loop {
+        let event = tokio::select! {
+            event = events.next() => event?,
+            _ = tx.closed() => return Ok(()),
+        };
+        let Some(event) = event else {
+            return Ok(());
+        };
+        if !pattern.matches(&event.container) {
+            continue;
+        }
+        let source = LogSourceType::Docker {
+            container: event.container.name,
+        };
+        let watch_event = if event.started {
+            if known.contains(&source) {
+                continue;
+            }
+            known.push(source.clone());
+            WatchEvent::Added(source)
+        } else {
+            let Some(pos) = known.iter().position(|s| *s == source) else {
+                continue;
+            };
+            known.remove(pos);
+            WatchEvent::Removed(source)
+        };
+        if tx.send(watch_event).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn file(path: &str) -> LogSourceType {
+        LogSourceType::File {
+            path: PathBuf::from(path),
+            rotated: false,
+        }
+    }
+
+    #[test]
+    fn test_is_glob() {
+        assert!(is_glob("/var/log/*.log"));
+        assert!(is_glob("app-?.log"));
+        assert!(is_glob("app-[0-9].log"));
+        assert!(!is_glob("/var/log/app.log"));
+    }
+
+    #[test]
+    fn test_dir_pattern_escapes_metacharacters() {
+        assert_eq!(dir_pattern(Path::new("/var/log/")), "/var/log/*");
+        assert_eq!(dir_pattern(Path::new("/logs/[prod]")), "/logs/[[]prod[]]/*");
+    }
+
+    #[test]
+    fn test_diff_paths() {
+        let known: BTreeSet<PathBuf> = ["/l/a.log", "/l/b.log"].iter().map(PathBuf::from).collect();
+        let current: BTreeSet<PathBuf> =
+            ["/l/b.log", "/l/c.log"].iter().map(PathBuf::from).collect();
+
+        assert_eq!(
+            diff_paths(&known, &current),
+            vec![
+                WatchEvent::Removed(file("/l/a.log")),
+                WatchEvent::Added(file("/l/c.log")),
+            ]
+        );
+        assert!(diff_paths(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_diff_sources_follows_a_rollout() {
+        let pod = |pod: &str| LogSourceType::K8s {
+            pod: pod.to_string(),
+            context: None,
+            namespace: None,
+            container: Some("api".to_string()),
+            init: false,
+            previous: false,
+        };
+        let known = vec![pod("api-old-1"), pod("api-old-2")];
+        let current = vec![pod("api-old-2"), pod("api-new-1")];
+
+        assert_eq!(
+            diff_sources(&known, &current),
+            vec![
+                WatchEvent::Removed(pod("api-old-1")),
+                WatchEvent::Added(pod("api-new-1")),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_container_watch_follows_docker_events() {
+        use docker_api::fake;
+
+        let docker = |name: &str| LogSourceType::Docker {
+            container: name.to_string(),
+        };
+        let event = |action: &str, name: &str| {
+            format!(
+                r#"{{"Type":"container","Action":"{}","Actor":{{"Attributes":{{"name":"{}","image":"nginx"}}}}}}"#,
+                action, name
+            ) + "\n"
+        };
+        let events = [
+            event("start", "web-2"),
+            event("start", "db"),
+            event("die", "web-1"),
+        ]
+        .concat();
+        let listing = r#"[{"Names":["/web-1"],"Image":"nginx"},{"Names":["/web-3"],"Image":"nginx"},{"Names":["/db"],"Image":"postgres"}]"#;
+
+        let socket =
+            std::env::temp_dir().join(format!("bark_watch_docker_{}.sock", std::process::id()));
+        // Subscribe first, then catch up with a listing
+        let daemon = fake::daemon(
+            &socket,
+            vec![fake::streaming(events.as_bytes()), fake::json(listing)],
+        );
+        let host = DockerHost::Unix(socket.clone());
+        let pattern = ContainerPattern::parse("web-*").unwrap();
+        let mut known = vec![docker("web-1")];
+        let (tx, mut rx) = mpsc::channel(16);
+
+        follow_container_events(&host, &pattern, &mut known, &tx)
+            .await
+            .unwrap();
+        drop(tx);
+        let mut reported = Vec::new();
+        while let Some(event) = rx.recv().await {
+            reported.push(event);
+        }
+
+        assert_eq!(
+            reported,
+            vec![
+                // Started before the subscription
+                WatchEvent::Added(docker("web-3")),
+                WatchEvent::Added(docker("web-2")),
+                WatchEvent::Removed(docker("web-1")),
+            ]
+        );
+        assert_eq!(known, vec![docker("web-3"), docker("web-2")]);
+        assert!(daemon.await.unwrap()[0].starts_with("GET /events?filters="));
+        let _ = fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_file_watch_skips_files_opened_at_startup() {
+        let dir = std::env::temp_dir().join(format!("bark_watch_seed_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("old.log.gz"), "").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(16);
+        spawn_file_watch(dir_pattern(&dir), vec![dir.join("old.log.gz")], tx);
+        fs::write(dir.join("new.log"), "").unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert_eq!(
+            event.unwrap(),
+            Some(WatchEvent::Added(file(
+                &dir.join("new.log").to_string_lossy()
+            )))
+        );
+        drop(rx);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_file_watch_reports_errors_and_keeps_polling() {
+        let (tx, mut rx) = mpsc::channel(16);
+        spawn_file_watch("/tmp/[".to_string(), Vec::new(), tx);
+
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert!(matches!(event, Ok(Some(WatchEvent::Error(_)))));
+        // Reported once, and the watch is still running
+        tokio::time::sleep(Duration::from_millis(WATCH_POLL_INTERVAL_MS * 2)).await;
+        assert!(matches!(
+            rx.try_recv(),
+            Err(mpsc::error::TryRecvError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_expand_glob_lists_only_matching_files() {
+        let dir = std::env::temp_dir().join(format!("bark_watch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub.log")).unwrap();
+        fs::write(dir.join("b.log"), "").unwrap();
+        fs::write(dir.join("a.log"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let pattern = format!("{}/*.log", glob::Pattern::escape(&dir.to_string_lossy()));
+        let files = expand_glob(&pattern).unwrap();
+        assert_eq!(files, vec![dir.join("a.log"), dir.join("b.log")]);
+
+        let all = expand_glob(&dir_pattern(&dir)).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -47,6 +47,10 @@ pub struct Theme {
     // JSON pretty-print
     pub json: Color,
 
+    // Structured fields (logfmt keys and values)
+    pub field_key: Color,
+    pub field_value: Color,
+
     // Filters panel
     pub filter_selected: Color,
     pub filter_prefix: Color,
@@ -114,6 +118,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(139, 233, 253), // Cyan
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(189, 147, 249),   // Purple
+            field_value: Color::Rgb(241, 250, 140), // Yellow
+
             // Filters
             filter_selected: Color::Rgb(255, 184, 108),
             filter_prefix: Color::Rgb(189, 147, 249), // Purple
@@ -181,6 +189,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(182, 255, 214), // Mint
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(214, 182, 255),   // Lavender
+            field_value: Color::Rgb(182, 214, 255), // Light blue
+
             // Filters
             filter_selected: Color::Rgb(255, 214, 182), // Peach
             filter_prefix: Color::Rgb(255, 182, 214),   // Pink
@@ -248,6 +260,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(0, 200, 255), // Electric blue
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(255, 0, 255),   // Magenta
+            field_value: Color::Rgb(0, 255, 150), // Neon green
+
             // Filters
             filter_selected: Color::Rgb(255, 200, 0), // Yellow neon
             filter_prefix: Color::Rgb(0, 255, 255),   // Cyan
@@ -315,6 +331,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(139, 233, 253), // Cyan
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(189, 147, 249),   // Purple
+            field_value: Color::Rgb(241, 250, 140), // Yellow
+
             // Filters
             filter_selected: Color::Rgb(241, 250, 140), // Yellow
             filter_prefix: Color::Rgb(255, 184, 108),   // Orange
@@ -382,6 +402,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(180, 180, 180),
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(140, 140, 140),
+            field_value: Color::Rgb(220, 220, 220),
+
             // Filters
             filter_selected: Color::Rgb(255, 255, 255),
             filter_prefix: Color::Rgb(180, 180, 180),
@@ -449,6 +473,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(100, 255, 100),
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(0, 160, 0),
+            field_value: Color::Rgb(150, 255, 150),
+
             // Filters
             filter_selected: Color::Rgb(0, 255, 0),
             filter_prefix: Color::Rgb(0, 200, 0),
@@ -516,6 +544,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(143, 188, 187), // Nord7
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(129, 161, 193),   // Nord9
+            field_value: Color::Rgb(163, 190, 140), // Nord14 green
+
             // Filters
             filter_selected: Color::Rgb(235, 203, 139),
             filter_prefix: Color::Rgb(136, 192, 208),
@@ -583,6 +615,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(131, 165, 152), // Aqua
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(254, 128, 25),    // Orange
+            field_value: Color::Rgb(142, 192, 124), // Green
+
             // Filters
             filter_selected: Color::Rgb(250, 189, 47),
             filter_prefix: Color::Rgb(254, 128, 25),
@@ -650,6 +686,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(148, 226, 213), // Teal
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(203, 166, 247),   // Mauve
+            field_value: Color::Rgb(166, 227, 161), // Green
+
             // Filters
             filter_selected: Color::Rgb(249, 226, 175),
             filter_prefix: Color::Rgb(203, 166, 247),
@@ -717,6 +757,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(125, 207, 255), // Cyan
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(187, 154, 247),   // Purple
+            field_value: Color::Rgb(158, 206, 106), // Green
+
             // Filters
             filter_selected: Color::Rgb(224, 175, 104),
             filter_prefix: Color::Rgb(187, 154, 247),
@@ -784,6 +828,10 @@ impl Theme {
             // JSON
             json: Color::Rgb(42, 161, 152), // Cyan
 
+            // Structured fields (logfmt)
+            field_key: Color::Rgb(38, 139, 210),  // Blue
+            field_value: Color::Rgb(133, 153, 0), // Green
+
             // Filters
             filter_selected: Color::Rgb(181, 137, 0),
             filter_prefix: Color::Rgb(42, 161, 152),
@@ -1005,4 +1053,25 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_all_themes_tell_field_keys_from_values() {
+        let themes = [
+            "default",
+            "kawaii",
+            "cyber",
+            "dracula",
+            "monochrome",
+            "matrix",
+            "nord",
+            "gruvbox",
+            "catppuccin",
+            "tokyo_night",
+            "solarized",
+        ];
+        for name in themes {
+            let theme = Theme::by_name(name);
+            assert_ne!(theme.field_key, theme.field_value, "{}", name);
+        }
+    }
 }
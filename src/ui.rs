@@ -14,13 +14,21 @@ use ratatui::{
     },
 };
 
-use crate::app::{AppState, FocusedPanel, InputMode, LogLevel, PickerMode, SplitDirection};
+use crate::app::{
+    AppState, FocusedPanel, InputMode, LogLevel, PickerMode, RowInfo, ScopeChooser, ScopeKind,
+    SplitDirection,
+};
+use crate::config::TABLE_COLUMN_MAX_WIDTH;
 use crate::filter::MatchRange;
+use crate::health::{SourceStatus, format_age, format_count};
+use crate::parse;
+use crate::sources::LogSourceType;
 use crate::theme::Theme;
+use std::time::Instant;
 
-const SIDE_PANEL_WIDTH: u16 = 24;
+const SIDE_PANEL_WIDTH: u16 = 28;
 
-/// Data for rendering a single log line: (raw, has_ansi, level_color, relative_time, is_json, is_bookmarked, source_id, line_number)
+/// Data for rendering a single log line: (raw, has_ansi, level_color, relative_time, is_json, is_logfmt, is_bookmarked, source_id, line_number)
 type LineRenderData = (
     String,
     bool,
@@ -28,6 +36,7 @@ type LineRenderData = (
     Option<String>,
     bool,
     bool,
+    bool,
     usize,
     usize, // line number (1-indexed for display)
 );
@@ -89,48 +98,127 @@ fn apply_horizontal_scroll_to_line(line: &Line<'_>, offset: usize) -> Line<'stat
     Line::from(result_spans)
 }
 
-/// Apply match highlighting to a line, returning styled spans
+/// Split styled byte ranges wherever an overlay range starts or ends and
+/// patch the overlay's style onto the parts it covers. Later overlays win.
+fn overlay_styles(
+    mut segments: Vec<(usize, usize, Style)>,
+    overlays: &[(MatchRange, Style)],
+) -> Vec<(usize, usize, Style)> {
+    for (m, overlay) in overlays {
+        let mut split = Vec::with_capacity(segments.len() + 2);
+        for (start, end, style) in segments {
+            let (from, to) = (m.start.max(start), m.end.min(end));
+            if from >= to {
+                split.push((start, end, style));
+                continue;
+            }
+            if start < from {
+                split.push((start, from, style));
+            }
+            split.push((from, to, style.patch(*overlay)));
+            if to < end {
+                split.push((to, end, style));
+            }
+        }
+        segments = split;
+    }
+    segments
+}
+
+/// Highlight rule ranges followed by filter matches (which win where they
+/// overlap), dropping ranges that don't fall on character boundaries of `text`
+fn match_overlays(
+    text: &str,
+    matches: &[MatchRange],
+    rules: &[(MatchRange, Style)],
+    theme: &Theme,
+) -> Vec<(MatchRange, Style)> {
+    let highlight_style = Style::default()
+        .bg(theme.highlight_match_bg)
+        .fg(theme.highlight_match_fg)
+        .add_modifier(Modifier::BOLD);
+    rules
+        .iter()
+        .copied()
+        .chain(matches.iter().map(|m| (*m, highlight_style)))
+        .filter(|(m, _)| {
+            m.start < m.end
+                && m.end <= text.len()
+                && text.is_char_boundary(m.start)
+                && text.is_char_boundary(m.end)
+        })
+        .collect()
+}
+
+/// Apply match and highlight-rule coloring to a line, returning styled spans
 fn highlight_matches(
     text: &str,
     matches: &[MatchRange],
+    rules: &[(MatchRange, Style)],
     base_style: Style,
     theme: &Theme,
 ) -> Line<'static> {
-    if matches.is_empty() {
+    if matches.is_empty() && rules.is_empty() {
         return Line::from(Span::styled(text.to_string(), base_style));
     }
 
-    let highlight_style = Style::default()
-        .bg(theme.highlight_match_bg)
-        .fg(theme.highlight_match_fg)
-        .add_modifier(Modifier::BOLD);
+    let overlays = match_overlays(text, matches, rules, theme);
+    let spans: Vec<Span> = overlay_styles(vec![(0, text.len(), base_style)], &overlays)
+        .into_iter()
+        .map(|(start, end, style)| Span::styled(text[start..end].to_string(), style))
+        .collect();
 
-    let mut spans = Vec::new();
-    let mut last_end = 0;
+    Line::from(spans)
+}
 
-    for m in matches {
-        // Add text before the match
-        if m.start > last_end {
-            spans.push(Span::styled(
-                text[last_end..m.start].to_string(),
-                base_style,
-            ));
-        }
-        // Add the highlighted match
-        if m.end <= text.len() {
-            spans.push(Span::styled(
-                text[m.start..m.end].to_string(),
-                highlight_style,
-            ));
-            last_end = m.end;
-        }
-    }
+/// Color a logfmt line's keys and values, then apply match and highlight-rule
+/// coloring (`matches` and `rules` are byte ranges in `raw`)
+fn highlight_logfmt(
+    raw: &str,
+    h_scroll: usize,
+    matches: &[MatchRange],
+    rules: &[(MatchRange, Style)],
+    base_style: Style,
+    theme: &Theme,
+) -> Line<'static> {
+    let Some(pairs) = parse::scan_logfmt(raw) else {
+        let scrolled = apply_horizontal_scroll(raw, h_scroll);
+        return Line::from(Span::styled(scrolled, base_style));
+    };
 
-    // Add remaining text after last match
-    if last_end < text.len() {
-        spans.push(Span::styled(text[last_end..].to_string(), base_style));
+    // Styled byte ranges covering the whole line
+    let key_style = Style::default().fg(theme.field_key);
+    let value_style = Style::default().fg(theme.field_value);
+    let mut segments: Vec<(usize, usize, Style)> = Vec::new();
+    let mut pos = 0;
+    for pair in &pairs {
+        segments.push((pos, pair.key.start, base_style));
+        segments.push((pair.key.start, pair.key.end, key_style));
+        segments.push((pair.key.end, pair.value.start, base_style));
+        // The level value keeps the level color
+        let style = if parse::LEVEL_KEYS.contains(&&raw[pair.key.clone()]) {
+            base_style
+        } else {
+            value_style
+        };
+        segments.push((pair.value.start, pair.value.end, style));
+        pos = pair.value.end;
     }
-
+    segments.push((pos, raw.len(), base_style));
+
+    // Skip what is scrolled off to the left
+    let offset = raw
+        .char_indices()
+        .nth(h_scroll)
+        .map(|(i, _)| i)
+        .unwrap_or(raw.len());
+
+    let overlays = match_overlays(raw, matches, rules, theme);
+    let spans: Vec<Span> = overlay_styles(segments, &overlays)
+        .into_iter()
+        .filter(|(_, end, _)| *end > offset)
+        .map(|(start, end, style)| Span::styled(raw[start.max(offset)..end].to_string(), style))
+        .collect();
     Line::from(spans)
 }
 
@@ -207,6 +295,11 @@ pub fn draw(frame: &mut Frame, state: &mut AppState) {
         draw_help_overlay(frame, &state.theme);
     }
 
+    // Draw source detail overlay if active
+    if let Some(source_id) = state.source_detail {
+        draw_source_detail_overlay(frame, state, source_id);
+    }
+
     // Draw picker overlay if active
     if state.picker.visible {
         draw_picker_overlay(frame, state);
@@ -216,45 +309,58 @@ pub fn draw(frame: &mut Frame, state: &mut AppState) {
     if state.settings.visible {
         draw_settings_overlay(frame, state);
     }
+
+    // Draw column picker overlay if active
+    if state.column_picker.visible {
+        draw_column_picker_overlay(frame, state);
+    }
 }
 
 /// Draw the side panel with sources and saved filters
 fn draw_side_panel(frame: &mut Frame, state: &AppState, area: Rect) {
+    let items = source_list_items(state, area.width.saturating_sub(2) as usize);
+    let sources_height: usize = items.iter().map(|item| item.height()).sum();
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(state.sources.len() as u16 + 2), // Sources section
-            Constraint::Min(3),                                 // Filters section
+            Constraint::Length(sources_height as u16 + 2), // Sources section
+            Constraint::Min(3),                            // Filters section
+            Constraint::Length(state.highlights.len().max(1) as u16 + 2), // Highlights section
         ])
         .split(area);
 
-    draw_sources_panel(frame, state, chunks[0]);
+    draw_sources_panel(frame, state, items, chunks[0]);
     draw_filters_panel(frame, state, chunks[1]);
+    draw_highlights_panel(frame, state, chunks[2]);
 }
 
-/// Draw the sources list
-fn draw_sources_panel(frame: &mut Frame, state: &AppState, area: Rect) {
-    use crate::app::SourceViewMode;
-
-    let focused = state.focused_panel == FocusedPanel::Sources;
-    let border_style = if focused {
-        Style::default().fg(state.theme.border_focused)
-    } else {
-        Style::default().fg(state.theme.border_unfocused)
-    };
+/// Color used for a source state
+fn source_status_color(theme: &Theme, status: SourceStatus) -> Color {
+    match status {
+        SourceStatus::Streaming => theme.level_info,
+        SourceStatus::Stalled | SourceStatus::Reconnecting(_) => theme.warning_message,
+        SourceStatus::Errored => theme.level_error,
+        SourceStatus::Connecting | SourceStatus::Ended => theme.empty_state,
+    }
+}
 
-    let title = if state.sources.len() > 1 {
-        " Sources (Space:toggle) "
-    } else {
-        " Sources "
-    };
+/// Cut text to a display width, marking the cut with an ellipsis
+fn truncate_to(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
 
-    let block = Block::default()
-        .title(title)
-        .borders(Borders::ALL)
-        .border_style(border_style);
+/// One list entry per source: name, state with count and rate, last error
+fn source_list_items(state: &AppState, width: usize) -> Vec<ListItem<'static>> {
+    use crate::app::SourceViewMode;
 
-    let items: Vec<ListItem> = state
+    let now = Instant::now();
+    state
         .sources
         .iter()
         .enumerate()
@@ -274,14 +380,6 @@ fn draw_sources_panel(frame: &mut Frame, state: &AppState, area: Rect) {
 
             let color = state.theme.get_source_color(i);
 
-            // Truncate long names
-            let name = source.name();
-            let display_name = if name.len() > 12 {
-                format!("{}...", &name[..9])
-            } else {
-                name
-            };
-
             let style = if is_selected {
                 Style::default().fg(color).add_modifier(Modifier::BOLD)
             } else if is_visible {
@@ -290,14 +388,144 @@ fn draw_sources_panel(frame: &mut Frame, state: &AppState, area: Rect) {
                 Style::default().fg(state.theme.empty_state)
             };
 
-            ListItem::new(format!("{} {} {}", prefix, visibility, display_name)).style(style)
+            let Some(health) = state.source_health.get(i) else {
+                let label = format!("{} {} {}", prefix, visibility, source.name());
+                return ListItem::new(truncate_to(&label, width)).style(style);
+            };
+            let status = health.status(now);
+            // Ended sources stay listed (their lines remain) but are struck through
+            let style = if status == SourceStatus::Ended {
+                style.add_modifier(Modifier::CROSSED_OUT | Modifier::DIM)
+            } else {
+                style
+            };
+
+            let label = format!("{} {} {}", prefix, visibility, source.name());
+            let summary = match status {
+                SourceStatus::Reconnecting(attempt) => {
+                    format!("  reconnecting (attempt {})", attempt)
+                }
+                SourceStatus::Streaming => format!(
+                    "  {} {}/s {}",
+                    status.label(),
+                    format_rate(health.rate(now)),
+                    format_count(health.line_count)
+                ),
+                _ => format!("  {} {}", status.label(), format_count(health.line_count)),
+            };
+
+            let mut lines = vec![
+                Line::styled(truncate_to(&label, width), style),
+                Line::styled(
+                    truncate_to(&summary, width),
+                    Style::default().fg(source_status_color(&state.theme, status)),
+                ),
+            ];
+            // Keep the cause visible while the source is unhealthy
+            if matches!(
+                status,
+                SourceStatus::Errored | SourceStatus::Reconnecting(_) | SourceStatus::Ended
+            ) {
+                if let Some(error) = health.last_error() {
+                    lines.push(Line::styled(
+                        truncate_to(&format!("  ! {}", error.message), width),
+                        Style::default().fg(state.theme.level_error),
+                    ));
+                }
+            }
+            ListItem::new(lines)
         })
-        .collect();
+        .collect()
+}
+
+/// Format a line rate with one decimal below 10/s
+fn format_rate(rate: f64) -> String {
+    if rate < 10.0 {
+        format!("{:.1}", rate)
+    } else {
+        format_count(rate.round() as u64)
+    }
+}
+
+/// Draw the sources list
+fn draw_sources_panel(frame: &mut Frame, state: &AppState, items: Vec<ListItem>, area: Rect) {
+    let focused = state.focused_panel == FocusedPanel::Sources;
+    let border_style = if focused {
+        Style::default().fg(state.theme.border_focused)
+    } else {
+        Style::default().fg(state.theme.border_unfocused)
+    };
+
+    let title = if state.sources.len() > 1 {
+        " Sources (Space:toggle) "
+    } else {
+        " Sources "
+    };
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(border_style);
 
     let list = List::new(items).block(block);
     frame.render_widget(list, area);
 }
 
+/// Draw the highlight rules, each with a swatch of its style
+fn draw_highlights_panel(frame: &mut Frame, state: &AppState, area: Rect) {
+    let focused = state.focused_panel == FocusedPanel::Highlights;
+    let border_style = if focused {
+        Style::default().fg(state.theme.border_focused)
+    } else {
+        Style::default().fg(state.theme.border_unfocused)
+    };
+
+    let title = if focused && !state.highlights.is_empty() {
+        " Highlights (Spc c x) "
+    } else {
+        " Highlights "
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(border_style);
+
+    if state.highlights.is_empty() {
+        let msg = Paragraph::new("  (none, * on a filter)")
+            .style(Style::default().fg(state.theme.empty_state))
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = state
+        .highlights
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let selected = focused && i == state.selected_highlight_idx;
+            let prefix = if selected { "▶ " } else { "  " };
+            let indicator = if rule.config.regex { " [.*]" } else { "" };
+            let text_style = if !rule.config.enabled {
+                Style::default()
+                    .fg(state.theme.empty_state)
+                    .add_modifier(Modifier::CROSSED_OUT)
+            } else if selected {
+                Style::default().fg(state.theme.filter_selected)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(prefix),
+                Span::styled("■", rule.style(&state.theme)),
+                Span::styled(format!(" {}{}", rule.config.pattern, indicator), text_style),
+            ]))
+        })
+        .collect();
+
+    frame.render_widget(List::new(items).block(block), area);
+}
+
 /// Draw the saved filters list
 fn draw_filters_panel(frame: &mut Frame, state: &AppState, area: Rect) {
     let focused = state.focused_panel == FocusedPanel::Filters;
@@ -328,7 +556,13 @@ fn draw_filters_panel(frame: &mut Frame, state: &AppState, area: Rect) {
                 } else {
                     "  "
                 };
-                let indicator = if filter.is_regex { " [.*]" } else { "" };
+                let mut indicator = if filter.is_regex { " [.*]" } else { "" }.to_string();
+                if !filter.stack.is_empty() {
+                    indicator.push_str(&format!(" [+{}]", filter.stack.len()));
+                }
+                if filter.predefined {
+                    indicator.push_str(" [cfg]");
+                }
                 let style = if i == state.selected_filter_idx {
                     Style::default().fg(state.theme.filter_selected)
                 } else {
@@ -404,26 +638,25 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
         return;
     }
 
+    if state.table_mode {
+        draw_table(frame, state, pane_idx, area, inner);
+        return;
+    }
+
     // Get visible lines for this pane
     let level_colors = state.level_colors_enabled;
     let show_relative = state.show_relative_time;
     let json_pretty_enabled = state.json_pretty;
-    let scroll_pos = state.panes[pane_idx].scroll;
     let bookmarks = state.panes[pane_idx].bookmarks.clone();
-    let filtered_indices = state.panes[pane_idx].filtered_indices.clone();
     let theme = state.theme.clone();
+    let line_num_max = state.total_lines();
     let visible = state.visible_lines_for_pane(pane_idx, height);
 
     // Collect line data first (to avoid borrow issues)
     // Also track which line indices are bookmarked
     let line_data: Vec<LineRenderData> = visible
         .iter()
-        .enumerate()
-        .map(|(visible_idx, (_scroll_idx, line))| {
-            let actual_line_idx = filtered_indices
-                .get(scroll_pos + visible_idx)
-                .copied()
-                .unwrap_or(0);
+        .map(|&(actual_line_idx, line)| {
             let is_bookmarked = bookmarks.contains(&actual_line_idx);
             let level_color = if level_colors {
                 get_level_color(&line.level, &theme)
@@ -440,6 +673,7 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
                     None
                 },
                 line.is_json,
+                line.is_logfmt,
                 is_bookmarked,
                 line.source_id,
                 actual_line_idx + 1, // 1-indexed line number
@@ -447,11 +681,22 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
         })
         .collect();
 
+    // Context lines are dimmed, and groups of them separated
+    let scroll_pos = state.panes[pane_idx].scroll;
+    let rows: Vec<RowInfo> = (scroll_pos..scroll_pos + line_data.len())
+        .map(|pos| state.panes[pane_idx].row(pos))
+        .collect();
+    // Multi-line events show their first line until unfolded
+    let folded: Vec<bool> = line_data
+        .iter()
+        .map(|data| !state.panes[pane_idx].is_unfolded(data.8 - 1))
+        .collect();
+
     // Pre-compute pretty JSON if needed
     let json_cache: Vec<Option<String>> = if json_pretty_enabled {
         line_data
             .iter()
-            .map(|(raw, _, _, _, is_json, _, _, _)| {
+            .map(|(raw, _, _, _, is_json, _, _, _, _)| {
                 if *is_json {
                     serde_json::from_str::<serde_json::Value>(raw)
                         .ok()
@@ -468,7 +713,7 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
     // Calculate line number width for padding (based on max line number we might show)
     let show_line_numbers = state.show_line_numbers;
     let line_num_width = if show_line_numbers {
-        state.lines.len().max(1).to_string().len()
+        line_num_max.max(1).to_string().len()
     } else {
         0
     };
@@ -495,22 +740,35 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
             level_color,
             relative_time,
             _is_json,
+            is_logfmt,
             is_bookmarked,
             source_id,
             line_number,
         ),
     ) in line_data.iter().enumerate()
     {
+        if idx > 0 && rows[idx].after_gap {
+            lines_content.push(group_separator(inner.width, &theme));
+            if lines_content.len() >= height {
+                break;
+            }
+        }
+        let first_row = lines_content.len();
+
         // Check if this line is selected
         let is_selected = selected_line
             .map(|sel| sel == scroll_pos + idx)
             .unwrap_or(false);
         // Check if we have pretty JSON for this line
-        let display_text = json_cache
-            .get(idx)
-            .and_then(|j| j.as_ref())
-            .map(|s| s.as_str())
-            .unwrap_or(raw);
+        let pretty_json = json_cache.get(idx).and_then(|j| j.as_deref());
+        let event_lines = raw.split('\n').count();
+        let folded_event = event_lines > 1 && folded[idx];
+        let display_text = match pretty_json {
+            _ if folded_event => raw.split('\n').next().unwrap_or_default(),
+            Some(pretty) => pretty,
+            None => raw,
+        };
+        let is_pretty = pretty_json.is_some() && !folded_event;
 
         // Build line number prefix if enabled
         let line_num_prefix: Option<Span> = if show_line_numbers {
@@ -523,28 +781,8 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
         };
 
         // Build source prefix if multiple sources
-        let source_prefix: Option<Span> = if show_source_prefix {
-            let source_name = state
-                .sources
-                .get(*source_id)
-                .map(|s| {
-                    let name = s.name();
-                    // Truncate long names
-                    if name.len() > 10 {
-                        format!("{}...", &name[..7])
-                    } else {
-                        name
-                    }
-                })
-                .unwrap_or_else(|| "?".to_string());
-            let color = theme.get_source_color(*source_id);
-            Some(Span::styled(
-                format!("[{:>10}] ", source_name),
-                Style::default().fg(color),
-            ))
-        } else {
-            None
-        };
+        let source_prefix: Option<Span> =
+            show_source_prefix.then(|| source_prefix(state, *source_id, &theme));
 
         // Build bookmark prefix if bookmarked
         let bookmark_prefix: Option<Span> = if *is_bookmarked {
@@ -578,18 +816,17 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
             )]
         });
 
-        // Handle multi-line display (for pretty JSON)
+        // Handle multi-line display (pretty JSON and unfolded events)
         let display_lines: Vec<&str> = display_text.lines().collect();
-        let is_multiline = display_lines.len() > 1;
 
         for (line_idx, display_line) in display_lines.iter().enumerate() {
             // Only show time prefix on first line of multi-line
             let show_prefix = line_idx == 0;
 
-            if *has_ansi && !is_multiline {
+            if *has_ansi && !is_pretty {
                 // Line has ANSI codes - use the standard rendering without highlighting
                 // (ANSI parsing is complex and highlighting would interfere)
-                if let Ok(text) = raw.as_bytes().into_text() {
+                if let Ok(text) = display_line.as_bytes().into_text() {
                     for text_line in text.lines.iter() {
                         // Apply horizontal scroll to ANSI lines by rebuilding spans
                         let mut scrolled_line = if h_scroll > 0 {
@@ -634,7 +871,7 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
                         }
                     }
                 } else {
-                    let scrolled = apply_horizontal_scroll(raw, h_scroll);
+                    let scrolled = apply_horizontal_scroll(display_line, h_scroll);
                     let mut line = Line::from(scrolled);
                     if show_prefix {
                         let mut prefix_spans = Vec::new();
@@ -661,8 +898,8 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
                     lines_content.push(line);
                 }
             } else {
-                // No ANSI codes or multi-line JSON - we can safely apply highlighting
-                let base_style = if is_multiline {
+                // No ANSI codes or pretty JSON - we can safely apply highlighting
+                let base_style = if is_pretty {
                     // JSON gets themed coloring
                     Style::default().fg(theme.json)
                 } else {
@@ -674,9 +911,9 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
                 // Apply horizontal scroll before highlighting
                 let scrolled = apply_horizontal_scroll(display_line, h_scroll);
                 // Adjust match ranges for the scroll offset (only for original text)
-                let matches = if !is_multiline && h_scroll > 0 {
+                let matches = if !is_pretty && h_scroll > 0 {
                     state
-                        .get_match_ranges(raw)
+                        .get_match_ranges(display_line)
                         .into_iter()
                         .filter_map(|m| {
                             if m.end <= h_scroll {
@@ -696,14 +933,27 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
                             }
                         })
                         .collect()
-                } else if !is_multiline {
-                    state.get_match_ranges(raw)
+                } else if !is_pretty {
+                    state.get_match_ranges(display_line)
                 } else {
                     Vec::new() // No highlighting for pretty JSON lines
                 };
 
-                let mut highlighted_line =
-                    highlight_matches(&scrolled, &matches, base_style, &theme);
+                let mut highlighted_line = if *is_logfmt && !is_pretty && line_idx == 0 {
+                    let raw_matches = state.get_match_ranges(display_line);
+                    let rules = state.get_highlight_ranges(display_line);
+                    highlight_logfmt(
+                        display_line,
+                        h_scroll,
+                        &raw_matches,
+                        &rules,
+                        base_style,
+                        &theme,
+                    )
+                } else {
+                    let rules = state.get_highlight_ranges(&scrolled);
+                    highlight_matches(&scrolled, &matches, &rules, base_style, &theme)
+                };
 
                 // Add prefixes (line number, source, bookmark, selection, time) - only on first line
                 if show_prefix {
@@ -737,6 +987,19 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
             }
         }
 
+        if folded_event && first_row < lines_content.len() {
+            lines_content[first_row].spans.push(Span::styled(
+                format!(" [+{} lines]", event_lines - 1),
+                Style::default().fg(theme.empty_state),
+            ));
+        }
+
+        if rows[idx].context {
+            for line in &mut lines_content[first_row..] {
+                *line = std::mem::take(line).patch_style(Modifier::DIM);
+            }
+        }
+
         if lines_content.len() >= height {
             break;
         }
@@ -753,6 +1016,326 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
     }
     frame.render_widget(paragraph, inner);
 
+    draw_pane_status(frame, state, pane_idx, area, inner, height);
+}
+
+/// Line drawn between groups of context lines (like grep's `--`)
+fn group_separator(width: u16, theme: &Theme) -> Line<'static> {
+    Line::styled(
+        "┄".repeat(width as usize),
+        Style::default().fg(theme.border_unfocused),
+    )
+}
+
+/// A row of the table view
+struct TableRow {
+    /// Column values, or None for lines without fields
+    cells: Option<Vec<String>>,
+    /// Plain text of the line (shown when it has no fields)
+    text: String,
+    level: LogLevel,
+    source_id: usize,
+    line_number: usize,
+    is_bookmarked: bool,
+}
+
+/// Text of a line without ANSI escape codes
+fn plain_text(raw: &str) -> String {
+    match raw.as_bytes().into_text() {
+        Ok(text) => text
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter().map(|s| s.content.as_ref()))
+            .collect(),
+        Err(_) => raw.to_string(),
+    }
+}
+
+/// Pad or cut text to exactly `width` characters
+fn fit_to(text: &str, width: usize) -> String {
+    format!("{:<width$}", truncate_to(text, width), width = width)
+}
+
+/// Join cells into a row, padding every column but the last
+fn table_row_text(cells: &[String], widths: &[usize]) -> String {
+    let last = cells.len().saturating_sub(1);
+    cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, &width))| {
+            if i == last {
+                cell.clone()
+            } else {
+                fit_to(cell, width)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// Shorten a name to its last characters
+fn keep_end(name: &str) -> String {
+    let name: Vec<char> = name.chars().collect();
+    if name.len() > 10 {
+        format!("...{}", name[name.len() - 7..].iter().collect::<String>())
+    } else {
+        name.into_iter().collect()
+    }
+}
+
+/// Source name prefix shown when there are multiple sources
+fn source_prefix(state: &AppState, source_id: usize, theme: &Theme) -> Span<'static> {
+    let source_name = state
+        .sources
+        .get(source_id)
+        .map(|s| match s {
+            // Pods of one workload and replicas of a compose service share a
+            // name prefix; keep the distinct end (`...x2k4q/api`) instead
+            LogSourceType::K8s {
+                pod,
+                container: Some(container),
+                ..
+            } => keep_end(&format!("{}/{}", pod, container)),
+            LogSourceType::Docker { .. } => keep_end(&s.name()),
+            _ => {
+                let name = s.name();
+                // Truncate long names
+                if name.len() > 10 {
+                    format!("{}...", &name[..7])
+                } else {
+                    name
+                }
+            }
+        })
+        .unwrap_or_else(|| "?".to_string());
+    Span::styled(
+        format!("[{:>10}] ", source_name),
+        Style::default().fg(theme.get_source_color(source_id)),
+    )
+}
+
+/// Draw a pane as a table: one column per chosen field, lines without fields
+/// as plain text, and the selected line's remaining fields underneath
+fn draw_table(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rect, inner: Rect) {
+    let theme = state.theme.clone();
+    let columns = state.table_columns();
+
+    // Fields of the selected line that have no column of their own
+    let detail: Vec<(String, String)> = if state.panes[pane_idx].detail_expanded {
+        state
+            .selected_fields(pane_idx)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| !columns.contains(key))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let detail_height = if detail.is_empty() {
+        0
+    } else {
+        (detail.len() as u16 + 1).min(inner.height / 2)
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),             // Column header
+            Constraint::Min(0),                // Rows
+            Constraint::Length(detail_height), // Selected line's fields
+        ])
+        .split(inner);
+    let rows_area = chunks[1];
+    let height = rows_area.height as usize;
+
+    // Clicks map to rows below the header
+    if pane_idx < state.log_view_areas.len() {
+        state.log_view_areas[pane_idx] = rows_area;
+    }
+
+    let scroll_pos = state.panes[pane_idx].scroll;
+    let selected_line = state.panes[pane_idx].selected_line;
+    let bookmarks = state.panes[pane_idx].bookmarks.clone();
+    let h_scroll = state.panes[pane_idx].horizontal_scroll;
+    let level_colors = state.level_colors_enabled;
+    let show_source_prefix = state.sources.len() > 1;
+    let line_num_width = if state.show_line_numbers {
+        state.total_lines().max(1).to_string().len()
+    } else {
+        0
+    };
+
+    let rows: Vec<TableRow> = state
+        .visible_lines_for_pane(pane_idx, height)
+        .iter()
+        .map(|&(line_idx, line)| TableRow {
+            cells: (!line.fields.is_empty()).then(|| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        parse::field(&line.fields, column)
+                            .map(|value| value.replace(['\n', '\r', '\t'], " "))
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            }),
+            text: {
+                // Multi-line events show their first line
+                let mut event = line.raw.split('\n');
+                let first = event.next().unwrap_or_default();
+                let mut text = if line.has_ansi {
+                    plain_text(first)
+                } else {
+                    first.to_string()
+                };
+                let more = event.count();
+                if more > 0 {
+                    text.push_str(&format!(" [+{} lines]", more));
+                }
+                text
+            },
+            level: line.level,
+            source_id: line.source_id,
+            line_number: line_idx + 1,
+            is_bookmarked: bookmarks.contains(&line_idx),
+        })
+        .collect();
+
+    // Size columns to the visible values
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+    for cells in rows.iter().filter_map(|row| row.cells.as_ref()) {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for width in &mut widths {
+        *width = (*width).min(TABLE_COLUMN_MAX_WIDTH);
+    }
+
+    // Gutter: line number, source, then a bookmark/selection marker
+    let gutter_width = if state.show_line_numbers {
+        line_num_width + 1
+    } else {
+        0
+    } + if show_source_prefix { 13 } else { 0 }
+        + 2;
+    let header_text = apply_horizontal_scroll(&table_row_text(&columns, &widths), h_scroll);
+    let header = Line::from(vec![
+        Span::raw(" ".repeat(gutter_width)),
+        Span::styled(
+            header_text,
+            Style::default()
+                .fg(theme.border_focused)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(header), chunks[0]);
+
+    let pane = &state.panes[pane_idx];
+    let row_info: Vec<RowInfo> = (pane.scroll..pane.scroll + rows.len())
+        .map(|pos| pane.row(pos))
+        .collect();
+
+    let mut lines_content: Vec<Line> = Vec::with_capacity(height);
+    for (idx, row) in rows.iter().enumerate() {
+        if idx > 0 && row_info[idx].after_gap {
+            lines_content.push(group_separator(rows_area.width, &theme));
+        }
+        if lines_content.len() >= height {
+            break;
+        }
+        let mut spans = Vec::new();
+        if state.show_line_numbers {
+            spans.push(Span::styled(
+                format!("{:>width$} ", row.line_number, width = line_num_width),
+                Style::default().fg(theme.timestamp),
+            ));
+        }
+        if show_source_prefix {
+            spans.push(source_prefix(state, row.source_id, &theme));
+        }
+        if selected_line == Some(scroll_pos + idx) {
+            spans.push(Span::styled(
+                "▶ ",
+                Style::default()
+                    .fg(theme.border_focused)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else if row.is_bookmarked {
+            spans.push(Span::styled(
+                "* ",
+                Style::default()
+                    .fg(theme.bookmark)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else {
+            spans.push(Span::raw("  "));
+        }
+
+        let text = match &row.cells {
+            Some(cells) => table_row_text(cells, &widths),
+            None => row.text.clone(),
+        };
+        let text = apply_horizontal_scroll(&text, h_scroll);
+        let base_style = if level_colors {
+            get_level_color(&row.level, &theme)
+                .map(|c| Style::default().fg(c))
+                .unwrap_or_default()
+        } else {
+            Style::default()
+        };
+        let matches = state.get_match_ranges(&text);
+        let rules = state.get_highlight_ranges(&text);
+        spans.extend(highlight_matches(&text, &matches, &rules, base_style, &theme).spans);
+        let mut line = Line::from(spans);
+        if row_info[idx].context {
+            line = line.patch_style(Modifier::DIM);
+        }
+        lines_content.push(line);
+    }
+    frame.render_widget(Paragraph::new(lines_content), rows_area);
+
+    if !detail.is_empty() {
+        let key_width = detail
+            .iter()
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(TABLE_COLUMN_MAX_WIDTH);
+        let detail_lines: Vec<Line> = detail
+            .iter()
+            .map(|(key, value)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("  {} ", fit_to(key, key_width)),
+                        Style::default().fg(theme.timestamp),
+                    ),
+                    Span::raw(value.replace(['\n', '\r', '\t'], " ")),
+                ])
+            })
+            .collect();
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(theme.border_unfocused))
+            .title(" fields ");
+        frame.render_widget(Paragraph::new(detail_lines).block(block), chunks[2]);
+    }
+
+    draw_pane_status(frame, state, pane_idx, area, rows_area, height);
+}
+
+/// Draw the scrollbar and the empty/no-match message of a pane
+fn draw_pane_status(
+    frame: &mut Frame,
+    state: &AppState,
+    pane_idx: usize,
+    area: Rect,
+    inner: Rect,
+    height: usize,
+) {
+    let theme = &state.theme;
+
     // Draw scrollbar if there are more lines than visible
     let (total, filtered) = state.line_counts_for_pane(pane_idx);
     if filtered > height {
@@ -771,6 +1354,10 @@ fn draw_pane(frame: &mut Frame, state: &mut AppState, pane_idx: usize, area: Rec
         let msg = Paragraph::new("Waiting for log lines...")
             .style(Style::default().fg(theme.empty_state));
         frame.render_widget(msg, inner);
+    } else if filtered == 0 && state.panes[pane_idx].min_level.is_some() {
+        let msg = Paragraph::new("No lines match the current filter and level (-: lower)")
+            .style(Style::default().fg(theme.warning_message));
+        frame.render_widget(msg, inner);
     } else if filtered == 0 && state.panes[pane_idx].active_filter.is_some() {
         let msg = Paragraph::new("No lines match the current filter")
             .style(Style::default().fg(theme.warning_message));
@@ -820,6 +1407,38 @@ fn draw_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
         String::new()
     };
 
+    // Browse progress - shown while a whole file is still being indexed/filtered
+    let browse_indicator = match &state.browse {
+        Some(browser) if !browser.is_fully_indexed() => {
+            format!("[indexing {}%]", browser.index_progress())
+        }
+        Some(_) if state.browse_in_progress() => "[scanning]".to_string(),
+        _ => String::new(),
+    };
+
+    // Line order (only meaningful with several sources)
+    let order_indicator = if state.sources.len() > 1 {
+        if state.merge_by_timestamp {
+            "[by time]"
+        } else {
+            "[by arrival]"
+        }
+    } else {
+        ""
+    };
+
+    let level_indicator = state.panes[state.active_pane]
+        .min_level
+        .map(|level| format!("[>={}]", level.name().to_uppercase()))
+        .unwrap_or_default();
+
+    let pane = &state.panes[state.active_pane];
+    let context_indicator = if pane.context_before == pane.context_after {
+        format!("[ctx {}]", pane.context_before)
+    } else {
+        format!("[ctx {}/{}]", pane.context_before, pane.context_after)
+    };
+
     // Pane indicator (only shown when split)
     let pane_indicator = if state.split_direction != SplitDirection::None {
         format!("[{}/{}]", state.active_pane + 1, state.panes.len())
@@ -835,11 +1454,18 @@ fn draw_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
         color_indicator,
         time_indicator,
         json_indicator,
+        order_indicator,
     ]
     .iter()
     .filter(|s| !s.is_empty())
     .map(|s| s.to_string())
     .collect();
+    if !level_indicator.is_empty() {
+        indicators.push(level_indicator);
+    }
+    if pane.has_context() {
+        indicators.push(context_indicator);
+    }
     if !pane_indicator.is_empty() {
         indicators.push(pane_indicator);
     }
@@ -849,6 +1475,9 @@ fn draw_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
     if !throughput_indicator.is_empty() {
         indicators.push(throughput_indicator);
     }
+    if !browse_indicator.is_empty() {
+        indicators.push(browse_indicator);
+    }
     let indicators_str = if indicators.is_empty() {
         String::new()
     } else {
@@ -858,7 +1487,10 @@ fn draw_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
     let filter_str = state.panes[state.active_pane]
         .active_filter
         .as_ref()
-        .map(|f| format!(" | filter: {}", f.pattern))
+        .map(|f| {
+            let kind = if f.is_query() { "query" } else { "filter" };
+            format!(" | {}: {}", kind, f.pattern)
+        })
         .unwrap_or_default();
 
     let help_text = match state.mode {
@@ -890,28 +1522,62 @@ fn draw_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
 fn draw_filter_bar(frame: &mut Frame, state: &mut AppState, area: Rect) {
     match state.mode {
         InputMode::FilterEditing => {
-            // Create a layout with "/" prefix and textarea
+            // Syntax errors of the previewed query show up at the right
+            let pane = &state.panes[state.active_pane];
+            let error = pane
+                .active_filter
+                .as_ref()
+                .and_then(|f| f.error.as_deref())
+                .map(|e| format!(" {} ", e))
+                .unwrap_or_default();
+
+            // Create a layout with "/" prefix, textarea and error
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
-                    Constraint::Length(1), // "/" prefix
-                    Constraint::Min(1),    // textarea
+                    Constraint::Length(1),                            // "/" prefix
+                    Constraint::Min(1),                               // textarea
+                    Constraint::Length(error.chars().count() as u16), // query error
                 ])
                 .split(area);
 
             let prefix = Paragraph::new("/").style(Style::default().fg(state.theme.filter_prefix));
             frame.render_widget(prefix, chunks[0]);
 
-            frame.render_widget(&state.panes[state.active_pane].filter_textarea, chunks[1]);
+            frame.render_widget(&pane.filter_textarea, chunks[1]);
+
+            if !error.is_empty() {
+                let error =
+                    Paragraph::new(error).style(Style::default().fg(state.theme.warning_message));
+                frame.render_widget(error, chunks[2]);
+            }
         }
         _ => {
+            // Pinned filters as numbered chips, followed by the status message
+            let mut spans = Vec::new();
+            for (i, chip) in state.panes[state.active_pane]
+                .filter_stack
+                .iter()
+                .enumerate()
+            {
+                let style = if chip.enabled {
+                    Style::default().fg(state.theme.filter_selected)
+                } else {
+                    Style::default()
+                        .fg(state.theme.empty_state)
+                        .add_modifier(Modifier::CROSSED_OUT)
+                };
+                spans.push(Span::styled(format!("[{} {}]", i + 1, chip.label()), style));
+                spans.push(Span::raw(" "));
+            }
             if let Some(msg) = &state.status_message {
-                let content = Line::from(Span::styled(
+                spans.push(Span::styled(
                     msg.as_str(),
                     Style::default().fg(state.theme.warning_message),
                 ));
-                let paragraph = Paragraph::new(content);
-                frame.render_widget(paragraph, area);
+            }
+            if !spans.is_empty() {
+                frame.render_widget(Paragraph::new(Line::from(spans)), area);
             }
         }
     }
@@ -923,7 +1589,7 @@ fn draw_help_overlay(frame: &mut Frame, theme: &Theme) {
 
     // Center the help box
     let width = 50.min(area.width.saturating_sub(4));
-    let height = 52.min(area.height.saturating_sub(4));
+    let height = 66.min(area.height.saturating_sub(4));
     let x = (area.width - width) / 2;
     let y = (area.height - height) / 2;
     let help_area = Rect::new(x, y, width, height);
@@ -951,8 +1617,14 @@ fn draw_help_overlay(frame: &mut Frame, theme: &Theme) {
         Line::from(""),
         Line::from("Filtering:"),
         Line::from("  /            Start filter input"),
+        Line::from("  level>=warn  Field query (AND/OR/NOT, = ~ <)"),
         Line::from("  ↑/↓          Browse filter history"),
         Line::from("  r            Toggle regex mode"),
+        Line::from("  f/F          Pin filter as include/exclude"),
+        Line::from("  1-9          Toggle pinned filter"),
+        Line::from("  Bksp/X       Drop last/all pinned filters"),
+        Line::from("  +/-          Raise/lower minimum level"),
+        Line::from("  {/}          Fewer/more context lines"),
         Line::from("  s            Save current filter"),
         Line::from("  e            Export filtered lines"),
         Line::from("  Esc          Clear filter"),
@@ -963,7 +1635,12 @@ fn draw_help_overlay(frame: &mut Frame, theme: &Theme) {
         Line::from("  #            Toggle line numbers"),
         Line::from("  c            Toggle level colors"),
         Line::from("  t            Toggle relative time"),
+        Line::from("  o            Order by timestamp/arrival"),
         Line::from("  J            Toggle JSON pretty-print"),
+        Line::from("  z/Z          Fold/unfold event (all events)"),
+        Line::from("  T            Toggle table view (JSON fields)"),
+        Line::from("  C            Choose table columns"),
+        Line::from("  Enter        Show selected line's fields"),
         Line::from("  b            Toggle side panel"),
         Line::from("  Tab          Cycle panel focus"),
         Line::from("  y            Yank line to clipboard"),
@@ -977,14 +1654,19 @@ fn draw_help_overlay(frame: &mut Frame, theme: &Theme) {
         Line::from(""),
         Line::from("Sources:"),
         Line::from("  D            Docker container picker"),
-        Line::from("  K            Kubernetes pod picker"),
+        Line::from("  K            Kubernetes pod picker (c/n: context, namespace)"),
         Line::from("  Space        Toggle source visibility"),
         Line::from("  v            Solo view (selected only)"),
         Line::from("  a            Show all sources"),
+        Line::from("  i            Source details and errors"),
         Line::from(""),
         Line::from("Saved Filters:"),
         Line::from("  x/Delete     Remove saved filter"),
         Line::from(""),
+        Line::from("Highlights:"),
+        Line::from("  *            Highlight filter text instead"),
+        Line::from("  Space/c/x    Toggle/recolor/remove (panel)"),
+        Line::from(""),
         Line::from("Other:"),
         Line::from("  S            Open settings"),
         Line::from("  ?            Toggle this help"),
@@ -1001,6 +1683,89 @@ fn draw_help_overlay(frame: &mut Frame, theme: &Theme) {
     frame.render_widget(paragraph, help_area);
 }
 
+/// Draw the health and recent errors of one source
+fn draw_source_detail_overlay(frame: &mut Frame, state: &AppState, source_id: usize) {
+    let (Some(source), Some(health)) = (
+        state.sources.get(source_id),
+        state.source_health.get(source_id),
+    ) else {
+        return;
+    };
+    let area = frame.area();
+    let now = Instant::now();
+
+    let width = 80.min(area.width.saturating_sub(4));
+    let height = 24.min(area.height.saturating_sub(4));
+    let x = (area.width - width) / 2;
+    let y = (area.height - height) / 2;
+    let detail_area = Rect::new(x, y, width, height);
+
+    frame.render_widget(Clear, detail_area);
+
+    let status = health.status(now);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut text = vec![
+        Line::from(Span::styled(source.name(), bold)),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("State:      "),
+            Span::styled(
+                match status {
+                    SourceStatus::Reconnecting(attempt) => {
+                        format!("reconnecting (attempt {})", attempt)
+                    }
+                    _ => status.label().to_string(),
+                },
+                Style::default().fg(source_status_color(&state.theme, status)),
+            ),
+            Span::raw(format!(" for {}", format_age(health.status_age(now)))),
+        ]),
+        Line::from(format!(
+            "Lines:      {} ({}/s)",
+            health.line_count,
+            format_rate(health.rate(now))
+        )),
+        Line::from(format!(
+            "Last line:  {}",
+            health
+                .last_line_age(now)
+                .map(|age| format!("{} ago", format_age(age)))
+                .unwrap_or_else(|| "never".to_string())
+        )),
+        Line::from(""),
+        Line::from(Span::styled("Recent errors (newest first):", bold)),
+    ];
+
+    let mut errors = health.errors().peekable();
+    if errors.peek().is_none() {
+        text.push(Line::styled(
+            "  none",
+            Style::default().fg(state.theme.empty_state),
+        ));
+    }
+    for error in errors {
+        text.push(Line::from(vec![
+            Span::styled(
+                format!("  {} ", error.at.format("%H:%M:%S")),
+                Style::default().fg(state.theme.timestamp),
+            ),
+            Span::styled(
+                error.message.clone(),
+                Style::default().fg(state.theme.level_error),
+            ),
+        ]));
+    }
+
+    let block = Block::default()
+        .title(" Source Details (Esc:close) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(state.theme.help_border))
+        .style(Style::default().bg(state.theme.help_bg));
+
+    let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, detail_area);
+}
+
 /// Draw the settings overlay
 fn draw_settings_overlay(frame: &mut Frame, state: &AppState) {
     let area = frame.area();
@@ -1082,6 +1847,61 @@ fn draw_settings_overlay(frame: &mut Frame, state: &AppState) {
     frame.render_widget(paragraph, settings_area);
 }
 
+/// Draw the table column picker overlay
+fn draw_column_picker_overlay(frame: &mut Frame, state: &AppState) {
+    let area = frame.area();
+    let picker = &state.column_picker;
+
+    let width = 44.min(area.width.saturating_sub(4));
+    let height = (picker.keys.len() as u16 + 5)
+        .min(area.height.saturating_sub(4))
+        .max(6);
+    let x = area.width.saturating_sub(width) / 2;
+    let y = area.height.saturating_sub(height) / 2;
+    let picker_area = Rect::new(x, y, width, height);
+
+    frame.render_widget(Clear, picker_area);
+
+    // Keep the highlighted key in view
+    let list_height = (height as usize).saturating_sub(5).max(1);
+    let start = picker.selected.saturating_sub(list_height - 1);
+
+    let mut lines: Vec<Line> = vec![Line::from("")];
+    for (i, key) in picker.keys.iter().enumerate().skip(start).take(list_height) {
+        let position = picker.chosen.iter().position(|c| c == key);
+        let mark = match position {
+            Some(pos) => format!("[{}]", pos + 1),
+            None => "[ ]".to_string(),
+        };
+        let prefix = if i == picker.selected { "> " } else { "  " };
+        let style = if i == picker.selected {
+            Style::default()
+                .fg(state.theme.filter_selected)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{}{:<4} {}", prefix, mark, key),
+            style,
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        " Space:add/remove  Enter:apply  Esc:cancel",
+        Style::default().fg(state.theme.status_help),
+    )));
+
+    let block = Block::default()
+        .title(" Table Columns ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(state.theme.help_border))
+        .style(Style::default().bg(state.theme.help_bg));
+
+    frame.render_widget(Paragraph::new(lines).block(block), picker_area);
+}
+
 /// Draw the source picker overlay
 fn draw_picker_overlay(frame: &mut Frame, state: &AppState) {
     let area = frame.area();
@@ -1098,9 +1918,17 @@ fn draw_picker_overlay(frame: &mut Frame, state: &AppState) {
     // Clear background
     frame.render_widget(Clear, picker_area);
 
-    let title = match picker.mode {
-        PickerMode::Docker => " Docker Containers ",
-        PickerMode::K8s => " Kubernetes Pods ",
+    let title = match (picker.mode, &picker.scope_chooser) {
+        (PickerMode::Docker, _) => " Docker Containers ".to_string(),
+        (PickerMode::K8s, Some(chooser)) => match chooser.kind {
+            ScopeKind::Context => " Kubernetes Contexts ".to_string(),
+            ScopeKind::Namespace => " Kubernetes Namespaces ".to_string(),
+        },
+        (PickerMode::K8s, None) => format!(
+            " Kubernetes Pods - {} / {} ",
+            picker.k8s_context.as_deref().unwrap_or("current context"),
+            picker.k8s_namespace.as_deref().unwrap_or("all namespaces"),
+        ),
     };
 
     let block = Block::default()
@@ -1112,6 +1940,11 @@ fn draw_picker_overlay(frame: &mut Frame, state: &AppState) {
     let inner = block.inner(picker_area);
     frame.render_widget(block, picker_area);
 
+    if let Some(chooser) = &picker.scope_chooser {
+        draw_scope_chooser(frame, chooser, theme, inner);
+        return;
+    }
+
     // Handle loading state
     if picker.loading {
         let loading = Paragraph::new("  Loading...").style(Style::default().fg(theme.empty_state));
@@ -1201,7 +2034,7 @@ fn draw_picker_overlay(frame: &mut Frame, state: &AppState) {
     frame.render_widget(list, chunks[0]);
 
     // Draw help text
-    let help = Paragraph::new(Line::from(vec![
+    let mut help = vec![
         Span::styled("j/k", Style::default().add_modifier(Modifier::BOLD)),
         Span::raw(":nav  "),
         Span::styled("Space", Style::default().add_modifier(Modifier::BOLD)),
@@ -1210,6 +2043,91 @@ fn draw_picker_overlay(frame: &mut Frame, state: &AppState) {
         Span::raw(":add  "),
         Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
         Span::raw(":cancel"),
+    ];
+    if picker.mode == PickerMode::K8s {
+        help.extend([
+            Span::raw("  "),
+            Span::styled("c", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(":context  "),
+            Span::styled("n", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(":namespace"),
+        ]);
+    }
+    // The K8s keys wrap onto the second line
+    let help = Paragraph::new(Line::from(help))
+        .style(Style::default().fg(theme.status_help))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(help, chunks[1]);
+}
+
+/// Draw the K8s picker's context or namespace list in place of the pods
+fn draw_scope_chooser(frame: &mut Frame, chooser: &ScopeChooser, theme: &Theme, inner: Rect) {
+    if chooser.loading {
+        let loading = Paragraph::new("  Loading...").style(Style::default().fg(theme.empty_state));
+        frame.render_widget(loading, inner);
+        return;
+    }
+    if let Some(ref error) = chooser.error {
+        let err_lines = vec![
+            Line::from(Span::styled(
+                "Error:",
+                Style::default()
+                    .fg(theme.level_error)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(error.as_str()),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press Esc to go back",
+                Style::default().fg(theme.empty_state),
+            )),
+        ];
+        frame.render_widget(Paragraph::new(err_lines), inner);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(2)])
+        .split(inner);
+
+    // Keep the selection in view
+    let list_height = chunks[0].height as usize;
+    let start = chooser
+        .selected
+        .saturating_sub(list_height.saturating_sub(1));
+    let items: Vec<ListItem> = chooser
+        .choices
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(list_height)
+        .map(|(i, choice)| {
+            let prefix = if i == chooser.selected { ">" } else { " " };
+            let marker = if chooser.current == Some(i) { "*" } else { " " };
+            let name = choice.as_deref().unwrap_or("(all namespaces)");
+            let style = if i == chooser.selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else if chooser.current == Some(i) {
+                Style::default().fg(theme.filter_selected)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} {} ", prefix, marker)),
+                Span::styled(name.to_string(), style),
+            ]))
+        })
+        .collect();
+    frame.render_widget(List::new(items), chunks[0]);
+
+    let help = Paragraph::new(Line::from(vec![
+        Span::styled("j/k", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(":nav  "),
+        Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(":choose  "),
+        Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(":back"),
     ]))
     .style(Style::default().fg(theme.status_help));
     frame.render_widget(help, chunks[1]);